@@ -258,10 +258,26 @@ impl Storage for MockStorage {
         unimplemented!()
     }
 
+    async fn try_get_transaction_by_hash(
+        &self,
+        _: Hash,
+    ) -> ProtocolResult<Option<SignedTransaction>> {
+        unimplemented!()
+    }
+
     async fn get_transactions(&self, _: Vec<Hash>) -> ProtocolResult<Vec<SignedTransaction>> {
         unimplemented!()
     }
 
+    async fn get_transactions_by_address(
+        &self,
+        _: Address,
+        _: u64,
+        _: u64,
+    ) -> ProtocolResult<Vec<SignedTransaction>> {
+        unimplemented!()
+    }
+
     async fn get_latest_block(&self) -> ProtocolResult<Block> {
         unimplemented!()
     }
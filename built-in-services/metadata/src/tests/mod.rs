@@ -9,8 +9,8 @@ use framework::binding::sdk::{DefalutServiceSDK, DefaultChainQuerier};
 use framework::binding::state::{GeneralServiceState, MPTTrie};
 use protocol::traits::{NoopDispatcher, ServiceSDK, Storage};
 use protocol::types::{
-    Address, Block, Hash, Hex, Metadata, Proof, Receipt, ServiceContext, ServiceContextParams,
-    SignedTransaction, ValidatorExtend, METADATA_KEY,
+    Address, Admission, Block, Hash, Hex, Metadata, Proof, Receipt, ServiceContext,
+    ServiceContextParams, SignedTransaction, ValidatorExtend, METADATA_KEY,
 };
 use protocol::{types::Bytes, ProtocolResult};
 
@@ -75,6 +75,9 @@ fn mock_metadata() -> Metadata {
         brake_ratio:     7,
         tx_num_limit: 20000,
         max_tx_size: 1_073_741_824,
+        fee_asset_id: None,
+        fee_treasury: None,
+        admission: Admission::default(),
     }
 }
 
@@ -122,10 +125,26 @@ impl Storage for MockStorage {
         unimplemented!()
     }
 
+    async fn try_get_transaction_by_hash(
+        &self,
+        _: Hash,
+    ) -> ProtocolResult<Option<SignedTransaction>> {
+        unimplemented!()
+    }
+
     async fn get_transactions(&self, _: Vec<Hash>) -> ProtocolResult<Vec<SignedTransaction>> {
         unimplemented!()
     }
 
+    async fn get_transactions_by_address(
+        &self,
+        _: Address,
+        _: u64,
+        _: u64,
+    ) -> ProtocolResult<Vec<SignedTransaction>> {
+        unimplemented!()
+    }
+
     async fn get_latest_block(&self) -> ProtocolResult<Block> {
         unimplemented!()
     }
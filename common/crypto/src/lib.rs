@@ -4,6 +4,7 @@ pub use ophelia::{
     ToPublicKey,
 };
 pub use ophelia_bls_amcl::{BlsCommonReference, BlsPrivateKey, BlsPublicKey, BlsSignature};
+pub use ophelia_ed25519::{Ed25519, Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature};
 pub use ophelia_secp256k1::{
     Secp256k1, Secp256k1PrivateKey, Secp256k1PublicKey, Secp256k1Signature,
 };
@@ -2,11 +2,7 @@ use static_merkle_tree::Tree;
 
 use protocol::{types::Hash, Bytes};
 
-#[derive(Debug, Clone)]
-pub struct ProofNode {
-    pub is_right: bool,
-    pub hash:     Hash,
-}
+pub use protocol::types::MerkleProofNode as ProofNode;
 
 pub struct Merkle {
     tree: Tree<Hash>,
@@ -39,6 +35,20 @@ impl Merkle {
                     .collect()
             })
     }
+
+    /// Verify that the leaf is included in the tree of the root, with the
+    /// proof returned by `get_proof_by_input_index`.
+    pub fn verify_proof(root: &Hash, leaf: &Hash, proof: &[ProofNode]) -> bool {
+        let computed = proof.iter().fold(leaf.clone(), |hash, node| {
+            if node.is_right {
+                merge(&hash, &node.hash)
+            } else {
+                merge(&node.hash, &hash)
+            }
+        });
+
+        &computed == root
+    }
 }
 
 fn merge(left: &Hash, right: &Hash) -> Hash {
@@ -50,3 +60,44 @@ fn merge(left: &Hash, right: &Hash) -> Hash {
     root.extend_from_slice(&right);
     Hash::digest(Bytes::from(root))
 }
+
+#[cfg(test)]
+mod tests {
+    use protocol::{types::Hash, Bytes};
+
+    use super::Merkle;
+
+    fn mock_hashes(len: usize) -> Vec<Hash> {
+        (0..len)
+            .map(|i| Hash::digest(Bytes::from(i.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn test_verify_proof() {
+        for len in 1..10 {
+            let hashes = mock_hashes(len);
+            let merkle = Merkle::from_hashes(hashes.clone());
+            let root = merkle.get_root_hash().unwrap();
+
+            for (index, leaf) in hashes.iter().enumerate() {
+                let proof = merkle.get_proof_by_input_index(index).unwrap();
+                assert!(Merkle::verify_proof(&root, leaf, &proof));
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_wrong_proof() {
+        let hashes = mock_hashes(5);
+        let merkle = Merkle::from_hashes(hashes.clone());
+        let root = merkle.get_root_hash().unwrap();
+
+        let proof = merkle.get_proof_by_input_index(1).unwrap();
+        assert!(!Merkle::verify_proof(&root, &hashes[2], &proof));
+
+        let mut proof = merkle.get_proof_by_input_index(2).unwrap();
+        proof[0].is_right = !proof[0].is_right;
+        assert!(!Merkle::verify_proof(&root, &hashes[2], &proof));
+    }
+}
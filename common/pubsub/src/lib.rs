@@ -1,7 +1,105 @@
+use futures::channel::mpsc::{channel, Receiver, Sender};
+use parking_lot::Mutex;
+
+use protocol::types::{Block, Receipt};
+
+// Buffered messages per subscriber. A subscriber that falls this far behind
+// is considered dead and will be dropped on the next publish.
+pub const DEFAULT_SUBSCRIBER_BUFFER: usize = 1024;
+
+/// Events emitted along the commit path of the chain.
+#[derive(Clone, Debug)]
+pub enum ChainEvent {
+    /// A block has been committed and saved.
+    NewBlock(Block),
+    /// The receipts of a block have been executed and saved.
+    NewReceipts(Vec<Receipt>),
+}
+
+/// A fan-out channel: every message published is delivered to all the
+/// subscribers alive at that moment.
+pub struct PubSub<T> {
+    buffer:      usize,
+    subscribers: Mutex<Vec<Sender<T>>>,
+}
+
+impl<T: Clone> PubSub<T> {
+    pub fn new(buffer: usize) -> Self {
+        PubSub {
+            buffer,
+            subscribers: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn subscribe(&self) -> Receiver<T> {
+        let (tx, rx) = channel(self.buffer);
+        self.subscribers.lock().push(tx);
+        rx
+    }
+
+    // Publishing never blocks, subscribers which have been dropped or whose
+    // buffer is full are removed.
+    pub fn publish(&self, msg: T) {
+        let mut subscribers = self.subscribers.lock();
+        let alive = subscribers
+            .drain(..)
+            .filter_map(|mut sub| sub.try_send(msg.clone()).ok().map(|_| sub))
+            .collect();
+        *subscribers = alive;
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().len()
+    }
+}
+
+impl<T: Clone> Default for PubSub<T> {
+    fn default() -> Self {
+        PubSub::new(DEFAULT_SUBSCRIBER_BUFFER)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use futures::executor::block_on;
+    use futures::StreamExt;
+
+    use super::PubSub;
+
+    #[test]
+    fn test_publish_to_all_subscribers() {
+        let pubsub = PubSub::new(4);
+        let mut rx_1 = pubsub.subscribe();
+        let mut rx_2 = pubsub.subscribe();
+
+        pubsub.publish(1u64);
+        pubsub.publish(2u64);
+
+        assert_eq!(block_on(rx_1.next()), Some(1));
+        assert_eq!(block_on(rx_1.next()), Some(2));
+        assert_eq!(block_on(rx_2.next()), Some(1));
+        assert_eq!(block_on(rx_2.next()), Some(2));
+    }
+
+    #[test]
+    fn test_drop_closed_subscriber() {
+        let pubsub = PubSub::new(4);
+        let rx = pubsub.subscribe();
+        let _rx_alive = pubsub.subscribe();
+        drop(rx);
+
+        pubsub.publish(1u64);
+        assert_eq!(pubsub.subscriber_count(), 1);
+    }
+
     #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
+    fn test_drop_lagging_subscriber() {
+        let pubsub = PubSub::new(0);
+        let _rx = pubsub.subscribe();
+
+        // A zero-sized channel still has one slot per sender.
+        pubsub.publish(1u64);
+        pubsub.publish(2u64);
+        assert_eq!(pubsub.subscriber_count(), 0);
     }
 }
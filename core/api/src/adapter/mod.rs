@@ -1,15 +1,25 @@
+use std::cmp;
 use std::marker::PhantomData;
 use std::sync::Arc;
 
 use derive_more::Display;
 
 use async_trait::async_trait;
+use common_merkle::Merkle;
+use protocol::fixed_codec::FixedCodec;
 use protocol::traits::ExecutorFactory;
 use protocol::traits::{
-    APIAdapter, Context, ExecutorParams, MemPool, ServiceMapping, ServiceResponse, Storage,
+    APIAdapter, ChainInfo, Context, ExecutorParams, MemPool, NetworkStatus, ServiceMapping,
+    ServiceResponse, StateProof, Storage, Synchronization, TransactionStatus,
 };
-use protocol::types::{Address, Block, Hash, Receipt, SignedTransaction, TransactionRequest};
-use protocol::{ProtocolError, ProtocolErrorKind, ProtocolResult};
+use protocol::types::{
+    Address, Block, BloomInput, Hash, MerkleProof, MerkleRoot, Metadata, RawTransaction, Receipt,
+    SignedTransaction, TransactionRequest,
+};
+use protocol::{Bytes, ProtocolError, ProtocolErrorKind, ProtocolResult};
+
+// The max number of blocks scanned by a single events query.
+const MAX_EVENTS_QUERY_RANGE: u64 = 10_000;
 
 #[derive(Debug, Display)]
 pub enum APIError {
@@ -19,16 +29,56 @@ pub enum APIError {
         expect
     )]
     UnExecedError { expect: u64, real: u64 },
+
+    #[display(fmt = "Invalid height range from {:?} to {:?}", from, to)]
+    InvalidHeightRange { from: u64, to: u64 },
+
+    #[display(fmt = "Height range {:?} exceeds the limit {:?}", range, limit)]
+    ExceedRangeLimit { range: u64, limit: u64 },
+
+    #[display(fmt = "State root of height {:?} was not found", height)]
+    StateRootNotFound { height: u64 },
+
+    #[display(fmt = "Receipt root of height {:?} was not found", height)]
+    ReceiptRootNotFound { height: u64 },
+
+    #[display(
+        fmt = "State of height {:?} has been pruned, the lowest available is {:?}",
+        height,
+        lowest
+    )]
+    StatePruned { height: u64, lowest: u64 },
+
+    #[display(fmt = "Merkle proof of {:?} was not found", tx_hash)]
+    ProofNotFound { tx_hash: Hash },
+
+    #[display(fmt = "Decode metadata failed {:?}", _0)]
+    DecodeMetadata(serde_json::Error),
 }
 
 impl std::error::Error for APIError {}
 
+impl From<APIError> for ProtocolError {
+    fn from(err: APIError) -> ProtocolError {
+        ProtocolError::new(ProtocolErrorKind::API, Box::new(err))
+    }
+}
+
 pub struct DefaultAPIAdapter<EF, M, S, DB, Mapping> {
     mempool:         Arc<M>,
     storage:         Arc<S>,
     trie_db:         Arc<DB>,
     service_mapping: Arc<Mapping>,
 
+    // Runtime status of the node, which is set after the node is started.
+    self_address:    Option<Address>,
+    network:         Option<Arc<dyn NetworkStatus>>,
+    synchronization: Option<Arc<dyn Synchronization>>,
+
+    // Only the states of the latest blocks are kept if set, 0 means keeping
+    // all.
+    keep_states: u64,
+
     pin_ef: PhantomData<EF>,
 }
 
@@ -51,9 +101,151 @@ impl<
             storage,
             trie_db,
             service_mapping,
+            self_address: None,
+            network: None,
+            synchronization: None,
+            keep_states: 0,
             pin_ef: PhantomData,
         }
     }
+
+    pub fn with_keep_states(mut self, keep_states: u64) -> Self {
+        self.keep_states = keep_states;
+        self
+    }
+
+    pub fn set_node_status(
+        &mut self,
+        self_address: Address,
+        network: Arc<dyn NetworkStatus>,
+        synchronization: Arc<dyn Synchronization>,
+    ) {
+        self.self_address = Some(self_address);
+        self.network = Some(network);
+        self.synchronization = Some(synchronization);
+    }
+
+    // Resolve the block and the state root right after it was executed, or
+    // the latest block and state root if `height` is `None`.
+    async fn get_state_at(&self, height: Option<u64>) -> ProtocolResult<(Block, MerkleRoot)> {
+        match height {
+            Some(height) => {
+                self.check_state_pruned(height).await?;
+                let block = self.storage.get_block_by_height(height).await?;
+                let state_root = self.get_state_root(&block).await?;
+                Ok((block, state_root))
+            }
+            None => {
+                let block = self.storage.get_latest_block().await?;
+                let state_root = block.header.state_root.clone();
+                Ok((block, state_root))
+            }
+        }
+    }
+
+    async fn check_state_pruned(&self, height: u64) -> ProtocolResult<()> {
+        if self.keep_states == 0 {
+            return Ok(());
+        }
+
+        let latest_height = self.storage.get_latest_block().await?.header.height;
+        if height + self.keep_states <= latest_height {
+            return Err(APIError::StatePruned {
+                height,
+                lowest: latest_height + 1 - self.keep_states,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    // The logs blooms of a header belong to the blocks executed since the
+    // previous header, that is up to `exec_height` included.
+    async fn bloom_hit_heights(
+        &self,
+        from_height: u64,
+        to_height: u64,
+        event_hash: &Hash,
+    ) -> ProtocolResult<Vec<u64>> {
+        let latest_height = self.storage.get_latest_block().await?.header.height;
+        let input = event_hash.as_bytes();
+
+        let mut hit_heights = Vec::new();
+        for height in from_height..=latest_height {
+            let header = self.storage.get_block_by_height(height).await?.header;
+            let first_height = header.exec_height + 1 - header.logs_bloom.len() as u64;
+
+            for (offset, bloom) in header.logs_bloom.iter().enumerate() {
+                let exec_height = first_height + offset as u64;
+                if exec_height >= from_height
+                    && exec_height <= to_height
+                    && bloom.contains_input(BloomInput::Raw(input.as_ref()))
+                {
+                    hit_heights.push(exec_height);
+                }
+            }
+
+            if header.exec_height >= to_height {
+                break;
+            }
+        }
+
+        Ok(hit_heights)
+    }
+
+    // The header only records the state root of its exec height, which lags
+    // behind the block height. The state right after executing a block is
+    // found in its receipts, or in the header of the block which executed it.
+    async fn get_state_root(&self, block: &Block) -> ProtocolResult<MerkleRoot> {
+        let height = block.header.height;
+        let latest = self.storage.get_latest_block().await?;
+        if latest.header.exec_height < height {
+            return Err(APIError::UnExecedError {
+                real:   latest.header.exec_height,
+                expect: height,
+            }
+            .into());
+        }
+
+        if let Some(tx_hash) = block.ordered_tx_hashes.first() {
+            let receipt = self.storage.get_receipt(tx_hash.clone()).await?;
+            return Ok(receipt.state_root);
+        }
+
+        for h in height + 1..=latest.header.height {
+            let header = self.storage.get_block_by_height(h).await?.header;
+            if header.exec_height == height {
+                return Ok(header.state_root);
+            }
+            if header.exec_height > height {
+                break;
+            }
+        }
+
+        Err(APIError::StateRootNotFound { height }.into())
+    }
+
+    // Blocks are executed after being committed, so the receipt root of a
+    // height is recorded by the header of a later block.
+    async fn get_receipt_root(&self, height: u64) -> ProtocolResult<(u64, MerkleRoot)> {
+        let latest_height = self.storage.get_latest_block().await?.header.height;
+
+        for h in height + 1..=latest_height {
+            let header = self.storage.get_block_by_height(h).await?.header;
+            if header.exec_height < height {
+                continue;
+            }
+
+            let first_height = header.exec_height + 1 - header.receipt_root.len() as u64;
+            if first_height > height {
+                break;
+            }
+            let root = header.receipt_root[(height - first_height) as usize].clone();
+            return Ok((h, root));
+        }
+
+        Err(APIError::ReceiptRootNotFound { height }.into())
+    }
 }
 
 #[async_trait]
@@ -73,6 +265,14 @@ impl<
         self.mempool.insert(ctx, signed_tx).await
     }
 
+    async fn insert_batch_signed_txs(
+        &self,
+        ctx: Context,
+        signed_txs: Vec<SignedTransaction>,
+    ) -> Vec<ProtocolResult<()>> {
+        self.mempool.insert_batch(ctx, signed_txs).await
+    }
+
     async fn get_block_by_height(
         &self,
         _ctx: Context,
@@ -86,6 +286,10 @@ impl<
         Ok(block)
     }
 
+    async fn get_block_by_hash(&self, _ctx: Context, block_hash: Hash) -> ProtocolResult<Block> {
+        self.storage.get_block_by_hash(block_hash).await
+    }
+
     async fn get_receipt_by_tx_hash(
         &self,
         _ctx: Context,
@@ -114,10 +318,222 @@ impl<
         self.storage.get_transaction_by_hash(tx_hash).await
     }
 
-    async fn query_service(
+    async fn get_transactions_in_block(
         &self,
-        ctx: Context,
+        _ctx: Context,
+        height: u64,
+        offset: u64,
+        limit: u64,
+    ) -> ProtocolResult<Vec<SignedTransaction>> {
+        let block = self.storage.get_block_by_height(height).await?;
+        let tx_hashes = paginate(block.ordered_tx_hashes, offset, limit);
+
+        self.storage.get_transactions(tx_hashes).await
+    }
+
+    async fn get_receipts_in_block(
+        &self,
+        _ctx: Context,
         height: u64,
+        offset: u64,
+        limit: u64,
+    ) -> ProtocolResult<Vec<Receipt>> {
+        let exec_height = self.storage.get_latest_block().await?.header.exec_height;
+        if exec_height < height {
+            return Err(APIError::UnExecedError {
+                real:   exec_height,
+                expect: height,
+            }
+            .into());
+        }
+
+        let block = self.storage.get_block_by_height(height).await?;
+        let tx_hashes = paginate(block.ordered_tx_hashes, offset, limit);
+
+        self.storage.get_receipts(tx_hashes).await
+    }
+
+    async fn get_transactions_by_address(
+        &self,
+        _ctx: Context,
+        address: Address,
+        offset: u64,
+        limit: u64,
+    ) -> ProtocolResult<Vec<SignedTransaction>> {
+        self.storage
+            .get_transactions_by_address(address, offset, limit)
+            .await
+    }
+
+    async fn get_transaction_proof(
+        &self,
+        ctx: Context,
+        tx_hash: Hash,
+    ) -> ProtocolResult<MerkleProof> {
+        let receipt = self.get_receipt_by_tx_hash(ctx, tx_hash.clone()).await?;
+        let block = self.storage.get_block_by_height(receipt.height).await?;
+
+        merkle_proof(
+            block.header.height,
+            block.header.order_root,
+            block.ordered_tx_hashes,
+            tx_hash,
+        )
+    }
+
+    async fn get_receipt_proof(&self, ctx: Context, tx_hash: Hash) -> ProtocolResult<MerkleProof> {
+        let receipt = self.get_receipt_by_tx_hash(ctx, tx_hash).await?;
+        let block = self.storage.get_block_by_height(receipt.height).await?;
+        let (root_height, root) = self.get_receipt_root(receipt.height).await?;
+
+        let leaves = self
+            .storage
+            .get_receipts(block.ordered_tx_hashes)
+            .await?
+            .into_iter()
+            .map(|r| Ok(Hash::digest(r.encode_fixed()?)))
+            .collect::<ProtocolResult<Vec<_>>>()?;
+        let leaf = Hash::digest(receipt.encode_fixed()?);
+
+        merkle_proof(root_height, root, leaves, leaf)
+    }
+
+    async fn get_events(
+        &self,
+        _ctx: Context,
+        from_height: u64,
+        to_height: u64,
+        service: String,
+        topic: Option<String>,
+    ) -> ProtocolResult<Vec<Receipt>> {
+        if from_height > to_height {
+            return Err(APIError::InvalidHeightRange {
+                from: from_height,
+                to:   to_height,
+            }
+            .into());
+        }
+        if to_height - from_height >= MAX_EVENTS_QUERY_RANGE {
+            return Err(APIError::ExceedRangeLimit {
+                range: to_height - from_height + 1,
+                limit: MAX_EVENTS_QUERY_RANGE,
+            }
+            .into());
+        }
+
+        let latest_header = self.storage.get_latest_block().await?.header;
+        let to_height = cmp::min(to_height, latest_header.exec_height);
+        if from_height > to_height {
+            return Ok(Vec::new());
+        }
+
+        // Each event is accrued to the logs bloom by the hash of its service
+        // and data, so blocks are only skipped by the bloom if the topic is
+        // given. Otherwise every block in the range is read.
+        let hit_heights = match &topic {
+            Some(topic) => {
+                let event_hash = Hash::digest(Bytes::from(service.clone() + topic));
+                self.bloom_hit_heights(from_height, to_height, &event_hash)
+                    .await?
+            }
+            None => (from_height..=to_height).collect(),
+        };
+
+        let mut matched = Vec::new();
+        for height in hit_heights {
+            let block = self.storage.get_block_by_height(height).await?;
+            let receipts = self.storage.get_receipts(block.ordered_tx_hashes).await?;
+
+            for mut receipt in receipts {
+                receipt.events.retain(|event| {
+                    event.service == service && topic.as_ref().map_or(true, |t| &event.data == t)
+                });
+
+                if !receipt.events.is_empty() {
+                    matched.push(receipt);
+                }
+            }
+        }
+
+        Ok(matched)
+    }
+
+    async fn get_chain_info(&self, ctx: Context) -> ProtocolResult<ChainInfo> {
+        let latest_header = self.storage.get_latest_block().await?.header;
+        let latest_proof = self.storage.get_latest_proof().await?;
+
+        let caller = self.self_address.clone().unwrap_or_default();
+        let resp = self
+            .query_service(
+                ctx,
+                None,
+                std::u64::MAX,
+                1,
+                caller,
+                "metadata".to_owned(),
+                "get_metadata".to_owned(),
+                "".to_owned(),
+            )
+            .await?;
+        let metadata: Metadata =
+            serde_json::from_str(&resp.succeed_data).map_err(APIError::DecodeMetadata)?;
+
+        Ok(ChainInfo {
+            latest_height: latest_header.height,
+            exec_height: latest_header.exec_height,
+            latest_proof,
+            metadata,
+            peer_count: self
+                .network
+                .as_ref()
+                .map_or(0, |network| network.connected_peers() as u64),
+            mempool_size: self.mempool.tx_count() as u64,
+            is_syncing: self
+                .synchronization
+                .as_ref()
+                .map_or(false, |sync| sync.is_syncing()),
+            self_address: self.self_address.clone(),
+        })
+    }
+
+    async fn get_mempool_tx_count(&self, _ctx: Context) -> ProtocolResult<u64> {
+        Ok(self.mempool.tx_count() as u64)
+    }
+
+    async fn get_pending_tx_hashes(&self, _ctx: Context, limit: u64) -> ProtocolResult<Vec<Hash>> {
+        Ok(self.mempool.pending_tx_hashes(limit as usize))
+    }
+
+    async fn get_pending_transaction(
+        &self,
+        _ctx: Context,
+        tx_hash: Hash,
+    ) -> ProtocolResult<Option<SignedTransaction>> {
+        Ok(self.mempool.get_pending_tx(&tx_hash))
+    }
+
+    async fn get_transaction_status(
+        &self,
+        _ctx: Context,
+        tx_hash: Hash,
+    ) -> ProtocolResult<TransactionStatus> {
+        if self.mempool.get_pending_tx(&tx_hash).is_some() {
+            return Ok(TransactionStatus::Pending);
+        }
+        if self.mempool.get_proposed_tx(&tx_hash).is_some() {
+            return Ok(TransactionStatus::Proposed);
+        }
+
+        match self.storage.try_get_transaction_by_hash(tx_hash).await? {
+            Some(_) => Ok(TransactionStatus::Committed),
+            None => Ok(TransactionStatus::Unknown),
+        }
+    }
+
+    async fn query_service(
+        &self,
+        _ctx: Context,
+        height: Option<u64>,
         cycles_limit: u64,
         cycles_price: u64,
         caller: Address,
@@ -125,20 +541,21 @@ impl<
         method: String,
         payload: String,
     ) -> ProtocolResult<ServiceResponse<String>> {
-        let block = self.get_block_by_height(ctx.clone(), Some(height)).await?;
+        let (block, state_root) = self.get_state_at(height).await?;
 
         let executor = EF::from_root(
-            block.header.state_root.clone(),
+            state_root.clone(),
             Arc::clone(&self.trie_db),
             Arc::clone(&self.storage),
             Arc::clone(&self.service_mapping),
         )?;
 
         let params = ExecutorParams {
-            state_root: block.header.state_root,
-            height,
+            state_root,
+            height: block.header.height,
             timestamp: block.header.timestamp,
             cycles_limit,
+            proposer: block.header.proposer.clone(),
         };
         executor.read(&params, &caller, cycles_price, &TransactionRequest {
             service_name,
@@ -146,4 +563,93 @@ impl<
             payload,
         })
     }
+
+    async fn get_state_proof(
+        &self,
+        _ctx: Context,
+        height: Option<u64>,
+        service_name: String,
+        address: Option<Address>,
+        key: Bytes,
+    ) -> ProtocolResult<StateProof> {
+        let (block, state_root) = self.get_state_at(height).await?;
+
+        let executor = EF::from_root(
+            state_root.clone(),
+            Arc::clone(&self.trie_db),
+            Arc::clone(&self.storage),
+            Arc::clone(&self.service_mapping),
+        )?;
+
+        let params = ExecutorParams {
+            state_root,
+            height: block.header.height,
+            timestamp: block.header.timestamp,
+            cycles_limit: std::u64::MAX,
+            proposer: block.header.proposer.clone(),
+        };
+        executor.get_state_proof(&params, &service_name, address.as_ref(), key)
+    }
+
+    async fn simulate_transaction(
+        &self,
+        ctx: Context,
+        caller: Address,
+        raw: RawTransaction,
+    ) -> ProtocolResult<Receipt> {
+        let block = self.get_block_by_height(ctx, None).await?;
+        let tx_hash = Hash::digest(raw.encode_fixed()?);
+
+        let mut executor = EF::from_root(
+            block.header.state_root.clone(),
+            Arc::clone(&self.trie_db),
+            Arc::clone(&self.storage),
+            Arc::clone(&self.service_mapping),
+        )?;
+
+        // Run as if the transaction was packed into the next block.
+        let params = ExecutorParams {
+            state_root:   block.header.state_root,
+            height:       block.header.height + 1,
+            timestamp:    block.header.timestamp,
+            cycles_limit: raw.cycles_limit,
+            proposer:     block.header.proposer.clone(),
+        };
+        executor.simulate(&params, &caller, tx_hash, &raw)
+    }
+}
+
+fn merkle_proof(
+    height: u64,
+    root: MerkleRoot,
+    leaves: Vec<Hash>,
+    leaf: Hash,
+) -> ProtocolResult<MerkleProof> {
+    let not_found = || APIError::ProofNotFound {
+        tx_hash: leaf.clone(),
+    };
+
+    let index = leaves
+        .iter()
+        .position(|l| l == &leaf)
+        .ok_or_else(not_found)?;
+    let nodes = Merkle::from_hashes(leaves)
+        .get_proof_by_input_index(index)
+        .ok_or_else(not_found)?;
+
+    Ok(MerkleProof {
+        height,
+        root,
+        leaf: leaf.clone(),
+        index: index as u64,
+        nodes,
+    })
+}
+
+fn paginate<T>(items: Vec<T>, offset: u64, limit: u64) -> Vec<T> {
+    items
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect()
 }
@@ -0,0 +1,293 @@
+use std::collections::{HashSet, VecDeque};
+use std::error::Error;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix_web::http::header;
+use actix_web::HttpRequest;
+use derive_more::Display;
+
+use protocol::types::Hash;
+use protocol::Bytes;
+
+use crate::config::{ApiKey, Scope};
+
+// Block size of keccak256, used to pad the hmac key.
+const HMAC_BLOCK_SIZE: usize = 136;
+// The max difference in seconds between the hmac timestamp and local time.
+const HMAC_TIMESTAMP_WINDOW: u64 = 300;
+
+const ALL_SCOPES: [Scope; 3] = [Scope::Read, Scope::Send, Scope::Admin];
+
+/// What an authenticated request is allowed to do.
+pub struct Grant {
+    // Id of the api key, `None` if auth is disabled.
+    pub key_id: Option<String>,
+    pub scopes: Vec<Scope>,
+}
+
+pub struct Authenticator {
+    api_keys: Vec<ApiKey>,
+    seen:     Mutex<SeenSignatures>,
+}
+
+// Hmac signatures accepted recently, each one is kept for two timestamp
+// windows since it's seen, after which its timestamp is out of the window.
+#[derive(Default)]
+struct SeenSignatures {
+    signatures: HashSet<Vec<u8>>,
+    // Signatures with the time they expire, in the order of being seen.
+    expiry:     VecDeque<(u64, Vec<u8>)>,
+}
+
+impl SeenSignatures {
+    // Returns false if the signature has been seen.
+    fn insert(&mut self, signature: Vec<u8>, now: u64) -> bool {
+        while let Some((expire_at, _)) = self.expiry.front() {
+            if *expire_at >= now {
+                break;
+            }
+            if let Some((_, expired)) = self.expiry.pop_front() {
+                self.signatures.remove(&expired);
+            }
+        }
+
+        if !self.signatures.insert(signature.clone()) {
+            return false;
+        }
+        self.expiry
+            .push_back((now + HMAC_TIMESTAMP_WINDOW * 2, signature));
+        true
+    }
+}
+
+impl Authenticator {
+    pub fn new(api_keys: Vec<ApiKey>) -> Self {
+        Authenticator {
+            api_keys,
+            seen: Mutex::new(SeenSignatures::default()),
+        }
+    }
+
+    /// Returns the grant of the request. Every scope is granted if no api key
+    /// is configured.
+    ///
+    /// Two schemes of `Authorization` header are accepted:
+    /// `Bearer <secret>`, and `HMAC <id>:<timestamp>:<signature>` where the
+    /// signature is the hex of hmac-keccak256 over `<timestamp>\n<body>`.
+    /// A hmac signature is accepted only once, and only if the timestamp is
+    /// within the window.
+    pub fn authenticate(&self, req: &HttpRequest, body: &[u8]) -> Result<Grant, AuthError> {
+        if self.api_keys.is_empty() {
+            return Ok(Grant {
+                key_id: None,
+                scopes: ALL_SCOPES.to_vec(),
+            });
+        }
+
+        let credential = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .ok_or(AuthError::MissingCredential)?
+            .to_str()
+            .map_err(|_| AuthError::InvalidCredential)?;
+
+        let mut parts = credential.splitn(2, ' ');
+        let scheme = parts.next().unwrap_or_default();
+        let credential = parts.next().ok_or(AuthError::InvalidCredential)?.trim();
+
+        let api_key = match scheme {
+            "Bearer" => self.verify_bearer(credential)?,
+            "HMAC" => self.verify_hmac(credential, body, unix_now())?,
+            _ => return Err(AuthError::UnsupportedScheme(scheme.to_owned())),
+        };
+
+        Ok(Grant {
+            key_id: Some(api_key.id.clone()),
+            scopes: api_key.scopes.clone(),
+        })
+    }
+
+    fn verify_bearer(&self, secret: &str) -> Result<&ApiKey, AuthError> {
+        self.api_keys
+            .iter()
+            .find(|key| constant_time_eq(key.secret.as_bytes(), secret.as_bytes()))
+            .ok_or(AuthError::UnknownKey)
+    }
+
+    fn verify_hmac(&self, credential: &str, body: &[u8], now: u64) -> Result<&ApiKey, AuthError> {
+        let parts = credential.split(':').collect::<Vec<_>>();
+        if parts.len() != 3 {
+            return Err(AuthError::InvalidCredential);
+        }
+        let (id, timestamp, signature) = (parts[0], parts[1], parts[2]);
+
+        let api_key = self
+            .api_keys
+            .iter()
+            .find(|key| key.id == id)
+            .ok_or(AuthError::UnknownKey)?;
+
+        let ts = timestamp
+            .parse::<u64>()
+            .map_err(|_| AuthError::InvalidCredential)?;
+        let diff = if ts > now { ts - now } else { now - ts };
+        if diff > HMAC_TIMESTAMP_WINDOW {
+            return Err(AuthError::ExpiredTimestamp(ts));
+        }
+
+        let signature = hex::decode(signature).map_err(|_| AuthError::InvalidCredential)?;
+        let expected = hmac_keccak256(api_key.secret.as_bytes(), &signed_message(timestamp, body));
+        if !constant_time_eq(expected.as_bytes().as_ref(), &signature) {
+            return Err(AuthError::InvalidSignature);
+        }
+
+        let mut seen = self.seen.lock().expect("auth lock poisoned");
+        if !seen.insert(signature, now) {
+            return Err(AuthError::ReplayedSignature);
+        }
+
+        Ok(api_key)
+    }
+}
+
+/// Fails if the scope is not granted to the request.
+pub fn require_scope(scopes: &[Scope], scope: Scope) -> Result<(), AuthError> {
+    if scopes.contains(&scope) {
+        Ok(())
+    } else {
+        Err(AuthError::MissingScope(scope))
+    }
+}
+
+fn signed_message(timestamp: &str, body: &[u8]) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(timestamp.len() + 1 + body.len());
+    msg.extend_from_slice(timestamp.as_bytes());
+    msg.push(b'\n');
+    msg.extend_from_slice(body);
+    msg
+}
+
+fn hmac_keccak256(key: &[u8], msg: &[u8]) -> Hash {
+    let mut block_key = [0u8; HMAC_BLOCK_SIZE];
+    if key.len() > HMAC_BLOCK_SIZE {
+        let hashed = Hash::digest(Bytes::from(key.to_vec()));
+        block_key[..hashed.as_bytes().len()].copy_from_slice(hashed.as_bytes().as_ref());
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = block_key.iter().map(|b| b ^ 0x36).collect::<Vec<_>>();
+    inner.extend_from_slice(msg);
+    let inner_hash = Hash::digest(Bytes::from(inner));
+
+    let mut outer = block_key.iter().map(|b| b ^ 0x5c).collect::<Vec<_>>();
+    outer.extend_from_slice(inner_hash.as_bytes().as_ref());
+    Hash::digest(Bytes::from(outer))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Display)]
+pub enum AuthError {
+    #[display(fmt = "missing authorization header")]
+    MissingCredential,
+
+    #[display(fmt = "malformed authorization header")]
+    InvalidCredential,
+
+    #[display(fmt = "unsupported authorization scheme {:?}", _0)]
+    UnsupportedScheme(String),
+
+    #[display(fmt = "unknown api key")]
+    UnknownKey,
+
+    #[display(fmt = "timestamp {} is out of the allowed window", _0)]
+    ExpiredTimestamp(u64),
+
+    #[display(fmt = "invalid hmac signature")]
+    InvalidSignature,
+
+    #[display(fmt = "hmac signature has been used")]
+    ReplayedSignature,
+
+    #[display(fmt = "api key has no {:?} scope", _0)]
+    MissingScope(Scope),
+
+    #[display(fmt = "{} is disabled", _0)]
+    Disabled(&'static str),
+}
+
+impl Error for AuthError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authenticator() -> Authenticator {
+        Authenticator::new(vec![ApiKey {
+            id:     "reader".to_owned(),
+            secret: "secret".to_owned(),
+            scopes: vec![Scope::Read],
+        }])
+    }
+
+    fn sign(secret: &str, timestamp: &str, body: &[u8]) -> String {
+        let sig = hmac_keccak256(secret.as_bytes(), &signed_message(timestamp, body));
+        hex::encode(sig.as_bytes())
+    }
+
+    #[test]
+    fn test_verify_bearer() {
+        let auth = authenticator();
+
+        assert_eq!(auth.verify_bearer("secret").unwrap().id, "reader");
+        assert!(auth.verify_bearer("secreT").is_err());
+        assert!(auth.verify_bearer("").is_err());
+    }
+
+    #[test]
+    fn test_verify_hmac() {
+        let auth = authenticator();
+        let body = b"{\"query\":\"{ getBlock { header { height } } }\"}";
+        let now = 1_500_000_000;
+
+        let credential = format!("reader:{}:{}", now, sign("secret", &now.to_string(), body));
+        assert!(auth.verify_hmac(&credential, body, now).is_ok());
+        // replayed
+        assert!(auth.verify_hmac(&credential, body, now + 60).is_err());
+
+        let ts = now + 1;
+        let credential = format!("reader:{}:{}", ts, sign("secret", &ts.to_string(), body));
+        assert!(auth.verify_hmac(&credential, body, now + 60).is_ok());
+
+        // tampered body
+        assert!(auth.verify_hmac(&credential, b"{}", now).is_err());
+        // stale timestamp
+        assert!(auth
+            .verify_hmac(&credential, body, now + HMAC_TIMESTAMP_WINDOW + 2)
+            .is_err());
+        // wrong secret
+        let credential = format!("reader:{}:{}", now, sign("other", &now.to_string(), body));
+        assert!(auth.verify_hmac(&credential, body, now).is_err());
+        // unknown key id
+        let credential = format!("writer:{}:{}", now, sign("secret", &now.to_string(), body));
+        assert!(auth.verify_hmac(&credential, body, now).is_err());
+    }
+}
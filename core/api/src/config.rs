@@ -1,5 +1,8 @@
+use std::fmt;
 use std::net::SocketAddr;
 
+use serde::Deserialize;
+
 #[derive(Debug, Clone)]
 pub struct GraphQLConfig {
     pub listening_address: SocketAddr,
@@ -7,6 +10,12 @@ pub struct GraphQLConfig {
     pub graphql_uri:  String,
     pub graphiql_uri: String,
 
+    // The WebSocket endpoint of graphql subscriptions.
+    pub subscription_uri: String,
+
+    // The JSON-RPC 2.0 endpoint, it is disabled if not set.
+    pub jsonrpc_uri: Option<String>,
+
     // Set number of workers to start.
     // By default http server uses number of available logical cpu as threads count.
     pub workers: usize,
@@ -18,6 +27,31 @@ pub struct GraphQLConfig {
     // It is used to prevent DOS attacking through memory exhaustion.
     // The default value is 1024 * 1024, which is 1MB.
     pub max_payload_size: usize,
+
+    // Clients must authenticate with one of the keys if any is set, otherwise
+    // the api is open to everyone.
+    pub api_keys: Vec<ApiKey>,
+
+    // Serve the GraphiQL page.
+    pub enable_graphiql: bool,
+
+    // Serve the `unsafeSendTransaction` mutation, which signs with a private
+    // key sent by the client.
+    pub enable_unsafe_api: bool,
+
+    // Requests per second of each client, which is the api key if auth is
+    // enabled, otherwise the ip address. It is disabled if set to 0.
+    pub rate_limit: u64,
+
+    // Max requests a client can send at once, it is at least `rate_limit`.
+    pub rate_limit_burst: u64,
+
+    // Max levels of nested fields in a graphql query, 0 means no limit.
+    pub max_query_depth: usize,
+
+    // Max number of fields in a graphql query with fragments expanded,
+    // 0 means no limit.
+    pub max_query_complexity: usize,
 }
 
 impl Default for GraphQLConfig {
@@ -27,11 +61,48 @@ impl Default for GraphQLConfig {
                 .parse()
                 .expect("Unable to parse socket address"),
 
-            graphql_uri:      "/graphql".to_owned(),
-            graphiql_uri:     "/graphiql".to_owned(),
-            workers:          num_cpus::get(),
-            maxconn:          25000,
-            max_payload_size: 1024 * 1024, // 1MB
+            graphql_uri:          "/graphql".to_owned(),
+            graphiql_uri:         "/graphiql".to_owned(),
+            subscription_uri:     "/graphql/subscriptions".to_owned(),
+            jsonrpc_uri:          None,
+            workers:              num_cpus::get(),
+            maxconn:              25000,
+            max_payload_size:     1024 * 1024, // 1MB
+            api_keys:             vec![],
+            enable_graphiql:      false,
+            enable_unsafe_api:    false,
+            rate_limit:           0,
+            rate_limit_burst:     0,
+            max_query_depth:      15,
+            max_query_complexity: 500,
         }
     }
 }
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    // Queries and subscriptions.
+    Read,
+    // `sendTransaction` and `sendTransactions`.
+    Send,
+    // Unsafe and admin operations.
+    Admin,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ApiKey {
+    pub id:     String,
+    pub secret: String,
+    pub scopes: Vec<Scope>,
+}
+
+// Keep the secret out of logs.
+impl fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ApiKey")
+            .field("id", &self.id)
+            .field("scopes", &self.scopes)
+            .finish()
+    }
+}
@@ -0,0 +1,286 @@
+use std::cmp;
+
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use protocol::traits::{APIAdapter, Context};
+use protocol::ProtocolError;
+
+use crate::auth::require_scope;
+use crate::config::Scope;
+use crate::schema::{
+    to_signed_transaction, to_transaction, Address, Block, Hash, InputRawTransaction,
+    InputTransactionEncryption, Receipt, ServiceResponse, SignedTransaction, Uint64,
+};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+const UNAUTHORIZED: i64 = -32001;
+const RATE_LIMITED: i64 = -32005;
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    method:  String,
+    #[serde(default)]
+    params:  Value,
+    id:      Option<Value>,
+}
+
+#[derive(Serialize)]
+struct RpcError {
+    code:    i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: String) -> Self {
+        RpcError { code, message }
+    }
+}
+
+impl From<ProtocolError> for RpcError {
+    fn from(err: ProtocolError) -> Self {
+        RpcError::new(SERVER_ERROR, err.to_string())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SendTransactionParams {
+    input_raw:        InputRawTransaction,
+    input_encryption: InputTransactionEncryption,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DryRunTransactionParams {
+    input_raw: InputRawTransaction,
+    caller:    Address,
+}
+
+#[derive(Deserialize)]
+struct GetBlockParams {
+    height: Option<Uint64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TxHashParams {
+    tx_hash: Hash,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryServiceParams {
+    height:       Option<Uint64>,
+    cycles_limit: Option<Uint64>,
+    cycles_price: Option<Uint64>,
+    caller:       Address,
+    service_name: String,
+    method:       String,
+    payload:      String,
+}
+
+/// Parse a JSON-RPC 2.0 request body, or returns the error response.
+pub fn parse(body: &[u8]) -> Result<Value, Value> {
+    serde_json::from_slice(body)
+        .map_err(|e| error_response(Value::Null, PARSE_ERROR, e.to_string()))
+}
+
+/// Number of calls in the request, a batch costs as much as its calls.
+pub fn call_count(request: &Value) -> u64 {
+    match request {
+        Value::Array(calls) => cmp::max(calls.len() as u64, 1),
+        _ => 1,
+    }
+}
+
+/// Handle a JSON-RPC 2.0 request, either a single call or a batch.
+/// Params are passed by name, the same as the arguments of the graphql api.
+/// Returns `None` if there is nothing to respond, i.e. only notifications.
+pub async fn handle(adapter: &dyn APIAdapter, scopes: &[Scope], request: Value) -> Option<Value> {
+    match request {
+        Value::Array(calls) => {
+            if calls.is_empty() {
+                return Some(error_response(
+                    Value::Null,
+                    INVALID_REQUEST,
+                    "empty batch".to_owned(),
+                ));
+            }
+
+            let resps = join_all(
+                calls
+                    .into_iter()
+                    .map(|call| handle_call(adapter, scopes, call)),
+            )
+            .await
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+
+            if resps.is_empty() {
+                None
+            } else {
+                Some(Value::Array(resps))
+            }
+        }
+        call => handle_call(adapter, scopes, call).await,
+    }
+}
+
+async fn handle_call(adapter: &dyn APIAdapter, scopes: &[Scope], call: Value) -> Option<Value> {
+    let req: Request = match serde_json::from_value(call) {
+        Ok(req) => req,
+        Err(e) => return Some(error_response(Value::Null, INVALID_REQUEST, e.to_string())),
+    };
+
+    if req.jsonrpc != "2.0" {
+        return Some(error_response(
+            req.id.unwrap_or(Value::Null),
+            INVALID_REQUEST,
+            "jsonrpc must be \"2.0\"".to_owned(),
+        ));
+    }
+
+    let result = dispatch(adapter, scopes, &req.method, req.params).await;
+
+    // A request without id is a notification, which is never answered.
+    let id = req.id?;
+    let resp = match result {
+        Ok(result) => json!({"jsonrpc": "2.0", "result": result, "id": id}),
+        Err(err) => json!({"jsonrpc": "2.0", "error": err, "id": id}),
+    };
+    Some(resp)
+}
+
+async fn dispatch(
+    adapter: &dyn APIAdapter,
+    scopes: &[Scope],
+    method: &str,
+    params: Value,
+) -> Result<Value, RpcError> {
+    match method {
+        "sendTransaction" => {
+            require_scope(scopes, Scope::Send)
+                .map_err(|e| RpcError::new(UNAUTHORIZED, e.to_string()))?;
+
+            let params: SendTransactionParams = parse_params(params)?;
+            let stx = to_signed_transaction(params.input_raw, params.input_encryption)
+                .map_err(invalid_params)?;
+            let tx_hash = stx.tx_hash.clone();
+
+            adapter.insert_signed_txs(Context::new(), stx).await?;
+            to_result(Hash::from(tx_hash))
+        }
+        "getBlock" => {
+            let params: GetBlockParams = parse_params(params)?;
+            let height = match params.height {
+                Some(height) => Some(height.try_into_u64().map_err(invalid_params)?),
+                None => None,
+            };
+
+            let block = adapter.get_block_by_height(Context::new(), height).await?;
+            to_result(Block::from(block))
+        }
+        "getTransaction" => {
+            let params: TxHashParams = parse_params(params)?;
+            let tx_hash = to_hash(&params.tx_hash)?;
+
+            let stx = adapter
+                .get_transaction_by_hash(Context::new(), tx_hash)
+                .await?;
+            to_result(SignedTransaction::from(stx))
+        }
+        "getReceipt" => {
+            let params: TxHashParams = parse_params(params)?;
+            let tx_hash = to_hash(&params.tx_hash)?;
+
+            let receipt = adapter
+                .get_receipt_by_tx_hash(Context::new(), tx_hash)
+                .await?;
+            to_result(Receipt::from(receipt))
+        }
+        "queryService" => {
+            let params: QueryServiceParams = parse_params(params)?;
+            let height = match params.height {
+                Some(height) => Some(height.try_into_u64().map_err(invalid_params)?),
+                None => None,
+            };
+            let cycles_limit = match params.cycles_limit {
+                Some(cycles_limit) => cycles_limit.try_into_u64().map_err(invalid_params)?,
+                None => std::u64::MAX,
+            };
+            let cycles_price = match params.cycles_price {
+                Some(cycles_price) => cycles_price.try_into_u64().map_err(invalid_params)?,
+                None => 1,
+            };
+            let caller = protocol::types::Address::from_hex(&params.caller.as_hex())
+                .map_err(invalid_params)?;
+
+            let resp = adapter
+                .query_service(
+                    Context::new(),
+                    height,
+                    cycles_limit,
+                    cycles_price,
+                    caller,
+                    params.service_name,
+                    params.method,
+                    params.payload,
+                )
+                .await?;
+            to_result(ServiceResponse::from(resp))
+        }
+        "dryRunTransaction" => {
+            let params: DryRunTransactionParams = parse_params(params)?;
+            let raw = to_transaction(params.input_raw).map_err(invalid_params)?;
+            let caller = protocol::types::Address::from_hex(&params.caller.as_hex())
+                .map_err(invalid_params)?;
+
+            let receipt = adapter
+                .simulate_transaction(Context::new(), caller, raw)
+                .await?;
+            to_result(Receipt::from(receipt))
+        }
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("method {} not found", method),
+        )),
+    }
+}
+
+fn parse_params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+    // Omitted params are treated as an empty object.
+    let params = match params {
+        Value::Null => json!({}),
+        params => params,
+    };
+
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn to_hash(hash: &Hash) -> Result<protocol::types::Hash, RpcError> {
+    protocol::types::Hash::from_hex(&hash.as_hex()).map_err(invalid_params)
+}
+
+fn to_result<T: Serialize>(result: T) -> Result<Value, RpcError> {
+    serde_json::to_value(result).map_err(|e| RpcError::new(SERVER_ERROR, e.to_string()))
+}
+
+fn invalid_params(err: ProtocolError) -> RpcError {
+    RpcError::new(INVALID_PARAMS, err.to_string())
+}
+
+pub fn rate_limited_response(message: String) -> Value {
+    error_response(Value::Null, RATE_LIMITED, message)
+}
+
+fn error_response(id: Value, code: i64, message: String) -> Value {
+    json!({"jsonrpc": "2.0", "error": RpcError::new(code, message), "id": id})
+}
@@ -1,28 +1,55 @@
 pub mod adapter;
+mod auth;
 pub mod config;
+mod jsonrpc;
+mod limit;
 mod schema;
-
-use actix_web::{web, App, Error, FromRequest, HttpResponse, HttpServer};
-use futures::executor::block_on;
+mod subscription;
+
+use actix_web::http::StatusCode;
+use actix_web::{web, App, Error, FromRequest, HttpRequest, HttpResponse, HttpServer};
+use actix_web_actors::ws;
+use futures::future::{self, Either};
+use futures::StreamExt;
+use futures_timer::Delay;
 use juniper::http::GraphQLRequest;
-use juniper::FieldResult;
+use juniper::{FieldResult, InputValue};
 use lazy_static::lazy_static;
+use serde::Deserialize;
+use serde_json::json;
 use std::cmp;
 use std::convert::TryFrom;
 use std::sync::Arc;
+use std::time::Duration;
 
 use common_crypto::{
     HashValue, PrivateKey, PublicKey, Secp256k1PrivateKey, Signature, ToPublicKey,
 };
+use common_pubsub::{ChainEvent, PubSub};
 use protocol::fixed_codec::FixedCodec;
 use protocol::traits::{APIAdapter, Context};
 
-use crate::config::GraphQLConfig;
+use crate::auth::{require_scope, AuthError, Authenticator, Grant};
+use crate::config::{GraphQLConfig, Scope};
+use crate::limit::{LimitError, QueryLimit, RateLimiter};
 use crate::schema::{
-    to_signed_transaction, to_transaction, Address, Block, Bytes, Hash, InputRawTransaction,
-    InputTransactionEncryption, Receipt, ServiceResponse, SignedTransaction, Uint64,
+    to_signed_transaction, to_transaction, Address, Block, Bytes, ChainInfo, EventLog, Hash,
+    InputRawTransaction, InputSignedTransaction, InputTransactionEncryption, MerkleProof, Receipt,
+    SendTransactionResult, ServiceResponse, SignedTransaction, StateProof, TransactionStatus,
+    Uint64, WaitReceiptResult, WaitStatus,
+};
+use crate::subscription::{
+    new_subscription_schema, SubscriptionSchema, SubscriptionSession, GRAPHQL_WS_PROTOCOL,
 };
 
+// Page size of the paginated queries.
+const DEFAULT_PAGE_SIZE: u64 = 100;
+const MAX_PAGE_SIZE: u64 = 1000;
+// Longest time a request may wait for a receipt.
+const MAX_WAIT_TIMEOUT_MS: u64 = 60_000;
+// Most transactions sent in one request.
+const MAX_BATCH_TXS: usize = 1000;
+
 lazy_static! {
     static ref GRAPHIQL_HTML: &'static str = include_str!("../source/graphiql.html");
 }
@@ -30,8 +57,16 @@ lazy_static! {
 // This is accessible as state in Tide, and as executor context in Juniper.
 #[derive(Clone)]
 struct State {
-    adapter: Arc<Box<dyn APIAdapter>>,
-    schema:  Arc<Schema>,
+    adapter:             Arc<Box<dyn APIAdapter>>,
+    schema:              Arc<Schema>,
+    subscription_schema: Arc<SubscriptionSchema>,
+    event_bus:           Arc<PubSub<ChainEvent>>,
+    auth:                Arc<Authenticator>,
+    enable_unsafe_api:   bool,
+    rate_limiter:        Arc<RateLimiter>,
+    query_limit:         Arc<QueryLimit>,
+    // Scopes granted to the current request.
+    scopes:              Vec<Scope>,
 }
 
 // We define `Query` unit struct here. GraphQL queries will refer to this
@@ -57,6 +92,18 @@ impl Query {
         Ok(Block::from(block))
     }
 
+    #[graphql(name = "getBlockByHash", description = "Get the block by hash")]
+    async fn get_block_by_hash(state_ctx: &State, block_hash: Hash) -> FieldResult<Block> {
+        let hash = protocol::types::Hash::from_hex(&block_hash.as_hex())?;
+
+        let block = state_ctx
+            .adapter
+            .get_block_by_hash(Context::new(), hash)
+            .await?;
+
+        Ok(Block::from(block))
+    }
+
     #[graphql(name = "getTransaction", description = "Get the transaction by hash")]
     async fn get_transaction(state_ctx: &State, tx_hash: Hash) -> FieldResult<SignedTransaction> {
         let hash = protocol::types::Hash::from_hex(&tx_hash.as_hex())?;
@@ -84,7 +131,221 @@ impl Query {
         Ok(Receipt::from(receipt))
     }
 
-    #[graphql(name = "queryService", description = "query service")]
+    #[graphql(
+        name = "waitForReceipt",
+        description = "Wait for the receipt of the transaction, at most 60 seconds"
+    )]
+    async fn wait_for_receipt(
+        state_ctx: &State,
+        tx_hash: Hash,
+        timeout_ms: Uint64,
+    ) -> FieldResult<WaitReceiptResult> {
+        let hash = protocol::types::Hash::from_hex(&tx_hash.as_hex())?;
+
+        wait_receipt(state_ctx, hash, timeout_ms.try_into_u64()?, None).await
+    }
+
+    #[graphql(
+        name = "getChainInfo",
+        description = "Get the status of the node, such as heights, peers and syncing"
+    )]
+    async fn get_chain_info(state_ctx: &State) -> FieldResult<ChainInfo> {
+        let info = state_ctx.adapter.get_chain_info(Context::new()).await?;
+
+        Ok(ChainInfo::from(info))
+    }
+
+    #[graphql(
+        name = "getTransactionProof",
+        description = "Get the merkle proof of an executed transaction against the order root"
+    )]
+    async fn get_transaction_proof(state_ctx: &State, tx_hash: Hash) -> FieldResult<MerkleProof> {
+        let hash = protocol::types::Hash::from_hex(&tx_hash.as_hex())?;
+
+        let proof = state_ctx
+            .adapter
+            .get_transaction_proof(Context::new(), hash)
+            .await?;
+
+        Ok(MerkleProof::from(proof))
+    }
+
+    #[graphql(
+        name = "getReceiptProof",
+        description = "Get the merkle proof of a receipt, the leaf is hash of the encoded receipt"
+    )]
+    async fn get_receipt_proof(state_ctx: &State, tx_hash: Hash) -> FieldResult<MerkleProof> {
+        let hash = protocol::types::Hash::from_hex(&tx_hash.as_hex())?;
+
+        let proof = state_ctx
+            .adapter
+            .get_receipt_proof(Context::new(), hash)
+            .await?;
+
+        Ok(MerkleProof::from(proof))
+    }
+
+    #[graphql(
+        name = "getTransactionsInBlock",
+        description = "Get the transactions of the block, at most 1000 per page"
+    )]
+    async fn get_transactions_in_block(
+        state_ctx: &State,
+        height: Uint64,
+        offset: Option<Uint64>,
+        limit: Option<Uint64>,
+    ) -> FieldResult<Vec<SignedTransaction>> {
+        let (offset, limit) = to_page(offset, limit)?;
+
+        let stxs = state_ctx
+            .adapter
+            .get_transactions_in_block(Context::new(), height.try_into_u64()?, offset, limit)
+            .await?;
+
+        Ok(stxs.into_iter().map(SignedTransaction::from).collect())
+    }
+
+    #[graphql(
+        name = "getReceiptsInBlock",
+        description = "Get the receipts of the block, at most 1000 per page"
+    )]
+    async fn get_receipts_in_block(
+        state_ctx: &State,
+        height: Uint64,
+        offset: Option<Uint64>,
+        limit: Option<Uint64>,
+    ) -> FieldResult<Vec<Receipt>> {
+        let (offset, limit) = to_page(offset, limit)?;
+
+        let receipts = state_ctx
+            .adapter
+            .get_receipts_in_block(Context::new(), height.try_into_u64()?, offset, limit)
+            .await?;
+
+        Ok(receipts.into_iter().map(Receipt::from).collect())
+    }
+
+    #[graphql(
+        name = "getTransactionsByAddress",
+        description = "Get the transactions sent by the address, the latest one comes first"
+    )]
+    async fn get_transactions_by_address(
+        state_ctx: &State,
+        address: Address,
+        offset: Option<Uint64>,
+        limit: Option<Uint64>,
+    ) -> FieldResult<Vec<SignedTransaction>> {
+        let (offset, limit) = to_page(offset, limit)?;
+        let address = protocol::types::Address::from_hex(&address.as_hex())?;
+
+        let stxs = state_ctx
+            .adapter
+            .get_transactions_by_address(Context::new(), address, offset, limit)
+            .await?;
+
+        Ok(stxs.into_iter().map(SignedTransaction::from).collect())
+    }
+
+    #[graphql(
+        name = "getEvents",
+        description = "Get the events emitted by the service between the heights, \
+                       optionally only those whose data equals the topic"
+    )]
+    async fn get_events(
+        state_ctx: &State,
+        from_height: Uint64,
+        to_height: Uint64,
+        service: String,
+        topic: Option<String>,
+    ) -> FieldResult<Vec<EventLog>> {
+        let receipts = state_ctx
+            .adapter
+            .get_events(
+                Context::new(),
+                from_height.try_into_u64()?,
+                to_height.try_into_u64()?,
+                service,
+                topic,
+            )
+            .await?;
+
+        Ok(receipts
+            .into_iter()
+            .flat_map(EventLog::from_receipt)
+            .collect())
+    }
+
+    #[graphql(
+        name = "getMempoolSize",
+        description = "Get the number of transactions waiting in mempool"
+    )]
+    async fn get_mempool_size(state_ctx: &State) -> FieldResult<Uint64> {
+        let count = state_ctx
+            .adapter
+            .get_mempool_tx_count(Context::new())
+            .await?;
+
+        Ok(Uint64::from(count))
+    }
+
+    #[graphql(
+        name = "getPendingTxHashes",
+        description = "Get hashes of transactions waiting in mempool, at most 1000 at once"
+    )]
+    async fn get_pending_tx_hashes(
+        state_ctx: &State,
+        limit: Option<Uint64>,
+    ) -> FieldResult<Vec<Hash>> {
+        let (_, limit) = to_page(None, limit)?;
+
+        let tx_hashes = state_ctx
+            .adapter
+            .get_pending_tx_hashes(Context::new(), limit)
+            .await?;
+
+        Ok(tx_hashes.into_iter().map(Hash::from).collect())
+    }
+
+    #[graphql(
+        name = "getPendingTransaction",
+        description = "Get the transaction waiting in mempool by hash"
+    )]
+    async fn get_pending_transaction(
+        state_ctx: &State,
+        tx_hash: Hash,
+    ) -> FieldResult<Option<SignedTransaction>> {
+        let hash = protocol::types::Hash::from_hex(&tx_hash.as_hex())?;
+
+        let stx = state_ctx
+            .adapter
+            .get_pending_transaction(Context::new(), hash)
+            .await?;
+
+        Ok(stx.map(SignedTransaction::from))
+    }
+
+    #[graphql(
+        name = "getTransactionStatus",
+        description = "Get the status of the transaction: pending, committed or unknown"
+    )]
+    async fn get_transaction_status(
+        state_ctx: &State,
+        tx_hash: Hash,
+    ) -> FieldResult<TransactionStatus> {
+        let hash = protocol::types::Hash::from_hex(&tx_hash.as_hex())?;
+
+        let status = state_ctx
+            .adapter
+            .get_transaction_status(Context::new(), hash)
+            .await?;
+
+        Ok(TransactionStatus::from(status))
+    }
+
+    #[graphql(
+        name = "queryService",
+        description = "Query service at the state right after the block of height, or the latest"
+    )]
     async fn query_service(
         state_ctx: &State,
         height: Option<Uint64>,
@@ -96,12 +357,8 @@ impl Query {
         payload: String,
     ) -> FieldResult<ServiceResponse> {
         let height = match height {
-            Some(id) => id.try_into_u64()?,
-            None => {
-                block_on(state_ctx.adapter.get_block_by_height(Context::new(), None))?
-                    .header
-                    .height
-            }
+            Some(id) => Some(id.try_into_u64()?),
+            None => None,
         };
         let cycles_limit = match cycles_limit {
             Some(cycles_limit) => cycles_limit.try_into_u64()?,
@@ -130,6 +387,53 @@ impl Query {
             .await?;
         Ok(ServiceResponse::from(exec_resp))
     }
+
+    #[graphql(
+        name = "getStateProof",
+        description = "Get the MPT proof of an encoded key, or of an account value if address is set"
+    )]
+    async fn get_state_proof(
+        state_ctx: &State,
+        height: Option<Uint64>,
+        service_name: String,
+        address: Option<Address>,
+        key: Bytes,
+    ) -> FieldResult<StateProof> {
+        let height = match height {
+            Some(id) => Some(id.try_into_u64()?),
+            None => None,
+        };
+        let address = match address {
+            Some(address) => Some(protocol::types::Address::from_hex(&address.as_hex())?),
+            None => None,
+        };
+        let key = protocol::Bytes::from(key.to_vec()?);
+
+        let proof = state_ctx
+            .adapter
+            .get_state_proof(Context::new(), height, service_name, address, key)
+            .await?;
+        Ok(StateProof::from(proof))
+    }
+
+    #[graphql(
+        name = "dryRunTransaction",
+        description = "Execute the transaction against the latest state, changes are discarded"
+    )]
+    async fn dry_run_transaction(
+        state_ctx: &State,
+        input_raw: InputRawTransaction,
+        caller: Address,
+    ) -> FieldResult<Receipt> {
+        let raw = to_transaction(input_raw)?;
+        let address = protocol::types::Address::from_hex(&caller.as_hex())?;
+
+        let receipt = state_ctx
+            .adapter
+            .simulate_transaction(Context::new(), address, raw)
+            .await?;
+        Ok(Receipt::from(receipt))
+    }
 }
 
 struct Mutation;
@@ -142,6 +446,8 @@ impl Mutation {
         input_raw: InputRawTransaction,
         input_encryption: InputTransactionEncryption,
     ) -> FieldResult<Hash> {
+        require_scope(&state_ctx.scopes, Scope::Send)?;
+
         let stx = to_signed_transaction(input_raw, input_encryption)?;
         let tx_hash = stx.tx_hash.clone();
 
@@ -153,6 +459,79 @@ impl Mutation {
         Ok(Hash::from(tx_hash))
     }
 
+    #[graphql(
+        name = "sendTransactionAndWait",
+        description = "Send transaction and wait for its receipt, at most 60 seconds"
+    )]
+    async fn send_transaction_and_wait(
+        state_ctx: &State,
+        input_raw: InputRawTransaction,
+        input_encryption: InputTransactionEncryption,
+        timeout_ms: Uint64,
+    ) -> FieldResult<WaitReceiptResult> {
+        require_scope(&state_ctx.scopes, Scope::Send)?;
+
+        let stx = to_signed_transaction(input_raw, input_encryption)?;
+        let tx_hash = stx.tx_hash.clone();
+        let tx_timeout = stx.raw.timeout;
+
+        state_ctx
+            .adapter
+            .insert_signed_txs(Context::new(), stx)
+            .await?;
+
+        wait_receipt(
+            state_ctx,
+            tx_hash,
+            timeout_ms.try_into_u64()?,
+            Some(tx_timeout),
+        )
+        .await
+    }
+
+    #[graphql(
+        name = "sendTransactions",
+        description = "Send a batch of transactions, returns whether each one was accepted"
+    )]
+    async fn send_transactions(
+        state_ctx: &State,
+        inputs: Vec<InputSignedTransaction>,
+    ) -> FieldResult<Vec<SendTransactionResult>> {
+        require_scope(&state_ctx.scopes, Scope::Send)?;
+        if inputs.len() > MAX_BATCH_TXS {
+            return Err(LimitError::TooManyTxs {
+                count: inputs.len(),
+                limit: MAX_BATCH_TXS,
+            }
+            .into());
+        }
+
+        let mut results = Vec::with_capacity(inputs.len());
+        let mut stxs = Vec::with_capacity(inputs.len());
+        let mut indexes = Vec::with_capacity(inputs.len());
+
+        // Malformed inputs are rejected here, the rest go to mempool at once.
+        for (index, input) in inputs.into_iter().enumerate() {
+            let tx_hash = input.input_encryption.tx_hash.clone();
+            let result =
+                to_signed_transaction(input.input_raw, input.input_encryption).map(|stx| {
+                    stxs.push(stx);
+                    indexes.push(index);
+                });
+            results.push(SendTransactionResult::new(tx_hash, result));
+        }
+
+        let inserted = state_ctx
+            .adapter
+            .insert_batch_signed_txs(Context::new(), stxs)
+            .await;
+        for (index, result) in indexes.into_iter().zip(inserted.into_iter()) {
+            results[index] = SendTransactionResult::new(results[index].tx_hash.clone(), result);
+        }
+
+        Ok(results)
+    }
+
     #[graphql(
         name = "unsafeSendTransaction",
         deprecated = "DON'T use it in production! This is just for development."
@@ -162,6 +541,11 @@ impl Mutation {
         input_raw: InputRawTransaction,
         input_privkey: Bytes,
     ) -> FieldResult<Hash> {
+        if !state_ctx.enable_unsafe_api {
+            return Err(AuthError::Disabled("unsafeSendTransaction").into());
+        }
+        require_scope(&state_ctx.scopes, Scope::Admin)?;
+
         let raw_tx = to_transaction(input_raw)?;
         let tx_hash = protocol::types::Hash::digest(raw_tx.encode_fixed()?);
 
@@ -173,6 +557,7 @@ impl Mutation {
         let stx = protocol::types::SignedTransaction {
             raw:       raw_tx,
             tx_hash:   tx_hash.clone(),
+            scheme:    protocol::types::SignatureScheme::Secp256k1,
             signature: signature.to_bytes(),
             pubkey:    pubkey.to_bytes(),
         };
@@ -189,6 +574,101 @@ impl Mutation {
 // well, the whole GraphQL schema.
 type Schema = juniper::RootNode<'static, Query, Mutation>;
 
+// Wait until the receipt is saved or the timeout, then tell why it is not
+// there. `tx_timeout` is the timeout height of the transaction if known.
+async fn wait_receipt(
+    state_ctx: &State,
+    tx_hash: protocol::types::Hash,
+    timeout_ms: u64,
+    tx_timeout: Option<u64>,
+) -> FieldResult<WaitReceiptResult> {
+    // Subscribe before looking up, so a receipt saved in between is not missed.
+    let mut events = state_ctx.event_bus.subscribe();
+    if let Ok(receipt) = state_ctx
+        .adapter
+        .get_receipt_by_tx_hash(Context::new(), tx_hash.clone())
+        .await
+    {
+        return Ok(WaitReceiptResult::executed(receipt));
+    }
+
+    let wait = async {
+        while let Some(event) = events.next().await {
+            if let ChainEvent::NewReceipts(receipts) = event {
+                if let Some(receipt) = receipts.into_iter().find(|r| r.tx_hash == tx_hash) {
+                    return Some(receipt);
+                }
+            }
+        }
+        None
+    };
+    let delay = Delay::new(Duration::from_millis(cmp::min(
+        timeout_ms,
+        MAX_WAIT_TIMEOUT_MS,
+    )));
+
+    if let Either::Left((Some(receipt), _)) = future::select(Box::pin(wait), delay).await {
+        return Ok(WaitReceiptResult::executed(receipt));
+    }
+
+    // The subscriber is dropped if it lags behind, so look up again.
+    if let Ok(receipt) = state_ctx
+        .adapter
+        .get_receipt_by_tx_hash(Context::new(), tx_hash.clone())
+        .await
+    {
+        return Ok(WaitReceiptResult::executed(receipt));
+    }
+
+    let status = state_ctx
+        .adapter
+        .get_transaction_status(Context::new(), tx_hash.clone())
+        .await?;
+    let tx_timeout = match (status, tx_timeout) {
+        (_, Some(tx_timeout)) => Some(tx_timeout),
+        (protocol::traits::TransactionStatus::Pending, None) => state_ctx
+            .adapter
+            .get_pending_transaction(Context::new(), tx_hash.clone())
+            .await?
+            .map(|stx| stx.raw.timeout),
+        _ => None,
+    };
+
+    let status = match status {
+        protocol::traits::TransactionStatus::Committed => WaitStatus::Committed,
+        status => {
+            let latest_height = state_ctx
+                .adapter
+                .get_block_by_height(Context::new(), None)
+                .await?
+                .header
+                .height;
+
+            match (status, tx_timeout) {
+                (_, Some(tx_timeout)) if tx_timeout < latest_height => WaitStatus::Expired,
+                (protocol::traits::TransactionStatus::Pending, _) => WaitStatus::Pending,
+                (protocol::traits::TransactionStatus::Proposed, _) => WaitStatus::Proposed,
+                _ => WaitStatus::Unknown,
+            }
+        }
+    };
+
+    Ok(WaitReceiptResult::timed_out(tx_hash, status))
+}
+
+fn to_page(offset: Option<Uint64>, limit: Option<Uint64>) -> FieldResult<(u64, u64)> {
+    let offset = match offset {
+        Some(offset) => offset.try_into_u64()?,
+        None => 0,
+    };
+    let limit = match limit {
+        Some(limit) => cmp::min(limit.try_into_u64()?, MAX_PAGE_SIZE),
+        None => DEFAULT_PAGE_SIZE,
+    };
+
+    Ok((offset, limit))
+}
+
 async fn graphiql() -> HttpResponse {
     HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
@@ -197,9 +677,29 @@ async fn graphiql() -> HttpResponse {
 
 async fn graphql(
     st: web::Data<State>,
-    data: web::Json<GraphQLRequest>,
+    req: HttpRequest,
+    body: web::Bytes,
 ) -> Result<HttpResponse, Error> {
-    let result = data.execute_async(&st.schema, &st).await;
+    let grant = match authenticate(&st, &req, &body) {
+        Ok(grant) => grant,
+        Err(resp) => return Ok(resp),
+    };
+    if let Err(e) = st.rate_limiter.check(&client_id(&req, &grant), 1) {
+        return Ok(graphql_error(StatusCode::TOO_MANY_REQUESTS, e));
+    }
+
+    let query =
+        serde_json::from_slice::<QueryBody>(&body).map_err(actix_web::error::ErrorBadRequest)?;
+    if let Err(e) = st.query_limit.check(&query.query, &st.schema.schema) {
+        return Ok(graphql_error(StatusCode::OK, e));
+    }
+    let data = GraphQLRequest::new(query.query, query.operation_name, query.variables);
+
+    let ctx = State {
+        scopes: grant.scopes,
+        ..st.get_ref().clone()
+    };
+    let result = data.execute_async(&st.schema, &ctx).await;
     let res = Ok::<_, serde_json::error::Error>(serde_json::to_string(&result)?)?;
 
     Ok(HttpResponse::Ok()
@@ -207,33 +707,151 @@ async fn graphql(
         .body(res))
 }
 
-pub async fn start_graphql<Adapter: APIAdapter + 'static>(cfg: GraphQLConfig, adapter: Adapter) {
+async fn jsonrpc(st: web::Data<State>, req: HttpRequest, body: web::Bytes) -> HttpResponse {
+    let grant = match authenticate(&st, &req, &body) {
+        Ok(grant) => grant,
+        Err(resp) => return resp,
+    };
+    let request = match jsonrpc::parse(&body) {
+        Ok(request) => request,
+        Err(resp) => return json_response(StatusCode::OK, resp),
+    };
+
+    let cost = jsonrpc::call_count(&request);
+    if let Err(e) = st.rate_limiter.check(&client_id(&req, &grant), cost) {
+        let resp = jsonrpc::rate_limited_response(e.to_string());
+        return json_response(StatusCode::TOO_MANY_REQUESTS, resp);
+    }
+
+    match jsonrpc::handle(&**st.adapter, &grant.scopes, request).await {
+        Some(resp) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(resp.to_string()),
+        None => HttpResponse::NoContent().finish(),
+    }
+}
+
+async fn subscriptions(
+    st: web::Data<State>,
+    req: HttpRequest,
+    stream: web::Payload,
+) -> Result<HttpResponse, Error> {
+    let grant = match authenticate(&st, &req, &[]) {
+        Ok(grant) => grant,
+        Err(resp) => return Ok(resp),
+    };
+    if let Err(e) = st.rate_limiter.check(&client_id(&req, &grant), 1) {
+        return Ok(HttpResponse::TooManyRequests().body(e.to_string()));
+    }
+
+    let session = SubscriptionSession::new(
+        Arc::clone(&st.subscription_schema),
+        Arc::clone(&st.event_bus),
+        Arc::clone(&st.query_limit),
+    );
+
+    ws::start_with_protocols(session, &[GRAPHQL_WS_PROTOCOL], &req, stream)
+}
+
+// Every request needs the read scope, the others are checked by the operations.
+fn authenticate(st: &State, req: &HttpRequest, body: &[u8]) -> Result<Grant, HttpResponse> {
+    st.auth
+        .authenticate(req, body)
+        .and_then(|grant| {
+            require_scope(&grant.scopes, Scope::Read)?;
+            Ok(grant)
+        })
+        .map_err(|e| HttpResponse::Unauthorized().body(e.to_string()))
+}
+
+// Clients are told apart by api key if auth is enabled, otherwise by ip.
+fn client_id(req: &HttpRequest, grant: &Grant) -> String {
+    match (&grant.key_id, req.peer_addr()) {
+        (Some(key_id), _) => format!("key:{}", key_id),
+        (None, Some(addr)) => format!("ip:{}", addr.ip()),
+        (None, None) => "unknown".to_owned(),
+    }
+}
+
+// The query is read before executing to check its cost.
+#[derive(Deserialize)]
+struct QueryBody {
+    query:          String,
+    #[serde(rename = "operationName")]
+    operation_name: Option<String>,
+    variables:      Option<InputValue>,
+}
+
+fn graphql_error(status: StatusCode, err: LimitError) -> HttpResponse {
+    json_response(
+        status,
+        json!({ "errors": [{ "message": err.to_string() }] }),
+    )
+}
+
+fn json_response(status: StatusCode, body: serde_json::Value) -> HttpResponse {
+    HttpResponse::build(status)
+        .content_type("application/json")
+        .body(body.to_string())
+}
+
+pub async fn start_graphql<Adapter: APIAdapter + 'static>(
+    cfg: GraphQLConfig,
+    adapter: Adapter,
+    event_bus: Arc<PubSub<ChainEvent>>,
+) {
     let schema = Schema::new(Query, Mutation);
 
     let state = State {
         adapter: Arc::new(Box::new(adapter)),
-        schema:  Arc::new(schema),
+        schema: Arc::new(schema),
+        subscription_schema: Arc::new(new_subscription_schema()),
+        event_bus,
+        auth: Arc::new(Authenticator::new(cfg.api_keys.clone())),
+        enable_unsafe_api: cfg.enable_unsafe_api,
+        rate_limiter: Arc::new(RateLimiter::new(cfg.rate_limit, cfg.rate_limit_burst)),
+        query_limit: Arc::new(QueryLimit::new(
+            cfg.max_query_depth,
+            cfg.max_query_complexity,
+        )),
+        scopes: vec![],
     };
 
     let path_graphql_uri = cfg.graphql_uri.to_owned();
     let path_graphiql_uri = cfg.graphiql_uri.to_owned();
+    let path_subscription_uri = cfg.subscription_uri.to_owned();
+    let path_jsonrpc_uri = cfg.jsonrpc_uri.to_owned();
     let workers = cfg.workers;
     let maxconn = cfg.maxconn;
     let add_listening_address = cfg.listening_address;
     let max_payload_size = cfg.max_payload_size;
+    let enable_graphiql = cfg.enable_graphiql;
 
     // Start http server
     HttpServer::new(move || {
-        App::new()
+        let app = App::new()
             .data(state.clone())
             .service(
                 web::resource(&path_graphql_uri)
-                    .app_data(web::Json::<GraphQLRequest>::configure(|cfg| {
-                        cfg.limit(max_payload_size)
-                    }))
+                    .app_data(web::Bytes::configure(|cfg| cfg.limit(max_payload_size)))
                     .route(web::post().to(graphql)),
             )
-            .service(web::resource(&path_graphiql_uri).route(web::get().to(graphiql)))
+            .service(web::resource(&path_subscription_uri).route(web::get().to(subscriptions)));
+
+        let app = if enable_graphiql {
+            app.service(web::resource(&path_graphiql_uri).route(web::get().to(graphiql)))
+        } else {
+            app
+        };
+
+        match &path_jsonrpc_uri {
+            Some(uri) => app.service(
+                web::resource(uri)
+                    .app_data(web::Bytes::configure(|cfg| cfg.limit(max_payload_size)))
+                    .route(web::post().to(jsonrpc)),
+            ),
+            None => app,
+        }
     })
     .workers(workers)
     .maxconn(cmp::max(maxconn / workers, 1))
@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use derive_more::Display;
+use juniper::parser::parse_document_source;
+use juniper::{Definition, ScalarValue, SchemaType, Selection};
+
+// The least number of tracked clients before full buckets are dropped.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+struct Bucket {
+    tokens:  f64,
+    updated: Instant,
+}
+
+struct Buckets {
+    clients:  HashMap<String, Bucket>,
+    // Full buckets are dropped once this many clients are tracked. It grows
+    // with the clients left after a sweep, so a request pays O(1) on average.
+    sweep_at: usize,
+}
+
+/// A token bucket per client, refilled at `rate` tokens per second and holding
+/// at most `burst` tokens.
+pub struct RateLimiter {
+    rate:    u64,
+    burst:   u64,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    /// Every request is allowed if `rate` is 0. `burst` is at least `rate`.
+    pub fn new(rate: u64, burst: u64) -> Self {
+        RateLimiter {
+            rate,
+            burst: burst.max(rate),
+            buckets: Mutex::new(Buckets {
+                clients:  HashMap::new(),
+                sweep_at: MAX_TRACKED_CLIENTS,
+            }),
+        }
+    }
+
+    pub fn check(&self, client: &str, cost: u64) -> Result<(), LimitError> {
+        if self.rate == 0 {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let (rate, burst) = (self.rate as f64, self.burst as f64);
+        let refill = |bucket: &mut Bucket| {
+            let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+            bucket.updated = now;
+        };
+
+        let mut buckets = self.buckets.lock().expect("rate limiter lock poisoned");
+        if buckets.clients.len() >= buckets.sweep_at {
+            buckets.clients.retain(|_, bucket| {
+                refill(bucket);
+                bucket.tokens < burst
+            });
+            buckets.sweep_at = MAX_TRACKED_CLIENTS.max(buckets.clients.len() * 2);
+        }
+
+        let bucket = buckets.clients.entry(client.to_owned()).or_insert(Bucket {
+            tokens:  burst,
+            updated: now,
+        });
+        refill(bucket);
+
+        if bucket.tokens < cost as f64 {
+            return Err(LimitError::RateLimited);
+        }
+        bucket.tokens -= cost as f64;
+        Ok(())
+    }
+}
+
+/// Limits of a graphql document, checked before it is executed. Fragments are
+/// expanded, so the cost is the same as the query written without them.
+pub struct QueryLimit {
+    max_depth:      usize,
+    max_complexity: usize,
+}
+
+impl QueryLimit {
+    /// A limit of 0 is not checked.
+    pub fn new(max_depth: usize, max_complexity: usize) -> Self {
+        QueryLimit {
+            max_depth,
+            max_complexity,
+        }
+    }
+
+    /// The document is parsed by juniper against `schema`, the same way it is
+    /// parsed for execution.
+    pub fn check<S: ScalarValue>(
+        &self,
+        query: &str,
+        schema: &SchemaType<S>,
+    ) -> Result<(), LimitError> {
+        if self.max_depth == 0 && self.max_complexity == 0 {
+            return Ok(());
+        }
+
+        let doc = parse_document_source(query, schema)
+            .map_err(|e| LimitError::Syntax(e.item.to_string()))?;
+
+        for cost in measure_document(&doc)? {
+            if self.max_depth != 0 && cost.depth > self.max_depth {
+                return Err(LimitError::TooDeep {
+                    depth: cost.depth,
+                    limit: self.max_depth,
+                });
+            }
+            if self.max_complexity != 0 && cost.complexity > self.max_complexity {
+                return Err(LimitError::TooComplex {
+                    complexity: cost.complexity,
+                    limit:      self.max_complexity,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Cost {
+    // Levels of nested fields.
+    depth:      usize,
+    // Number of fields.
+    complexity: usize,
+}
+
+// Returns the cost of every operation in the document.
+fn measure_document<'a, S>(doc: &'a [Definition<'a, S>]) -> Result<Vec<Cost>, LimitError> {
+    let fragments = doc
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::Fragment(fragment) => Some((
+                fragment.item.name.item,
+                fragment.item.selection_set.as_slice(),
+            )),
+            Definition::Operation(_) => None,
+        })
+        .collect::<HashMap<_, _>>();
+    let mut measured = HashMap::new();
+
+    doc.iter()
+        .filter_map(|definition| match definition {
+            Definition::Operation(operation) => Some(operation.item.selection_set.as_slice()),
+            Definition::Fragment(_) => None,
+        })
+        .map(|selections| measure(selections, &fragments, &mut measured, &mut vec![]))
+        .collect()
+}
+
+fn measure<'a, S>(
+    selections: &'a [Selection<'a, S>],
+    fragments: &HashMap<&'a str, &'a [Selection<'a, S>]>,
+    measured: &mut HashMap<&'a str, Cost>,
+    visiting: &mut Vec<&'a str>,
+) -> Result<Cost, LimitError> {
+    let mut cost = Cost::default();
+
+    for selection in selections.iter() {
+        let (depth, complexity) = match selection {
+            Selection::Field(field) => {
+                let children = match &field.item.selection_set {
+                    Some(children) => measure(children, fragments, measured, visiting)?,
+                    None => Cost::default(),
+                };
+                (children.depth + 1, children.complexity.saturating_add(1))
+            }
+            Selection::InlineFragment(fragment) => {
+                let children =
+                    measure(&fragment.item.selection_set, fragments, measured, visiting)?;
+                (children.depth, children.complexity)
+            }
+            Selection::FragmentSpread(spread) => {
+                let name = spread.item.name.item;
+                let child = match measured.get(name) {
+                    Some(cost) => *cost,
+                    None => {
+                        if visiting.contains(&name) {
+                            let msg = format!("fragment {} is cyclic", name);
+                            return Err(LimitError::Syntax(msg));
+                        }
+                        let fragment = *fragments.get(name).ok_or_else(|| {
+                            LimitError::Syntax(format!("unknown fragment {}", name))
+                        })?;
+
+                        visiting.push(name);
+                        let cost = measure(fragment, fragments, measured, visiting)?;
+                        visiting.pop();
+
+                        measured.insert(name, cost);
+                        cost
+                    }
+                };
+                (child.depth, child.complexity)
+            }
+        };
+
+        cost.depth = cost.depth.max(depth);
+        cost.complexity = cost.complexity.saturating_add(complexity);
+    }
+
+    Ok(cost)
+}
+
+#[derive(Debug, Display)]
+pub enum LimitError {
+    #[display(fmt = "rate limit exceeded, try again later")]
+    RateLimited,
+
+    #[display(fmt = "query depth {} exceeds the limit {}", depth, limit)]
+    TooDeep { depth: usize, limit: usize },
+
+    #[display(fmt = "query complexity {} exceeds the limit {}", complexity, limit)]
+    TooComplex {
+        complexity: usize,
+        limit:      usize,
+    },
+
+    #[display(fmt = "invalid query: {}", _0)]
+    Syntax(String),
+
+    #[display(fmt = "{} transactions exceed the limit {}", count, limit)]
+    TooManyTxs { count: usize, limit: usize },
+}
+
+impl Error for LimitError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subscription::new_subscription_schema;
+
+    fn cost(query: &str) -> Result<(usize, usize), LimitError> {
+        let schema = new_subscription_schema();
+        let doc = parse_document_source(query, &schema.schema)
+            .map_err(|e| LimitError::Syntax(e.item.to_string()))?;
+
+        let mut max = Cost::default();
+        for cost in measure_document(&doc)? {
+            max.depth = max.depth.max(cost.depth);
+            max.complexity = max.complexity.max(cost.complexity);
+        }
+        Ok((max.depth, max.complexity))
+    }
+
+    #[test]
+    fn test_query_cost() {
+        assert_eq!(cost("{ getBlock { header { height } } }").unwrap(), (3, 3));
+        assert_eq!(
+            cost(
+                r#"query Q($h: String = "0x1") @dir(a: {b: "}"}) {
+                    a: getBlock(height: $h) { header { height, execHeight } # {{{
+                    }
+                    getReceipt(txHash: """ { " """) { ...R ... on Receipt { height } }
+                }
+                fragment R on Receipt { txHash response { ret } }"#
+            )
+            .unwrap(),
+            (3, 9)
+        );
+        assert!(cost("{ a { b }").is_err());
+        assert!(cost("{ ...A } fragment A on Q { ...A }").is_err());
+        assert!(cost("{ ...Missing }").is_err());
+    }
+
+    #[test]
+    fn test_query_limit() {
+        let limit = QueryLimit::new(2, 3);
+        let schema = new_subscription_schema();
+        let check = |query: &str| limit.check(query, &schema.schema);
+
+        assert!(check("{ a { b } }").is_ok());
+        assert!(check("{ a { b { c } } }").is_err());
+        assert!(check("{ a b c d }").is_err());
+        // fragments are expanded
+        assert!(check("{ a { ...F ...F } } fragment F on A { b }").is_ok());
+        assert!(check("{ a { ...F } } fragment F on A { b { c } }").is_err());
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(1, 3);
+
+        assert!(limiter.check("a", 2).is_ok());
+        assert!(limiter.check("a", 1).is_ok());
+        assert!(limiter.check("a", 1).is_err());
+        assert!(limiter.check("b", 3).is_ok());
+
+        assert!(RateLimiter::new(0, 0).check("a", 1000).is_ok());
+    }
+}
@@ -1,9 +1,12 @@
+use serde::Serialize;
+
 use protocol::fixed_codec::FixedCodec;
 use protocol::types::Hash as PHash;
 
 use crate::schema::{Address, Bytes, Hash, MerkleRoot, Uint64};
 
-#[derive(juniper::GraphQLObject, Clone)]
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 #[graphql(
     description = "Block is a single digital record created within a blockchain. \
                    Each block contains a record of the previous Block, \
@@ -19,7 +22,8 @@ pub struct Block {
     hash:              Hash,
 }
 
-#[derive(juniper::GraphQLObject, Clone)]
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 #[graphql(description = "A block header is like the metadata of a block.")]
 pub struct BlockHeader {
     #[graphql(
@@ -52,7 +56,8 @@ pub struct BlockHeader {
     pub validators:        Vec<Validator>,
 }
 
-#[derive(juniper::GraphQLObject, Clone)]
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 #[graphql(description = "The verifier of the block header proved")]
 pub struct Proof {
     pub height:     Uint64,
@@ -62,7 +67,8 @@ pub struct Proof {
     pub bitmap:     Bytes,
 }
 
-#[derive(juniper::GraphQLObject, Clone)]
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 #[graphql(description = "Validator address set")]
 pub struct Validator {
     pub address:        Address,
@@ -1,22 +1,28 @@
 mod block;
+mod node;
+mod proof;
 mod receipt;
 mod transaction;
 
 use std::convert::From;
 
 use derive_more::{Display, From};
+use serde::{Deserialize, Serialize};
 use std::num::ParseIntError;
 
 use protocol::{ProtocolError, ProtocolErrorKind, ProtocolResult};
 
 pub use block::{Block, BlockHeader};
-pub use receipt::{Event, Receipt, ReceiptResponse};
+pub use node::ChainInfo;
+pub use proof::{MerkleProof, MerkleProofNode, StateProof};
+pub use receipt::{Event, EventLog, Receipt, ReceiptResponse, WaitReceiptResult, WaitStatus};
 pub use transaction::{
-    to_signed_transaction, to_transaction, InputRawTransaction, InputTransactionEncryption,
-    SignedTransaction,
+    to_signed_transaction, to_transaction, InputRawTransaction, InputSignedTransaction,
+    InputTransactionEncryption, SendTransactionResult, SignedTransaction, TransactionStatus,
 };
 
-#[derive(juniper::GraphQLObject, Clone)]
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct ServiceResponse {
     pub code:          Uint64,
     pub succeed_data:  String,
@@ -33,20 +39,20 @@ impl From<protocol::traits::ServiceResponse<String>> for ServiceResponse {
     }
 }
 
-#[derive(juniper::GraphQLScalarValue, Clone)]
+#[derive(juniper::GraphQLScalarValue, Serialize, Deserialize, Clone)]
 #[graphql(description = "The output digest of Keccak hash function")]
 pub struct Hash(String);
 pub type MerkleRoot = Hash;
 
-#[derive(juniper::GraphQLScalarValue, Clone)]
+#[derive(juniper::GraphQLScalarValue, Serialize, Deserialize, Clone)]
 #[graphql(description = "20 bytes of account address")]
 pub struct Address(String);
 
-#[derive(juniper::GraphQLScalarValue, Clone)]
+#[derive(juniper::GraphQLScalarValue, Serialize, Deserialize, Clone)]
 #[graphql(description = "Uint64")]
 pub struct Uint64(String);
 
-#[derive(juniper::GraphQLScalarValue, Clone)]
+#[derive(juniper::GraphQLScalarValue, Serialize, Deserialize, Clone)]
 #[graphql(description = "Bytes corresponding hex string.")]
 pub struct Bytes(String);
 
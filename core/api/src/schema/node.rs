@@ -0,0 +1,99 @@
+use serde::Serialize;
+
+use crate::schema::block::Proof;
+use crate::schema::{Address, Hash, Uint64};
+
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[graphql(description = "The status of the node and the chain it runs")]
+pub struct ChainInfo {
+    pub latest_height: Uint64,
+    pub exec_height:   Uint64,
+    pub latest_proof:  Proof,
+    pub metadata:      Metadata,
+    pub peer_count:    Uint64,
+    pub mempool_size:  Uint64,
+    pub is_syncing:    bool,
+    pub self_address:  Option<Address>,
+}
+
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Metadata {
+    pub chain_id:        Hash,
+    pub common_ref:      String,
+    pub timeout_gap:     Uint64,
+    pub cycles_limit:    Uint64,
+    pub cycles_price:    Uint64,
+    pub interval:        Uint64,
+    pub verifier_list:   Vec<ValidatorExtend>,
+    pub propose_ratio:   Uint64,
+    pub prevote_ratio:   Uint64,
+    pub precommit_ratio: Uint64,
+    pub brake_ratio:     Uint64,
+    pub tx_num_limit:    Uint64,
+    pub max_tx_size:     Uint64,
+    pub fee_asset_id:    Option<Hash>,
+    pub fee_treasury:    Option<Address>,
+}
+
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorExtend {
+    pub bls_pub_key:    String,
+    pub address:        Address,
+    pub propose_weight: i32,
+    pub vote_weight:    i32,
+}
+
+impl From<protocol::traits::ChainInfo> for ChainInfo {
+    fn from(info: protocol::traits::ChainInfo) -> Self {
+        ChainInfo {
+            latest_height: Uint64::from(info.latest_height),
+            exec_height:   Uint64::from(info.exec_height),
+            latest_proof:  Proof::from(info.latest_proof),
+            metadata:      Metadata::from(info.metadata),
+            peer_count:    Uint64::from(info.peer_count),
+            mempool_size:  Uint64::from(info.mempool_size),
+            is_syncing:    info.is_syncing,
+            self_address:  info.self_address.map(Address::from),
+        }
+    }
+}
+
+impl From<protocol::types::Metadata> for Metadata {
+    fn from(metadata: protocol::types::Metadata) -> Self {
+        Metadata {
+            chain_id:        Hash::from(metadata.chain_id),
+            common_ref:      metadata.common_ref.as_string(),
+            timeout_gap:     Uint64::from(metadata.timeout_gap),
+            cycles_limit:    Uint64::from(metadata.cycles_limit),
+            cycles_price:    Uint64::from(metadata.cycles_price),
+            interval:        Uint64::from(metadata.interval),
+            verifier_list:   metadata
+                .verifier_list
+                .into_iter()
+                .map(ValidatorExtend::from)
+                .collect(),
+            propose_ratio:   Uint64::from(metadata.propose_ratio),
+            prevote_ratio:   Uint64::from(metadata.prevote_ratio),
+            precommit_ratio: Uint64::from(metadata.precommit_ratio),
+            brake_ratio:     Uint64::from(metadata.brake_ratio),
+            tx_num_limit:    Uint64::from(metadata.tx_num_limit),
+            max_tx_size:     Uint64::from(metadata.max_tx_size),
+            fee_asset_id:    metadata.fee_asset_id.map(Hash::from),
+            fee_treasury:    metadata.fee_treasury.map(Address::from),
+        }
+    }
+}
+
+impl From<protocol::types::ValidatorExtend> for ValidatorExtend {
+    fn from(validator: protocol::types::ValidatorExtend) -> Self {
+        ValidatorExtend {
+            bls_pub_key:    validator.bls_pub_key.as_string(),
+            address:        Address::from(validator.address),
+            propose_weight: validator.propose_weight as i32,
+            vote_weight:    validator.vote_weight as i32,
+        }
+    }
+}
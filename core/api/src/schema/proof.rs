@@ -0,0 +1,71 @@
+use serde::Serialize;
+
+use crate::schema::{Bytes, Hash, MerkleRoot, Uint64};
+
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[graphql(
+    description = "A merkle path from the leaf to the root in the header of the block at height"
+)]
+pub struct MerkleProof {
+    pub height: Uint64,
+    pub root:   MerkleRoot,
+    pub leaf:   Hash,
+    pub index:  Uint64,
+    pub nodes:  Vec<MerkleProofNode>,
+}
+
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleProofNode {
+    pub is_right: bool,
+    pub hash:     Hash,
+}
+
+impl From<protocol::types::MerkleProof> for MerkleProof {
+    fn from(proof: protocol::types::MerkleProof) -> Self {
+        Self {
+            height: Uint64::from(proof.height),
+            root:   MerkleRoot::from(proof.root),
+            leaf:   Hash::from(proof.leaf),
+            index:  Uint64::from(proof.index),
+            nodes:  proof.nodes.into_iter().map(MerkleProofNode::from).collect(),
+        }
+    }
+}
+
+impl From<protocol::types::MerkleProofNode> for MerkleProofNode {
+    fn from(node: protocol::types::MerkleProofNode) -> Self {
+        Self {
+            is_right: node.is_right,
+            hash:     Hash::from(node.hash),
+        }
+    }
+}
+
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[graphql(description = "MPT proofs of the service root, and of the key in the service state")]
+pub struct StateProof {
+    pub state_root:   MerkleRoot,
+    pub service_name: String,
+    pub service_root: MerkleRoot,
+    pub root_proof:   Vec<Bytes>,
+    pub key:          Bytes,
+    pub value:        Option<Bytes>,
+    pub proof:        Vec<Bytes>,
+}
+
+impl From<protocol::traits::StateProof> for StateProof {
+    fn from(proof: protocol::traits::StateProof) -> Self {
+        Self {
+            state_root:   MerkleRoot::from(proof.state_root),
+            service_name: proof.service_name,
+            service_root: MerkleRoot::from(proof.service_root),
+            root_proof:   proof.root_proof.into_iter().map(Bytes::from).collect(),
+            key:          Bytes::from(proof.key),
+            value:        proof.value.map(Bytes::from),
+            proof:        proof.proof.into_iter().map(Bytes::from).collect(),
+        }
+    }
+}
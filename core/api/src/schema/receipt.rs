@@ -1,22 +1,86 @@
+use serde::Serialize;
+
 use crate::schema::{Hash, MerkleRoot, ServiceResponse, Uint64};
 
-#[derive(juniper::GraphQLObject, Clone)]
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct Receipt {
     pub state_root:  MerkleRoot,
     pub height:      Uint64,
     pub tx_hash:     Hash,
     pub cycles_used: Uint64,
+    pub fee:         Uint64,
     pub events:      Vec<Event>,
     pub response:    ReceiptResponse,
 }
 
-#[derive(juniper::GraphQLObject, Clone)]
+#[derive(juniper::GraphQLEnum, Serialize, Clone, Copy)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum WaitStatus {
+    #[graphql(description = "The receipt is saved")]
+    Executed,
+    #[graphql(description = "Timed out, the transaction is still waiting in mempool")]
+    Pending,
+    #[graphql(
+        description = "Timed out, the transaction is ordered by a proposal not committed yet"
+    )]
+    Proposed,
+    #[graphql(description = "Timed out, the transaction is committed but not executed yet")]
+    Committed,
+    #[graphql(
+        description = "Timed out, the chain has passed the timeout height of the transaction"
+    )]
+    Expired,
+    #[graphql(description = "Timed out, the transaction is unknown")]
+    Unknown,
+}
+
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[graphql(description = "The receipt if the transaction is executed before the timeout")]
+pub struct WaitReceiptResult {
+    pub tx_hash: Hash,
+    pub status:  WaitStatus,
+    pub receipt: Option<Receipt>,
+}
+
+impl WaitReceiptResult {
+    pub fn executed(receipt: protocol::types::Receipt) -> Self {
+        WaitReceiptResult {
+            tx_hash: Hash::from(receipt.tx_hash.clone()),
+            status:  WaitStatus::Executed,
+            receipt: Some(Receipt::from(receipt)),
+        }
+    }
+
+    pub fn timed_out(tx_hash: protocol::types::Hash, status: WaitStatus) -> Self {
+        WaitReceiptResult {
+            tx_hash: Hash::from(tx_hash),
+            status,
+            receipt: None,
+        }
+    }
+}
+
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct Event {
     pub service: String,
     pub data:    String,
 }
 
-#[derive(juniper::GraphQLObject, Clone)]
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[graphql(description = "An event with the transaction which emitted it")]
+pub struct EventLog {
+    pub height:  Uint64,
+    pub tx_hash: Hash,
+    pub service: String,
+    pub data:    String,
+}
+
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct ReceiptResponse {
     pub service_name: String,
     pub method:       String,
@@ -30,6 +94,7 @@ impl From<protocol::types::Receipt> for Receipt {
             height:      Uint64::from(receipt.height),
             tx_hash:     Hash::from(receipt.tx_hash),
             cycles_used: Uint64::from(receipt.cycles_used),
+            fee:         Uint64::from(receipt.fee),
             events:      receipt.events.into_iter().map(Event::from).collect(),
             response:    ReceiptResponse::from(receipt.response),
         }
@@ -45,6 +110,24 @@ impl From<protocol::types::Event> for Event {
     }
 }
 
+impl EventLog {
+    pub fn from_receipt(receipt: protocol::types::Receipt) -> Vec<EventLog> {
+        let height = Uint64::from(receipt.height);
+        let tx_hash = Hash::from(receipt.tx_hash);
+
+        receipt
+            .events
+            .into_iter()
+            .map(|event| EventLog {
+                height:  height.clone(),
+                tx_hash: tx_hash.clone(),
+                service: event.service,
+                data:    event.data,
+            })
+            .collect()
+    }
+}
+
 impl From<protocol::types::ReceiptResponse> for ReceiptResponse {
     fn from(response: protocol::types::ReceiptResponse) -> Self {
         Self {
@@ -1,8 +1,11 @@
+use serde::{Deserialize, Serialize};
+
 use protocol::ProtocolResult;
 
 use crate::schema::{Bytes, Hash, SchemaError, Uint64};
 
-#[derive(juniper::GraphQLObject, Clone)]
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct SignedTransaction {
     pub chain_id:     Hash,
     pub cycles_limit: Uint64,
@@ -13,10 +16,87 @@ pub struct SignedTransaction {
     pub method:       String,
     pub payload:      String,
     pub tx_hash:      Hash,
+    pub scheme:       SignatureScheme,
     pub pubkey:       Bytes,
     pub signature:    Bytes,
 }
 
+#[derive(juniper::GraphQLEnum, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[graphql(description = "Signature scheme of the transaction")]
+pub enum SignatureScheme {
+    Secp256k1,
+    Ed25519,
+}
+
+#[derive(juniper::GraphQLEnum, Serialize, Clone, Copy)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransactionStatus {
+    #[graphql(description = "The transaction is waiting in mempool")]
+    Pending,
+    #[graphql(description = "The transaction is ordered by a proposal not committed yet")]
+    Proposed,
+    #[graphql(description = "The transaction is included in a committed block")]
+    Committed,
+    #[graphql(description = "The transaction is unknown, it may have been dropped")]
+    Unknown,
+}
+
+#[derive(juniper::GraphQLObject, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[graphql(description = "The result of a transaction in a batch, with the error if rejected")]
+pub struct SendTransactionResult {
+    pub tx_hash:  Hash,
+    pub accepted: bool,
+    pub error:    Option<String>,
+}
+
+impl SendTransactionResult {
+    pub fn new(tx_hash: Hash, result: ProtocolResult<()>) -> Self {
+        match result {
+            Ok(()) => SendTransactionResult {
+                tx_hash,
+                accepted: true,
+                error: None,
+            },
+            Err(e) => SendTransactionResult {
+                tx_hash,
+                accepted: false,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+impl From<protocol::traits::TransactionStatus> for TransactionStatus {
+    fn from(status: protocol::traits::TransactionStatus) -> Self {
+        match status {
+            protocol::traits::TransactionStatus::Pending => TransactionStatus::Pending,
+            protocol::traits::TransactionStatus::Proposed => TransactionStatus::Proposed,
+            protocol::traits::TransactionStatus::Committed => TransactionStatus::Committed,
+            protocol::traits::TransactionStatus::Unknown => TransactionStatus::Unknown,
+        }
+    }
+}
+
+impl From<protocol::types::SignatureScheme> for SignatureScheme {
+    fn from(scheme: protocol::types::SignatureScheme) -> Self {
+        match scheme {
+            protocol::types::SignatureScheme::Secp256k1 => SignatureScheme::Secp256k1,
+            protocol::types::SignatureScheme::Ed25519 => SignatureScheme::Ed25519,
+        }
+    }
+}
+
+impl From<SignatureScheme> for protocol::types::SignatureScheme {
+    fn from(scheme: SignatureScheme) -> Self {
+        match scheme {
+            SignatureScheme::Secp256k1 => protocol::types::SignatureScheme::Secp256k1,
+            SignatureScheme::Ed25519 => protocol::types::SignatureScheme::Ed25519,
+        }
+    }
+}
+
 impl From<protocol::types::SignedTransaction> for SignedTransaction {
     fn from(stx: protocol::types::SignedTransaction) -> Self {
         Self {
@@ -29,6 +109,7 @@ impl From<protocol::types::SignedTransaction> for SignedTransaction {
             method:       stx.raw.request.method,
             payload:      stx.raw.request.payload,
             tx_hash:      Hash::from(stx.tx_hash),
+            scheme:       SignatureScheme::from(stx.scheme),
             pubkey:       Bytes::from(stx.pubkey),
             signature:    Bytes::from(stx.signature),
         }
@@ -39,7 +120,8 @@ impl From<protocol::types::SignedTransaction> for SignedTransaction {
 // GraphQLInputObject
 // #####################
 
-#[derive(juniper::GraphQLInputObject, Clone)]
+#[derive(juniper::GraphQLInputObject, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
 #[graphql(description = "There was many types of transaction in Muta, \
                          A transaction often require computing resources or write data to chain,\
                          these resources are valuable so we need to pay some token for them.\
@@ -68,15 +150,26 @@ pub struct InputRawTransaction {
     pub payload:      String,
 }
 
-#[derive(juniper::GraphQLInputObject, Clone)]
+#[derive(juniper::GraphQLInputObject, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
 #[graphql(description = "Signature of the transaction")]
 pub struct InputTransactionEncryption {
     #[graphql(description = "The digest of the transaction")]
     pub tx_hash:   Hash,
-    #[graphql(description = "The public key of transfer")]
+    #[graphql(description = "The public key of transfer, or the multisig public key in rlp")]
     pub pubkey:    Bytes,
     #[graphql(description = "The signature of the transaction")]
     pub signature: Bytes,
+    #[graphql(description = "The signature scheme, secp256k1 if not set")]
+    pub scheme:    Option<SignatureScheme>,
+}
+
+#[derive(juniper::GraphQLInputObject, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[graphql(description = "A signed transaction in a batch")]
+pub struct InputSignedTransaction {
+    pub input_raw:        InputRawTransaction,
+    pub input_encryption: InputTransactionEncryption,
 }
 
 pub fn to_signed_transaction(
@@ -90,6 +183,7 @@ pub fn to_signed_transaction(
     Ok(protocol::types::SignedTransaction {
         raw:       to_transaction(raw)?,
         tx_hash:   protocol::types::Hash::from_hex(&encryption.tx_hash.as_hex())?,
+        scheme:    encryption.scheme.map(Into::into).unwrap_or_default(),
         pubkey:    bytes::BytesMut::from(pubkey).freeze(),
         signature: bytes::BytesMut::from(signature).freeze(),
     })
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use actix::{Actor, ActorContext, AsyncContext, StreamHandler};
+use actix_web_actors::ws;
+use juniper::http::GraphQLRequest;
+use juniper::{EmptyMutation, FieldResult, InputValue};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use common_pubsub::{ChainEvent, PubSub};
+
+use crate::limit::QueryLimit;
+use crate::schema::{Block, Event, Hash, Receipt};
+
+// The sub-protocol spoken by apollo and most other GraphQL clients over
+// WebSocket.
+pub const GRAPHQL_WS_PROTOCOL: &str = "graphql-ws";
+
+// Every chain event is run through the subscription documents of a session.
+// A field resolves to null when the event does not concern it.
+pub struct SubscriptionContext {
+    event: Option<ChainEvent>,
+}
+
+pub struct Subscription;
+
+#[juniper::graphql_object(Context = SubscriptionContext)]
+impl Subscription {
+    #[graphql(name = "newBlock", description = "Emit the block once it is committed")]
+    fn new_block(ctx: &SubscriptionContext) -> Option<Block> {
+        match &ctx.event {
+            Some(ChainEvent::NewBlock(block)) => Some(Block::from(block.clone())),
+            _ => None,
+        }
+    }
+
+    #[graphql(
+        name = "receipt",
+        description = "Emit the receipt once the transaction is executed"
+    )]
+    fn receipt(ctx: &SubscriptionContext, tx_hash: Hash) -> FieldResult<Option<Receipt>> {
+        let hash = protocol::types::Hash::from_hex(&tx_hash.as_hex())?;
+
+        let receipt = match &ctx.event {
+            Some(ChainEvent::NewReceipts(receipts)) => receipts
+                .iter()
+                .find(|receipt| receipt.tx_hash == hash)
+                .cloned()
+                .map(Receipt::from),
+            _ => None,
+        };
+        Ok(receipt)
+    }
+
+    #[graphql(
+        name = "events",
+        description = "Emit the events of the service once a block is executed"
+    )]
+    fn events(ctx: &SubscriptionContext, service: String) -> Option<Vec<Event>> {
+        let receipts = match &ctx.event {
+            Some(ChainEvent::NewReceipts(receipts)) => receipts,
+            _ => return None,
+        };
+
+        let events = receipts
+            .iter()
+            .flat_map(|receipt| receipt.events.iter())
+            .filter(|event| event.service == service)
+            .cloned()
+            .map(Event::from)
+            .collect::<Vec<_>>();
+
+        if events.is_empty() {
+            None
+        } else {
+            Some(events)
+        }
+    }
+}
+
+pub type SubscriptionSchema =
+    juniper::RootNode<'static, Subscription, EmptyMutation<SubscriptionContext>>;
+
+pub fn new_subscription_schema() -> SubscriptionSchema {
+    SubscriptionSchema::new(Subscription, EmptyMutation::new())
+}
+
+#[derive(Deserialize)]
+struct ClientMessage {
+    #[serde(rename = "type")]
+    ty:      String,
+    id:      Option<String>,
+    payload: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct StartPayload {
+    query:          String,
+    #[serde(rename = "operationName")]
+    operation_name: Option<String>,
+    variables:      Option<InputValue>,
+}
+
+pub struct SubscriptionSession {
+    schema:      Arc<SubscriptionSchema>,
+    event_bus:   Arc<PubSub<ChainEvent>>,
+    query_limit: Arc<QueryLimit>,
+
+    subscriptions: HashMap<String, GraphQLRequest>,
+}
+
+impl SubscriptionSession {
+    pub fn new(
+        schema: Arc<SubscriptionSchema>,
+        event_bus: Arc<PubSub<ChainEvent>>,
+        query_limit: Arc<QueryLimit>,
+    ) -> Self {
+        SubscriptionSession {
+            schema,
+            event_bus,
+            query_limit,
+            subscriptions: HashMap::new(),
+        }
+    }
+
+    fn handle_client_message(&mut self, text: &str, ctx: &mut ws::WebsocketContext<Self>) {
+        let msg: ClientMessage = match serde_json::from_str(text) {
+            Ok(msg) => msg,
+            Err(e) => {
+                let err =
+                    json!({"type": "connection_error", "payload": {"message": e.to_string()}});
+                ctx.text(err.to_string());
+                return;
+            }
+        };
+
+        match (msg.ty.as_str(), msg.id) {
+            ("connection_init", _) => ctx.text(json!({"type": "connection_ack"}).to_string()),
+            ("connection_terminate", _) => ctx.stop(),
+            ("start", Some(id)) => match self.start(msg.payload) {
+                Ok(request) => {
+                    self.subscriptions.insert(id, request);
+                }
+                Err(payload) => {
+                    ctx.text(json!({"type": "error", "id": id, "payload": payload}).to_string())
+                }
+            },
+            ("stop", Some(id)) => {
+                self.subscriptions.remove(&id);
+                ctx.text(json!({"type": "complete", "id": id}).to_string());
+            }
+            (ty, _) => {
+                let err = json!({
+                    "type": "connection_error",
+                    "payload": {"message": format!("unexpected message {}", ty)}
+                });
+                ctx.text(err.to_string());
+            }
+        }
+    }
+
+    // Check the limits of the subscription document and run it once without
+    // any event, so that syntax and validation errors are reported to the
+    // client up front.
+    fn start(&self, payload: Option<Value>) -> Result<GraphQLRequest, Value> {
+        let payload: StartPayload = serde_json::from_value(payload.unwrap_or(Value::Null))
+            .map_err(|e| json!([{ "message": e.to_string() }]))?;
+
+        let query = as_query_operation(&payload.query);
+        self.query_limit
+            .check(&query, &self.schema.schema)
+            .map_err(|e| json!([{ "message": e.to_string() }]))?;
+
+        let request = GraphQLRequest::new(query, payload.operation_name, payload.variables);
+
+        let resp = request.execute(&self.schema, &SubscriptionContext { event: None });
+        if !resp.is_ok() {
+            let resp = serde_json::to_value(&resp).unwrap_or(Value::Null);
+            return Err(resp.get("errors").cloned().unwrap_or(resp));
+        }
+
+        Ok(request)
+    }
+}
+
+impl Actor for SubscriptionSession {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        // The event stream ends if this session lags too far behind, which
+        // stops the session.
+        ctx.add_stream(self.event_bus.subscribe());
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for SubscriptionSession {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => ctx.pong(&msg),
+            Ok(ws::Message::Text(text)) => self.handle_client_message(&text, ctx),
+            Ok(ws::Message::Close(_)) | Err(_) => ctx.stop(),
+            _ => (),
+        }
+    }
+}
+
+impl StreamHandler<ChainEvent> for SubscriptionSession {
+    fn handle(&mut self, event: ChainEvent, ctx: &mut Self::Context) {
+        let sub_ctx = SubscriptionContext { event: Some(event) };
+
+        for (id, request) in self.subscriptions.iter() {
+            let resp = request.execute(&self.schema, &sub_ctx);
+            let payload = match serde_json::to_value(&resp) {
+                Ok(payload) => payload,
+                Err(_) => continue,
+            };
+
+            if is_empty_response(&payload) {
+                continue;
+            }
+
+            ctx.text(json!({"type": "data", "id": id, "payload": payload}).to_string());
+        }
+    }
+}
+
+// Juniper does not know the subscription operation type, documents are
+// executed as queries against the `Subscription` root instead.
+fn as_query_operation(query: &str) -> String {
+    let query = query.trim_start();
+
+    if query.starts_with("subscription") {
+        format!("query{}", &query["subscription".len()..])
+    } else {
+        query.to_owned()
+    }
+}
+
+fn is_empty_response(resp: &Value) -> bool {
+    resp.get("errors").is_none()
+        && resp
+            .get("data")
+            .and_then(Value::as_object)
+            .map(|data| data.values().all(Value::is_null))
+            .unwrap_or(false)
+}
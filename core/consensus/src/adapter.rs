@@ -12,6 +12,7 @@ use tokio::sync::mpsc::error::TrySendError;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 
 use common_merkle::Merkle;
+use common_pubsub::{ChainEvent, PubSub};
 
 use protocol::traits::{
     CommonConsensusAdapter, ConsensusAdapter, Context, ExecutorFactory, ExecutorParams,
@@ -59,6 +60,7 @@ pub struct OverlordConsensusAdapter<
     exec_queue:  Sender<ExecuteInfo>,
     exec_demons: Option<ExecDemons<S, DB, EF, Mapping>>,
     crypto:      Arc<OverlordCrypto>,
+    event_bus:   Arc<PubSub<ChainEvent>>,
 }
 
 #[async_trait]
@@ -346,7 +348,9 @@ where
 {
     /// Save a block to the database.
     async fn save_block(&self, _: Context, block: Block) -> ProtocolResult<()> {
-        self.storage.insert_block(block).await
+        self.storage.insert_block(block.clone()).await?;
+        self.event_bus.publish(ChainEvent::NewBlock(block));
+        Ok(())
     }
 
     async fn save_proof(&self, _: Context, proof: Proof) -> ProtocolResult<()> {
@@ -363,7 +367,9 @@ where
     }
 
     async fn save_receipts(&self, _: Context, receipts: Vec<Receipt>) -> ProtocolResult<()> {
-        self.storage.insert_receipts(receipts).await
+        self.storage.insert_receipts(receipts.clone()).await?;
+        self.event_bus.publish(ChainEvent::NewReceipts(receipts));
+        Ok(())
     }
 
     /// Flush the given transactions in the mempool.
@@ -418,6 +424,7 @@ where
             height,
             timestamp,
             cycles_limit: u64::max_value(),
+            proposer: caller.clone(),
         };
         let exec_resp = executor.read(&params, &caller, 1, &TransactionRequest {
             service_name: "metadata".to_string(),
@@ -718,6 +725,7 @@ where
         service_mapping: Arc<Mapping>,
         status_agent: StatusAgent,
         crypto: Arc<OverlordCrypto>,
+        event_bus: Arc<PubSub<ChainEvent>>,
     ) -> ProtocolResult<Self> {
         let (exec_queue, rx) = channel(OVERLORD_GAP);
         let exec_demons = Some(ExecDemons::new(
@@ -726,6 +734,7 @@ where
             Arc::clone(&service_mapping),
             rx,
             status_agent,
+            Arc::clone(&event_bus),
         ));
 
         let adapter = OverlordConsensusAdapter {
@@ -739,6 +748,7 @@ where
             exec_queue,
             exec_demons,
             crypto,
+            event_bus,
         };
 
         Ok(adapter)
@@ -754,15 +764,15 @@ where
     }
 }
 
-#[derive(Debug)]
 pub struct ExecDemons<S, DB, EF, Mapping> {
     storage:         Arc<S>,
     trie_db:         Arc<DB>,
     service_mapping: Arc<Mapping>,
 
-    pin_ef: PhantomData<EF>,
-    queue:  Receiver<ExecuteInfo>,
-    status: StatusAgent,
+    pin_ef:    PhantomData<EF>,
+    queue:     Receiver<ExecuteInfo>,
+    status:    StatusAgent,
+    event_bus: Arc<PubSub<ChainEvent>>,
 }
 
 impl<S, DB, EF, Mapping> ExecDemons<S, DB, EF, Mapping>
@@ -778,6 +788,7 @@ where
         service_mapping: Arc<Mapping>,
         rx: Receiver<ExecuteInfo>,
         status_agent: StatusAgent,
+        event_bus: Arc<PubSub<ChainEvent>>,
     ) -> Self {
         ExecDemons {
             storage,
@@ -786,6 +797,7 @@ where
             queue: rx,
             pin_ef: PhantomData,
             status: status_agent,
+            event_bus,
         }
     }
 
@@ -816,6 +828,7 @@ where
                 height,
                 timestamp: info.timestamp,
                 cycles_limit: info.cycles_limit,
+                proposer: info.coinbase.clone(),
             };
             let resp = executor.exec(&exec_params, &txs)?;
             log::info!(
@@ -840,7 +853,9 @@ where
     }
 
     async fn save_receipts(&self, receipts: Vec<Receipt>) -> ProtocolResult<()> {
-        self.storage.insert_receipts(receipts).await
+        self.storage.insert_receipts(receipts.clone()).await?;
+        self.event_bus.publish(ChainEvent::NewReceipts(receipts));
+        Ok(())
     }
 }
 
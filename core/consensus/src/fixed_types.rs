@@ -254,6 +254,7 @@ mod test {
         SignedTransaction {
             raw,
             tx_hash,
+            scheme: Default::default(),
             pubkey: Bytes::from(gen_random_bytes(32)),
             signature: Bytes::from(gen_random_bytes(64)),
         }
@@ -1,3 +1,4 @@
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -26,11 +27,13 @@ pub struct RichBlock {
 }
 
 pub struct OverlordSynchronization<Adapter: SynchronizationAdapter> {
-    adapter: Arc<Adapter>,
-    status:  StatusAgent,
-    crypto:  Arc<OverlordCrypto>,
-    lock:    Arc<Mutex<()>>,
-    syncing: Mutex<()>,
+    adapter:    Arc<Adapter>,
+    status:     StatusAgent,
+    crypto:     Arc<OverlordCrypto>,
+    lock:       Arc<Mutex<()>>,
+    syncing:    Mutex<()>,
+    // Set only while blocks are being pulled and executed.
+    is_syncing: AtomicBool,
 
     sync_txs_chunk_size: usize,
 }
@@ -66,6 +69,7 @@ impl<Adapter: SynchronizationAdapter> Synchronization for OverlordSynchronizatio
         );
 
         let sync_status_agent = self.init_status_agent().await?;
+        self.is_syncing.store(true, Ordering::SeqCst);
         let sync_resp = self
             .start_sync(
                 ctx.clone(),
@@ -74,6 +78,7 @@ impl<Adapter: SynchronizationAdapter> Synchronization for OverlordSynchronizatio
                 remote_height,
             )
             .await;
+        self.is_syncing.store(false, Ordering::SeqCst);
         let sync_status = sync_status_agent.to_inner();
 
         if let Err(e) = sync_resp {
@@ -106,6 +111,10 @@ impl<Adapter: SynchronizationAdapter> Synchronization for OverlordSynchronizatio
         );
         Ok(())
     }
+
+    fn is_syncing(&self) -> bool {
+        self.is_syncing.load(Ordering::SeqCst)
+    }
 }
 
 impl<Adapter: SynchronizationAdapter> OverlordSynchronization<Adapter> {
@@ -124,6 +133,7 @@ impl<Adapter: SynchronizationAdapter> OverlordSynchronization<Adapter> {
             crypto,
             lock,
             syncing,
+            is_syncing: AtomicBool::new(false),
 
             sync_txs_chunk_size,
         }
@@ -416,6 +426,7 @@ impl<Adapter: SynchronizationAdapter> OverlordSynchronization<Adapter> {
             height: rich_block.block.header.height,
             timestamp: rich_block.block.header.timestamp,
             cycles_limit,
+            proposer: rich_block.block.header.proposer.clone(),
         };
         let resp = self.adapter.sync_exec(ctx, &exec_params, &rich_block.txs)?;
 
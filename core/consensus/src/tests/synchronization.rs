@@ -18,8 +18,9 @@ use protocol::fixed_codec::FixedCodec;
 use protocol::traits::{CommonConsensusAdapter, Synchronization, SynchronizationAdapter};
 use protocol::traits::{Context, ExecutorParams, ExecutorResp, ServiceResponse};
 use protocol::types::{
-    Address, Block, BlockHeader, Bytes, Hash, Hex, MerkleRoot, Metadata, Proof, RawTransaction,
-    Receipt, ReceiptResponse, SignedTransaction, TransactionRequest, Validator, ValidatorExtend,
+    Address, Admission, Block, BlockHeader, Bytes, Hash, Hex, MerkleRoot, Metadata, Proof,
+    RawTransaction, Receipt, ReceiptResponse, SignedTransaction, TransactionRequest, Validator,
+    ValidatorExtend,
 };
 use protocol::ProtocolResult;
 
@@ -298,6 +299,9 @@ impl CommonConsensusAdapter for MockCommonConsensusAdapter {
             brake_ratio:     10,
             tx_num_limit:    20000,
             max_tx_size:     1_073_741_824,
+            fee_asset_id:    None,
+            fee_treasury:    None,
+            admission:       Admission::default(),
         })
     }
 
@@ -752,6 +756,7 @@ fn get_receipt(tx: &SignedTransaction, height: u64) -> Receipt {
         height,
         tx_hash: tx.tx_hash.clone(),
         cycles_used: tx.raw.cycles_limit,
+        fee: 0,
         events: vec![],
         response: ReceiptResponse {
             service_name: "sync".to_owned(),
@@ -802,6 +807,7 @@ fn mock_tx_list(num: usize, height: u64) -> Vec<SignedTransaction> {
         let signed_tx = SignedTransaction {
             raw,
             tx_hash,
+            scheme: Default::default(),
             pubkey: test_pubkey.to_bytes(),
             signature: signature.to_bytes(),
         };
@@ -133,6 +133,7 @@ mod test {
         SignedTransaction {
             raw:       mock_raw_tx(),
             tx_hash:   mock_hash(),
+            scheme:    Default::default(),
             pubkey:    Default::default(),
             signature: Default::default(),
         }
@@ -8,6 +8,7 @@ use protocol::{
 };
 use serde_derive::{Deserialize, Serialize};
 
+use crate::adapter::verifier::SigVerifier;
 use crate::context::TxContext;
 
 pub const END_GOSSIP_NEW_TXS: &str = "/gossip/mempool/new_txs";
@@ -24,6 +25,8 @@ pub struct MsgNewTxs {
 
 pub struct NewTxsHandler<M> {
     mem_pool: Arc<M>,
+    // Batches are dropped while its queue is full.
+    verifier: Option<SigVerifier>,
 }
 
 impl<M> NewTxsHandler<M>
@@ -31,7 +34,15 @@ where
     M: MemPool,
 {
     pub fn new(mem_pool: Arc<M>) -> Self {
-        NewTxsHandler { mem_pool }
+        NewTxsHandler {
+            mem_pool,
+            verifier: None,
+        }
+    }
+
+    pub fn with_sig_verifier(mut self, verifier: SigVerifier) -> Self {
+        self.verifier = Some(verifier);
+        self
     }
 }
 
@@ -43,6 +54,15 @@ where
     type Message = MsgNewTxs;
 
     async fn process(&self, ctx: Context, msg: Self::Message) {
+        if let Some(verifier) = self.verifier.as_ref().filter(|v| v.is_full()) {
+            log::warn!(
+                "[core_mempool] signature verifier is full, drop {} txs, pending {}",
+                msg.batch_stxs.len(),
+                verifier.pending()
+            );
+            return;
+        }
+
         let ctx = ctx.mark_network_origin_new_txs();
 
         let insert_stx = |stx| -> _ {
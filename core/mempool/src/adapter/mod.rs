@@ -1,8 +1,12 @@
 pub mod message;
+pub mod verifier;
 
 use std::{
+    cell::RefCell,
+    collections::HashMap,
     error::Error,
     marker::PhantomData,
+    rc::Rc,
     sync::atomic::{AtomicU64, Ordering},
     sync::Arc,
     time::Duration,
@@ -20,18 +24,27 @@ use futures::{
 };
 use futures_timer::Delay;
 use log::{debug, error};
+use serde_derive::Deserialize;
 
-use common_crypto::Crypto;
 use protocol::{
     fixed_codec::FixedCodec,
-    traits::{Context, Gossip, MemPoolAdapter, Priority, Rpc, Storage},
-    types::{Hash, SignedTransaction},
+    traits::{
+        AdmissionControl, Context, Executor, ExecutorFactory, ExecutorParams, Gossip,
+        MemPoolAdapter, PeerTrust, Priority, Rpc, ServiceMapping, Storage,
+    },
+    types::{
+        Address, Block, Hash, MerkleRoot, Metadata, ServiceContext, ServiceContextParams,
+        SignedTransaction, TransactionRequest,
+    },
     ProtocolError, ProtocolErrorKind, ProtocolResult,
 };
 
 use crate::adapter::message::{
     MsgNewTxs, MsgPullTxs, MsgPushTxs, END_GOSSIP_NEW_TXS, RPC_PULL_TXS, RPC_PULL_TXS_SYNC,
 };
+use crate::adapter::verifier::SigVerifier;
+use crate::context::TxContext;
+use crate::tx_cache::max_fee;
 use crate::MemPoolError;
 
 pub const DEFAULT_BROADCAST_TXS_SIZE: usize = 200;
@@ -130,7 +143,27 @@ impl IntervalTxsBroadcaster {
     }
 }
 
-pub struct DefaultMemPoolAdapter<C, N, S> {
+#[derive(Deserialize)]
+struct BalanceResponse {
+    balance: u64,
+}
+
+// Settings of the chain read from the state of a block.
+struct ChainConfig {
+    // Fee asset and treasury, none if fee is disabled.
+    fee:       Option<(Hash, Option<Address>)>,
+    // Checked again by the executor before dispatching the transaction.
+    admission: Vec<Arc<dyn AdmissionControl>>,
+}
+
+// Chain settings and balances read from the state of a block.
+struct BlockState {
+    state_root: MerkleRoot,
+    config:     Arc<ChainConfig>,
+    balances:   HashMap<Address, u64>,
+}
+
+pub struct DefaultMemPoolAdapter<N, S, DB, Mapping, EF> {
     network: N,
     storage: Arc<S>,
 
@@ -141,18 +174,27 @@ pub struct DefaultMemPoolAdapter<C, N, S> {
     stx_tx: UnboundedSender<SignedTransaction>,
     err_rx: Mutex<UnboundedReceiver<ProtocolError>>,
 
-    pin_c: PhantomData<C>,
+    // Signatures and checks against the state are run on its pool.
+    verifier:           SigVerifier,
+    // Admission controls of this node, only checked in the mempool.
+    admission_controls: Vec<Arc<dyn AdmissionControl>>,
+    state_checker:      Arc<StateChecker<S, DB, Mapping, EF>>,
 }
 
-impl<C, N, S> DefaultMemPoolAdapter<C, N, S>
+impl<N, S, DB, Mapping, EF> DefaultMemPoolAdapter<N, S, DB, Mapping, EF>
 where
-    C: Crypto,
     N: Rpc + Gossip + Clone + Unpin + 'static,
     S: Storage,
+    DB: cita_trie::DB,
+    Mapping: ServiceMapping,
+    EF: ExecutorFactory<DB, S, Mapping>,
 {
     pub fn new(
         network: N,
         storage: Arc<S>,
+        trie_db: Arc<DB>,
+        service_mapping: Arc<Mapping>,
+        verifier: SigVerifier,
         broadcast_txs_size: usize,
         broadcast_txs_interval: u64,
     ) -> Self {
@@ -173,6 +215,16 @@ where
             err_tx,
         ));
 
+        let state_checker = StateChecker {
+            storage: Arc::clone(&storage),
+            trie_db,
+            service_mapping,
+
+            block_state: parking_lot::Mutex::new(None),
+
+            pin_ef: PhantomData,
+        };
+
         DefaultMemPoolAdapter {
             network,
             storage,
@@ -184,17 +236,251 @@ where
             stx_tx,
             err_rx: Mutex::new(err_rx),
 
-            pin_c: PhantomData,
+            verifier,
+            admission_controls: vec![],
+            state_checker: Arc::new(state_checker),
+        }
+    }
+
+    pub fn with_admission_controls(mut self, controls: Vec<Arc<dyn AdmissionControl>>) -> Self {
+        self.admission_controls = controls;
+        self
+    }
+}
+
+// Checks of transactions against the state of the latest block.
+struct StateChecker<S, DB, Mapping, EF> {
+    storage:         Arc<S>,
+    trie_db:         Arc<DB>,
+    service_mapping: Arc<Mapping>,
+
+    // Read from the latest block, replaced once a new block is executed.
+    block_state: parking_lot::Mutex<Option<BlockState>>,
+
+    pin_ef: PhantomData<EF>,
+}
+
+impl<S, DB, Mapping, EF> StateChecker<S, DB, Mapping, EF>
+where
+    S: Storage,
+    DB: cita_trie::DB,
+    Mapping: ServiceMapping,
+    EF: ExecutorFactory<DB, S, Mapping>,
+{
+    // Run the admission controls of the chain, which the executor checks
+    // again before dispatching the transaction, then the given ones of this
+    // node.
+    fn check_admission(
+        &self,
+        block: &Block,
+        stx: &SignedTransaction,
+        node_controls: &[Arc<dyn AdmissionControl>],
+    ) -> ProtocolResult<()> {
+        let mut controls = self.chain_config(block)?.admission.clone();
+        controls.extend(node_controls.iter().cloned());
+        if controls.is_empty() {
+            return Ok(());
+        }
+
+        let ctx = ServiceContext::new(ServiceContextParams {
+            tx_hash:         Some(stx.tx_hash.clone()),
+            nonce:           Some(stx.raw.nonce.clone()),
+            cycles_limit:    stx.raw.cycles_limit,
+            cycles_price:    stx.raw.cycles_price,
+            cycles_used:     Rc::new(RefCell::new(0)),
+            caller:          stx.sender()?,
+            height:          block.header.height + 1,
+            service_name:    stx.raw.request.service_name.clone(),
+            service_method:  stx.raw.request.method.clone(),
+            service_payload: stx.raw.request.payload.clone(),
+            extra:           None,
+            timestamp:       block.header.timestamp,
+            events:          Rc::new(RefCell::new(vec![])),
+        });
+
+        controls.iter().try_for_each(|control| control.next(&ctx))
+    }
+
+    // Make sure the sender can afford the max fee of the transaction and its
+    // pending ones, if transaction fee is enabled. Balance is read from the
+    // state of the latest executed block, once per block and sender.
+    // Transactions pulled for consensus are not checked, the block may be
+    // executed on a state this node hasn't reached yet, the executor rejects
+    // them if the fee can't be charged.
+    fn check_balance(
+        &self,
+        ctx: &Context,
+        block: &Block,
+        stx: &SignedTransaction,
+    ) -> ProtocolResult<()> {
+        if ctx.is_pulled_txs() {
+            return Ok(());
+        }
+
+        let sender = stx.sender()?;
+        let config = self.chain_config(block)?;
+        let (asset_id, treasury) = match &config.fee {
+            Some(fee) => fee,
+            None => return Ok(()),
+        };
+        // Like the executor, nothing is charged if the sender is the
+        // recipient. Without a treasury the recipient is the proposer, which
+        // is unknown until the transaction is proposed.
+        if treasury.as_ref() == Some(&sender) {
+            return Ok(());
+        }
+
+        let balance = self.balance(block, asset_id, &sender)?;
+        let max_fee = max_fee(stx);
+        let pending_fee = ctx.pending_fee();
+        if balance < max_fee.saturating_add(pending_fee) {
+            return Err(MemPoolError::InsufficientBalance {
+                tx_hash: stx.tx_hash.clone(),
+                balance,
+                max_fee,
+                pending_fee,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    // The block state is not locked while reading the state of the block, a
+    // new one is swapped in once the block changes.
+    fn chain_config(&self, block: &Block) -> ProtocolResult<Arc<ChainConfig>> {
+        let state_root = &block.header.state_root;
+        {
+            let block_state = self.block_state.lock();
+            if let Some(state) = block_state.as_ref() {
+                if &state.state_root == state_root {
+                    return Ok(Arc::clone(&state.config));
+                }
+            }
+        }
+
+        let config = Arc::new(self.read_chain_config(block)?);
+        let mut block_state = self.block_state.lock();
+        if block_state.as_ref().map(|state| &state.state_root) != Some(state_root) {
+            *block_state = Some(BlockState {
+                state_root: state_root.clone(),
+                config:     Arc::clone(&config),
+                balances:   HashMap::new(),
+            });
+        }
+        Ok(config)
+    }
+
+    fn balance(&self, block: &Block, asset_id: &Hash, user: &Address) -> ProtocolResult<u64> {
+        let state_root = &block.header.state_root;
+        {
+            let block_state = self.block_state.lock();
+            if let Some(state) = block_state.as_ref() {
+                if &state.state_root == state_root {
+                    if let Some(balance) = state.balances.get(user) {
+                        return Ok(*balance);
+                    }
+                }
+            }
+        }
+
+        let balance = self.read_balance(block, asset_id, user)?;
+        if let Some(state) = self.block_state.lock().as_mut() {
+            if &state.state_root == state_root {
+                state.balances.insert(user.clone(), balance);
+            }
         }
+        Ok(balance)
+    }
+
+    fn read_chain_config(&self, block: &Block) -> ProtocolResult<ChainConfig> {
+        let executor = self.executor(block)?;
+        let metadata: Metadata = serde_json::from_str(&self.read_state(
+            &*executor,
+            block,
+            "metadata",
+            "get_metadata",
+            "".to_owned(),
+        )?)
+        .map_err(MemPoolError::DecodeState)?;
+
+        let treasury = metadata.fee_treasury;
+        Ok(ChainConfig {
+            fee:       metadata.fee_asset_id.map(|asset_id| (asset_id, treasury)),
+            admission: executor.admission_controls()?,
+        })
+    }
+
+    fn read_balance(&self, block: &Block, asset_id: &Hash, user: &Address) -> ProtocolResult<u64> {
+        let payload = format!(
+            r#"{{"asset_id": "{}", "user": "{}"}}"#,
+            asset_id.as_hex(),
+            user.as_hex()
+        );
+        let executor = self.executor(block)?;
+        let resp: BalanceResponse = serde_json::from_str(&self.read_state(
+            &*executor,
+            block,
+            "asset",
+            "get_balance",
+            payload,
+        )?)
+        .map_err(MemPoolError::DecodeState)?;
+
+        Ok(resp.balance)
+    }
+
+    fn executor(&self, block: &Block) -> ProtocolResult<Box<dyn Executor>> {
+        EF::from_root(
+            block.header.state_root.clone(),
+            Arc::clone(&self.trie_db),
+            Arc::clone(&self.storage),
+            Arc::clone(&self.service_mapping),
+        )
+    }
+
+    fn read_state(
+        &self,
+        executor: &dyn Executor,
+        block: &Block,
+        service: &str,
+        method: &str,
+        payload: String,
+    ) -> ProtocolResult<String> {
+        let params = ExecutorParams {
+            state_root:   block.header.state_root.clone(),
+            height:       block.header.height,
+            timestamp:    block.header.timestamp,
+            cycles_limit: std::u64::MAX,
+            proposer:     block.header.proposer.clone(),
+        };
+        let request = TransactionRequest {
+            service_name: service.to_owned(),
+            method: method.to_owned(),
+            payload,
+        };
+
+        let resp = executor.read(&params, &block.header.proposer, 1, &request)?;
+        if resp.is_error() {
+            return Err(MemPoolError::ReadState {
+                service: request.service_name,
+                method:  request.method,
+                msg:     resp.error_message,
+            }
+            .into());
+        }
+        Ok(resp.succeed_data)
     }
 }
 
 #[async_trait]
-impl<C, N, S> MemPoolAdapter for DefaultMemPoolAdapter<C, N, S>
+impl<N, S, DB, Mapping, EF> MemPoolAdapter for DefaultMemPoolAdapter<N, S, DB, Mapping, EF>
 where
-    C: Crypto + Send + Sync + 'static,
-    N: Rpc + Gossip + Clone + Unpin + 'static,
+    N: Rpc + PeerTrust + Gossip + Clone + Unpin + 'static,
     S: Storage + 'static,
+    DB: cita_trie::DB + 'static,
+    Mapping: ServiceMapping + 'static,
+    EF: ExecutorFactory<DB, S, Mapping> + 'static,
 {
     async fn pull_txs(
         &self,
@@ -242,37 +528,41 @@ where
         Ok(())
     }
 
-    async fn check_signature(&self, _ctx: Context, tx: SignedTransaction) -> ProtocolResult<()> {
-        let hash = tx.tx_hash.as_bytes();
-        let pub_key = tx.pubkey.as_ref();
-        let sig = tx.signature.as_ref();
-
-        C::verify_signature(hash.as_ref(), sig, pub_key).map_err(|_| {
-            MemPoolError::CheckSig {
-                tx_hash: tx.tx_hash,
-            }
-            .into()
-        })
-    }
-
-    // TODO: Verify Fee?
-    // TODO: Verify Nonce?
-    // TODO: Cycle limit?
-    async fn check_transaction(&self, _ctx: Context, stx: SignedTransaction) -> ProtocolResult<()> {
+    // A transaction failing these checks is malformed or of another chain,
+    // other checks may fail because of state lag between honest nodes.
+    async fn check_signature(&self, ctx: Context, tx: SignedTransaction) -> ProtocolResult<()> {
         // Verify transaction hash
-        let fixed_bytes = stx.raw.encode_fixed()?;
-        let size = fixed_bytes.len() as u64;
-        let tx_hash = Hash::digest(fixed_bytes);
-
-        if tx_hash != stx.tx_hash {
+        let tx_hash = Hash::digest(tx.raw.encode_fixed()?);
+        if tx_hash != tx.tx_hash {
             let wrong_hash = MemPoolError::CheckHash {
-                expect: stx.tx_hash,
+                expect: tx.tx_hash,
                 actual: tx_hash,
             };
 
             return Err(wrong_hash.into());
         }
 
+        // Verify chain id
+        let latest_block = self.storage.get_latest_block().await?;
+        if latest_block.header.chain_id != tx.raw.chain_id {
+            let wrong_chain_id = MemPoolError::WrongChain {
+                tx_hash: tx.tx_hash,
+            };
+
+            return Err(wrong_chain_id.into());
+        }
+
+        // Only transactions sent to us directly are rejected if busy.
+        let limited = !ctx.is_network_origin_txs() && !ctx.is_pulled_txs();
+        self.verifier.verify(&tx, limited).await
+    }
+
+    // TODO: Verify Nonce?
+    // TODO: Cycle limit?
+    async fn check_transaction(&self, ctx: Context, stx: SignedTransaction) -> ProtocolResult<()> {
+        let size = stx.raw.encode_fixed()?.len() as u64;
+        let tx_hash = stx.tx_hash.clone();
+
         // check tx size
         let max_tx_size = self.max_tx_size.load(Ordering::SeqCst);
         if size > max_tx_size {
@@ -296,17 +586,8 @@ where
             .into());
         }
 
-        // Verify chain id
-        let latest_block = self.storage.get_latest_block().await?;
-        if latest_block.header.chain_id != stx.raw.chain_id {
-            let wrong_chain_id = MemPoolError::WrongChain {
-                tx_hash: stx.tx_hash,
-            };
-
-            return Err(wrong_chain_id.into());
-        }
-
         // Verify timeout
+        let latest_block = self.storage.get_latest_block().await?;
         let latest_height = latest_block.header.height;
         let timeout_gap = self.timeout_gap.load(Ordering::SeqCst);
 
@@ -327,7 +608,22 @@ where
             return Err(timeout.into());
         }
 
-        Ok(())
+        // Verify admission and fee. They read the state, so run them on the
+        // pool of the verifier, checks of a batch insertion run in parallel.
+        // Transactions pulled for consensus are not checked by the admission
+        // controls of this node, other nodes may accept them.
+        let node_controls = if ctx.is_pulled_txs() {
+            vec![]
+        } else {
+            self.admission_controls.clone()
+        };
+        let checker = Arc::clone(&self.state_checker);
+        self.verifier
+            .spawn(move || {
+                checker.check_admission(&latest_block, &stx, &node_controls)?;
+                checker.check_balance(&ctx, &latest_block, &stx)
+            })
+            .await?
     }
 
     async fn check_storage_exist(&self, _ctx: Context, tx_hash: Hash) -> ProtocolResult<()> {
@@ -349,6 +645,11 @@ where
         Ok(height)
     }
 
+    fn report_invalid_tx(&self, ctx: Context, tx_hash: &Hash) {
+        self.network
+            .report_bad(ctx, &format!("invalid tx {:?}", tx_hash));
+    }
+
     fn set_args(&self, timeout_gap: u64, cycles_limit: u64, max_tx_size: u64) {
         self.timeout_gap.store(timeout_gap, Ordering::Relaxed);
         self.cycles_limit.store(cycles_limit, Ordering::Relaxed);
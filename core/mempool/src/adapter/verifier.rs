@@ -0,0 +1,285 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use futures::channel::oneshot;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+use common_crypto::{Crypto, Ed25519, Secp256k1};
+use protocol::types::{SignatureScheme, SignedTransaction, TxWitness};
+use protocol::{Bytes, ProtocolResult};
+
+use crate::MemPoolError;
+
+pub const DEFAULT_VERIFY_BATCH_SIZE: usize = 64;
+pub const DEFAULT_VERIFY_QUEUE_SIZE: usize = 20_000;
+
+struct VerifyRequest {
+    hash:    Bytes,
+    scheme:  SignatureScheme,
+    witness: TxWitness,
+    resp:    oneshot::Sender<bool>,
+}
+
+/// Verify the signature of a single key, or every signature of a multisig
+/// account, with the crypto of the scheme. The number of multisig signatures
+/// is checked on decoding the witness.
+pub fn verify_witness(scheme: SignatureScheme, hash: &[u8], witness: &TxWitness) -> bool {
+    match scheme {
+        SignatureScheme::Secp256k1 => verify_witness_with::<Secp256k1>(hash, witness),
+        SignatureScheme::Ed25519 => verify_witness_with::<Ed25519>(hash, witness),
+    }
+}
+
+fn verify_witness_with<C: Crypto>(hash: &[u8], witness: &TxWitness) -> bool {
+    match witness {
+        TxWitness::Single { pubkey, signature } => {
+            C::verify_signature(hash, signature.as_ref(), pubkey.as_ref()).is_ok()
+        }
+        TxWitness::MultiSig { pubkey, signatures } => {
+            signatures
+                .iter()
+                .all(|sig| match pubkey.pubkeys.get(sig.index as usize) {
+                    Some(pk) => {
+                        C::verify_signature(hash, sig.signature.as_ref(), pk.as_ref()).is_ok()
+                    }
+                    None => false,
+                })
+        }
+    }
+}
+
+/// Verifies transaction signatures on a dedicated thread pool.
+///
+/// Requests are collected in batches, and each batch is verified in parallel
+/// on the pool. At most `queue_size` requests are queued. If it is full,
+/// further limited ones fail immediately so that callers can back off, the
+/// others wait for room.
+///
+/// Other checks of transactions can be spawned on the pool too, so that the
+/// checks of concurrent insertions run in parallel.
+#[derive(Clone)]
+pub struct SigVerifier {
+    pool:       Arc<ThreadPool>,
+    req_tx:     Sender<VerifyRequest>,
+    // Number of requests not verified yet.
+    pending:    Arc<AtomicUsize>,
+    queue_size: usize,
+}
+
+impl SigVerifier {
+    /// Zero `workers` means one worker per CPU.
+    pub fn new(workers: usize, batch_size: usize, queue_size: usize) -> ProtocolResult<Self> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(workers)
+            .thread_name(|i| format!("mempool-verifier-{}", i))
+            .build()
+            .map_err(MemPoolError::BuildVerifier)?;
+        let pool = Arc::new(pool);
+
+        let (req_tx, req_rx) = channel(queue_size.max(1));
+        let pending = Arc::new(AtomicUsize::new(0));
+
+        tokio::spawn(Self::dispatch(
+            req_rx,
+            batch_size.max(1),
+            Arc::clone(&pool),
+            Arc::clone(&pending),
+        ));
+
+        Ok(SigVerifier {
+            pool,
+            req_tx,
+            pending,
+            queue_size,
+        })
+    }
+
+    /// Requests not `limited` wait until they are queued, they are used for
+    /// transactions required by consensus.
+    pub async fn verify(&self, stx: &SignedTransaction, limited: bool) -> ProtocolResult<()> {
+        let tx_hash = &stx.tx_hash;
+        let witness = stx.witness()?;
+        let busy = MemPoolError::VerifierBusy {
+            tx_hash:    tx_hash.clone(),
+            queue_size: self.queue_size,
+        };
+        if self.pending.fetch_add(1, Ordering::SeqCst) >= self.queue_size && limited {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+            return Err(busy.into());
+        }
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let req = VerifyRequest {
+            hash: tx_hash.as_bytes(),
+            scheme: stx.scheme,
+            witness,
+            resp: resp_tx,
+        };
+        let mut req_tx = self.req_tx.clone();
+        let sent = if limited {
+            req_tx.try_send(req).map_err(|e| match e {
+                TrySendError::Full(_) => busy,
+                TrySendError::Closed(_) => MemPoolError::VerifierDropped,
+            })
+        } else {
+            req_tx
+                .send(req)
+                .await
+                .map_err(|_| MemPoolError::VerifierDropped)
+        };
+        if let Err(e) = sent {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+            return Err(e.into());
+        }
+
+        match resp_rx.await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(MemPoolError::CheckSig {
+                tx_hash: tx_hash.clone(),
+            }
+            .into()),
+            Err(_) => Err(MemPoolError::VerifierDropped.into()),
+        }
+    }
+
+    /// Run `check` on the pool. It panics if `check` panics.
+    pub async fn spawn<T, F>(&self, check: F) -> ProtocolResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.pool.spawn(move || {
+            let _ = resp_tx.send(panic::catch_unwind(AssertUnwindSafe(check)));
+        });
+
+        match resp_rx.await {
+            Ok(Ok(ret)) => Ok(ret),
+            Ok(Err(e)) => panic::resume_unwind(e),
+            Err(_) => Err(MemPoolError::VerifierDropped.into()),
+        }
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    /// Whether `queue_size` requests are pending.
+    pub fn is_full(&self) -> bool {
+        self.pending() >= self.queue_size
+    }
+
+    async fn dispatch(
+        mut req_rx: Receiver<VerifyRequest>,
+        batch_size: usize,
+        pool: Arc<ThreadPool>,
+        pending: Arc<AtomicUsize>,
+    ) {
+        while let Some(req) = req_rx.recv().await {
+            let mut batch = Vec::with_capacity(batch_size);
+            batch.push(req);
+
+            // Take the queued requests, don't wait for more.
+            while batch.len() < batch_size {
+                match req_rx.try_recv() {
+                    Ok(req) => batch.push(req),
+                    Err(_) => break,
+                }
+            }
+
+            let pending = Arc::clone(&pending);
+            pool.spawn(move || {
+                batch.into_par_iter().for_each(|req| {
+                    let valid = verify_witness(req.scheme, req.hash.as_ref(), &req.witness);
+
+                    pending.fetch_sub(1, Ordering::SeqCst);
+                    // The caller may be gone, it's ok.
+                    let _ = req.resp.send(valid);
+                });
+            });
+        }
+
+        log::debug!("[core_mempool]: signature verifier dropped");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::join_all;
+
+    use protocol::types::SignatureScheme;
+
+    use crate::tests::{default_mock_txs, mock_ed25519_tx, mock_multisig_tx};
+
+    use super::SigVerifier;
+
+    #[tokio::test]
+    async fn test_verify() {
+        let verifier = SigVerifier::new(2, 4, 100).unwrap();
+
+        let txs = default_mock_txs(10);
+        let results = join_all(txs.iter().map(|tx| verifier.verify(tx, true))).await;
+        assert!(results.iter().all(|r| r.is_ok()));
+
+        let mut invalid_tx = txs[0].clone();
+        invalid_tx.signature = txs[1].signature.clone();
+        assert!(verifier.verify(&invalid_tx, true).await.is_err());
+        assert_eq!(verifier.pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_verify_multisig() {
+        let verifier = SigVerifier::new(2, 4, 100).unwrap();
+
+        let tx = mock_multisig_tx(2, 3, &[1, 2]);
+        assert!(verifier.verify(&tx, true).await.is_ok());
+        let tx = mock_multisig_tx(3, 3, &[0, 2]);
+        assert!(verifier.verify(&tx, true).await.is_err());
+
+        let mut invalid_tx = mock_multisig_tx(1, 2, &[0]);
+        invalid_tx.signature = mock_multisig_tx(1, 2, &[0]).signature;
+        assert!(verifier.verify(&invalid_tx, true).await.is_err());
+        assert_eq!(verifier.pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_verify_ed25519() {
+        let verifier = SigVerifier::new(2, 4, 100).unwrap();
+
+        let tx = mock_ed25519_tx(true);
+        assert!(verifier.verify(&tx, true).await.is_ok());
+        assert!(verifier
+            .verify(&mock_ed25519_tx(false), true)
+            .await
+            .is_err());
+
+        // Verified with the crypto of the scheme
+        let mut tx = default_mock_txs(1).remove(0);
+        tx.scheme = SignatureScheme::Ed25519;
+        assert!(verifier.verify(&tx, true).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_busy() {
+        let verifier = SigVerifier::new(1, 4, 0).unwrap();
+
+        let txs = default_mock_txs(1);
+        assert!(verifier.is_full());
+        assert!(verifier.verify(&txs[0], true).await.is_err());
+        assert_eq!(verifier.pending(), 0);
+        assert!(verifier.verify(&txs[0], false).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_spawn() {
+        let verifier = SigVerifier::new(2, 4, 100).unwrap();
+
+        let results = join_all((0..10).map(|i| verifier.spawn(move || i * 2))).await;
+        let results = results.into_iter().map(|r| r.unwrap()).collect::<Vec<_>>();
+        assert_eq!(results, (0..10).map(|i| i * 2).collect::<Vec<_>>());
+    }
+}
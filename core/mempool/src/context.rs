@@ -2,11 +2,24 @@ use protocol::traits::Context;
 
 const TXS_ORIGINAL_KEY: &str = "txs_original";
 const NETWORK_TXS: usize = 1;
+// Transactions pulled for consensus.
+const PULLED_TXS: usize = 2;
+const PENDING_FEE_KEY: &str = "pending_fee";
 
 pub(crate) trait TxContext {
     fn mark_network_origin_new_txs(&self) -> Self;
 
     fn is_network_origin_txs(&self) -> bool;
+
+    fn mark_pulled_txs(&self) -> Self;
+
+    fn is_pulled_txs(&self) -> bool;
+
+    /// Max fees of pending transactions from the same sender, which the
+    /// sender should afford along with the checked one.
+    fn with_pending_fee(&self, pending_fee: u64) -> Self;
+
+    fn pending_fee(&self) -> u64;
 }
 
 impl TxContext for Context {
@@ -17,4 +30,20 @@ impl TxContext for Context {
     fn is_network_origin_txs(&self) -> bool {
         self.get::<usize>(TXS_ORIGINAL_KEY) == Some(&NETWORK_TXS)
     }
+
+    fn mark_pulled_txs(&self) -> Self {
+        self.with_value::<usize>(TXS_ORIGINAL_KEY, PULLED_TXS)
+    }
+
+    fn is_pulled_txs(&self) -> bool {
+        self.get::<usize>(TXS_ORIGINAL_KEY) == Some(&PULLED_TXS)
+    }
+
+    fn with_pending_fee(&self, pending_fee: u64) -> Self {
+        self.with_value::<u64>(PENDING_FEE_KEY, pending_fee)
+    }
+
+    fn pending_fee(&self) -> u64 {
+        self.get::<u64>(PENDING_FEE_KEY).copied().unwrap_or(0)
+    }
 }
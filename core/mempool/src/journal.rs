@@ -0,0 +1,226 @@
+use std::fs;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use parking_lot::Mutex;
+
+use protocol::fixed_codec::FixedCodec;
+use protocol::types::SignedTransaction;
+use protocol::{Bytes, ProtocolResult};
+
+use crate::MemPoolError;
+
+const JOURNAL_FILE: &str = "txs.journal";
+// Compaction is skipped until there are enough stale records.
+const MIN_COMPACT_RECORDS: usize = 1024;
+
+/// An append-only file of transactions accepted by the mempool, so that they
+/// can be reloaded after restart.
+///
+/// Each record is a transaction in fixed codec prefixed with its length in
+/// big-endian u32. Records of committed or dropped transactions are only
+/// removed by compaction.
+pub struct TxJournal {
+    path:    PathBuf,
+    file:    Mutex<fs::File>,
+    // Number of records in the file, including stale ones.
+    records: AtomicUsize,
+}
+
+impl TxJournal {
+    pub fn new<P: AsRef<Path>>(dir: P) -> ProtocolResult<Self> {
+        if !dir.as_ref().exists() {
+            fs::create_dir_all(&dir).map_err(MemPoolError::Journal)?;
+        }
+
+        let mut path = dir.as_ref().to_path_buf();
+        path.push(JOURNAL_FILE);
+        let file = open_append(&path)?;
+
+        Ok(TxJournal {
+            path,
+            file: Mutex::new(file),
+            records: AtomicUsize::new(0),
+        })
+    }
+
+    pub fn append(&self, stx: &SignedTransaction) -> ProtocolResult<()> {
+        let record = encode_record(stx)?;
+
+        self.file
+            .lock()
+            .write_all(&record)
+            .map_err(MemPoolError::Journal)?;
+        self.records.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Read all transactions in the journal. A broken record at the end,
+    /// which is left by a crash while appending, is ignored. Compact the
+    /// journal after load, so that new records don't follow a broken one.
+    pub fn load(&self) -> ProtocolResult<Vec<SignedTransaction>> {
+        let mut buf = Vec::new();
+        fs::File::open(&self.path)
+            .and_then(|mut file| file.read_to_end(&mut buf))
+            .map_err(MemPoolError::Journal)?;
+
+        let mut txs = Vec::new();
+        let mut offset = 0;
+        while offset + 4 <= buf.len() {
+            let mut len_bytes = [0u8; 4];
+            len_bytes.copy_from_slice(&buf[offset..offset + 4]);
+            let len = u32::from_be_bytes(len_bytes) as usize;
+
+            let start = offset + 4;
+            if start + len > buf.len() {
+                log::warn!("[core_mempool]: ignore a broken record at the end of journal");
+                break;
+            }
+
+            let bytes = Bytes::from(buf[start..start + len].to_vec());
+            match SignedTransaction::decode_fixed(bytes) {
+                Ok(stx) => txs.push(stx),
+                Err(e) => log::warn!("[core_mempool]: ignore a broken journal record {:?}", e),
+            }
+            offset = start + len;
+        }
+
+        self.records.store(txs.len(), Ordering::SeqCst);
+        Ok(txs)
+    }
+
+    /// Whether the stale records outnumber the `live` transactions.
+    pub fn need_compact(&self, live: usize) -> bool {
+        let records = self.records.load(Ordering::SeqCst);
+        records > MIN_COMPACT_RECORDS && records > live * 2
+    }
+
+    /// Rewrite the journal with only the given transactions. The new journal
+    /// is written to a temporary file first, then renamed to replace the old
+    /// one.
+    pub fn compact(&self, txs: &[SignedTransaction]) -> ProtocolResult<()> {
+        let mut file = self.file.lock();
+
+        let tmp_path = self.path.with_extension("tmp");
+        {
+            let tmp_file = fs::File::create(&tmp_path).map_err(MemPoolError::Journal)?;
+            let mut writer = BufWriter::new(tmp_file);
+            for stx in txs {
+                writer
+                    .write_all(&encode_record(stx)?)
+                    .map_err(MemPoolError::Journal)?;
+            }
+            writer.flush().map_err(MemPoolError::Journal)?;
+            writer.get_ref().sync_all().map_err(MemPoolError::Journal)?;
+        }
+        fs::rename(&tmp_path, &self.path).map_err(MemPoolError::Journal)?;
+
+        *file = open_append(&self.path)?;
+        self.records.store(txs.len(), Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> ProtocolResult<fs::File> {
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(MemPoolError::Journal)?;
+    Ok(file)
+}
+
+fn encode_record(stx: &SignedTransaction) -> ProtocolResult<Vec<u8>> {
+    let bytes = stx.encode_fixed()?;
+    let mut record = Vec::with_capacity(4 + bytes.len());
+    record.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    record.extend_from_slice(&bytes);
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use rand::random;
+
+    use protocol::types::{Hash, RawTransaction, SignedTransaction, TransactionRequest};
+    use protocol::Bytes;
+
+    use super::TxJournal;
+
+    fn mock_tx() -> SignedTransaction {
+        let nonce = Hash::digest(Bytes::from(
+            (0..10).map(|_| random::<u8>()).collect::<Vec<_>>(),
+        ));
+        let raw = RawTransaction {
+            chain_id:     nonce.clone(),
+            nonce:        nonce.clone(),
+            timeout:      100,
+            cycles_limit: 1,
+            cycles_price: 1,
+            request:      TransactionRequest {
+                service_name: "test".to_owned(),
+                method:       "test".to_owned(),
+                payload:      "test".to_owned(),
+            },
+        };
+
+        SignedTransaction {
+            raw,
+            tx_hash: nonce,
+            scheme: Default::default(),
+            pubkey: Bytes::from(vec![1u8; 33]),
+            signature: Bytes::from(vec![2u8; 64]),
+        }
+    }
+
+    fn journal_dir(name: &str) -> PathBuf {
+        let mut dir = env::temp_dir();
+        dir.push(format!("muta-mempool-journal-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let dir = journal_dir("load");
+        let txs = (0..10).map(|_| mock_tx()).collect::<Vec<_>>();
+
+        let journal = TxJournal::new(&dir).unwrap();
+        txs.iter().for_each(|tx| journal.append(tx).unwrap());
+
+        let journal = TxJournal::new(&dir).unwrap();
+        assert_eq!(journal.load().unwrap(), txs);
+
+        // A record cut off by crash
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(&journal.path)
+            .unwrap();
+        file.write_all(&[0, 0, 1, 0, 9]).unwrap();
+        assert_eq!(journal.load().unwrap(), txs);
+    }
+
+    #[test]
+    fn test_compact() {
+        let dir = journal_dir("compact");
+        let txs = (0..10).map(|_| mock_tx()).collect::<Vec<_>>();
+
+        let journal = TxJournal::new(&dir).unwrap();
+        txs.iter().for_each(|tx| journal.append(tx).unwrap());
+
+        journal.compact(&txs[5..]).unwrap();
+        assert_eq!(journal.load().unwrap(), txs[5..].to_vec());
+
+        // Appends go to the compacted journal
+        journal.append(&txs[0]).unwrap();
+        let mut expect = txs[5..].to_vec();
+        expect.push(txs[0].clone());
+        assert_eq!(journal.load().unwrap(), expect);
+    }
+}
@@ -2,6 +2,7 @@
 
 mod adapter;
 mod context;
+mod journal;
 mod map;
 #[cfg(test)]
 mod tests;
@@ -11,23 +12,30 @@ pub use adapter::message::{
     MsgPushTxs, NewTxsHandler, PullTxsHandler, PullTxsSyncHandler, END_GOSSIP_NEW_TXS,
     RPC_PULL_TXS, RPC_PULL_TXS_SYNC, RPC_RESP_PULL_TXS, RPC_RESP_PULL_TXS_SYNC,
 };
+pub use adapter::verifier::{SigVerifier, DEFAULT_VERIFY_BATCH_SIZE, DEFAULT_VERIFY_QUEUE_SIZE};
 pub use adapter::DefaultMemPoolAdapter;
 pub use adapter::{DEFAULT_BROADCAST_TXS_INTERVAL, DEFAULT_BROADCAST_TXS_SIZE};
+pub use journal::TxJournal;
+pub use tx_cache::{PackageOrder, SenderLimit};
+
+// Percentage of `cycles_price` a replacement transaction pays more at least.
+pub const DEFAULT_PRICE_BUMP: u64 = 10;
 
 use std::error::Error;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use async_trait::async_trait;
 use derive_more::Display;
+use futures::future::{join_all, try_join_all};
 use tokio::sync::RwLock;
 
 use protocol::traits::{Context, MemPool, MemPoolAdapter, MixedTxHashes};
-use protocol::types::{Hash, SignedTransaction};
+use protocol::types::{Address, Hash, SignedTransaction};
 use protocol::{ProtocolError, ProtocolErrorKind, ProtocolResult};
 
 use crate::context::TxContext;
 use crate::map::Map;
-use crate::tx_cache::TxCache;
+use crate::tx_cache::{max_fee, TxCache};
 
 /// Memory pool for caching transactions.
 pub struct HashMemPool<Adapter: MemPoolAdapter> {
@@ -44,23 +52,80 @@ pub struct HashMemPool<Adapter: MemPoolAdapter> {
     adapter:        Adapter,
     /// exclusive flush_memory and insert_tx to avoid repeat txs insertion.
     flush_lock:     RwLock<()>,
+    /// Journal of accepted transactions, reloaded after restart.
+    journal:        Option<TxJournal>,
+    /// Percentage of `cycles_price` a replacement transaction pays more at
+    /// least.
+    price_bump:     u64,
 }
 
 impl<Adapter> HashMemPool<Adapter>
 where
     Adapter: MemPoolAdapter,
 {
-    pub fn new(pool_size: usize, adapter: Adapter) -> Self {
+    pub fn new(pool_size: usize, order: PackageOrder, adapter: Adapter) -> Self {
         HashMemPool {
             pool_size,
             timeout_gap: AtomicU64::new(0),
-            tx_cache: TxCache::new(pool_size * 2),
+            tx_cache: TxCache::new(pool_size * 2, order),
             callback_cache: Map::new(pool_size),
             adapter,
             flush_lock: RwLock::new(()),
+            journal: None,
+            price_bump: DEFAULT_PRICE_BUMP,
         }
     }
 
+    pub fn with_journal(mut self, journal: TxJournal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    pub fn with_sender_limit(mut self, sender_limit: SenderLimit) -> Self {
+        self.tx_cache.set_sender_limit(sender_limit);
+        self
+    }
+
+    pub fn with_price_bump(mut self, price_bump: u64) -> Self {
+        self.price_bump = price_bump;
+        self
+    }
+
+    /// Reload transactions in the journal, which are validated again against
+    /// the current height. The pool starts empty if the journal can't be
+    /// read.
+    pub async fn reload_journal(&self, ctx: Context) -> ProtocolResult<()> {
+        let journal = match &self.journal {
+            Some(journal) => journal,
+            None => return Ok(()),
+        };
+
+        let txs = journal.load().unwrap_or_else(|e| {
+            log::error!("[core_mempool]: load journal failed {:?}", e);
+            vec![]
+        });
+        let total = txs.len();
+        // Peers have been broadcasted these transactions before restart.
+        let ctx = ctx.mark_network_origin_new_txs();
+
+        let mut reloaded = 0;
+        for tx in txs.into_iter() {
+            if self.insert_tx(ctx.clone(), tx, TxType::NewTx).await.is_ok() {
+                reloaded += 1;
+            }
+        }
+        if let Err(e) = journal.compact(&self.tx_cache.txs()) {
+            log::error!("[core_mempool]: compact journal failed {:?}", e);
+        }
+
+        log::info!(
+            "[core_mempool]: reload {:?} of {:?} txs in journal",
+            reloaded,
+            total
+        );
+        Ok(())
+    }
+
     pub fn get_tx_cache(&self) -> &TxCache {
         &self.tx_cache
     }
@@ -90,21 +155,65 @@ where
         let _lock = self.flush_lock.read().await;
 
         let tx_hash = &tx.tx_hash;
-        self.tx_cache.check_reach_limit(self.pool_size)?;
+        let cycles_price = tx.raw.cycles_price;
         self.tx_cache.check_exist(tx_hash)?;
-        self.adapter
-            .check_signature(ctx.clone(), tx.clone())
-            .await?;
-        self.adapter
-            .check_transaction(ctx.clone(), tx.clone())
-            .await?;
+
+        // A new transaction replaces the pending one of the same sender and
+        // nonce, if it pays enough more and the pending one is not ordered by
+        // a proposal.
+        let replaced = match tx_type {
+            TxType::NewTx => self.tx_cache.get_same_nonce(&tx)?,
+            TxType::ProposeTx => None,
+        };
+        match &replaced {
+            Some(replaced) => self.check_replace(&tx, replaced)?,
+            None => {
+                self.tx_cache
+                    .check_reach_limit(self.pool_size, cycles_price)?;
+                if let TxType::NewTx = tx_type {
+                    self.tx_cache.check_sender_limit(&tx)?;
+                }
+            }
+        }
+
+        // Transactions from peers are reported if malformed. A new transaction
+        // is paid for along with the pending ones of its sender, except the
+        // one it replaces.
+        let (ctx, from_peer) = match tx_type {
+            TxType::NewTx => {
+                let pending_fee = self
+                    .tx_cache
+                    .pending_max_fee(&tx.sender()?)
+                    .saturating_sub(replaced.as_ref().map_or(0, max_fee));
+                let from_peer = ctx.is_network_origin_txs();
+                (ctx.with_pending_fee(pending_fee), from_peer)
+            }
+            TxType::ProposeTx => (ctx, true),
+        };
+        self.verify_tx(ctx.clone(), &tx, from_peer).await?;
         self.adapter
             .check_storage_exist(ctx.clone(), tx_hash.clone())
             .await?;
+        match replaced {
+            Some(replaced) => {
+                log::debug!(
+                    "[core_mempool]: replace {:?} with {:?}",
+                    replaced.tx_hash,
+                    tx_hash
+                );
+                self.tx_cache.replace(&replaced.tx_hash)?;
+            }
+            None => self.tx_cache.evict_for(self.pool_size, cycles_price)?,
+        }
         match tx_type {
             TxType::NewTx => self.tx_cache.insert_new_tx(tx.clone())?,
             TxType::ProposeTx => self.tx_cache.insert_propose_tx(tx.clone())?,
         }
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.append(&tx) {
+                log::error!("[core_mempool]: journal {:?} failed {:?}", tx_hash, e);
+            }
+        }
 
         if !ctx.is_network_origin_txs() {
             self.adapter.broadcast_tx(ctx, tx).await?;
@@ -112,6 +221,53 @@ where
 
         Ok(())
     }
+
+    fn check_replace(
+        &self,
+        tx: &SignedTransaction,
+        replaced: &SignedTransaction,
+    ) -> ProtocolResult<()> {
+        let replaced_hash = &replaced.tx_hash;
+        if self.tx_cache.is_in_flight(replaced_hash)
+            || self.callback_cache.contains_key(replaced_hash)
+        {
+            return Err(MemPoolError::ReplaceInFlight {
+                tx_hash: replaced_hash.clone(),
+            }
+            .into());
+        }
+
+        let price = u128::from(replaced.raw.cycles_price);
+        let bump = (price * u128::from(self.price_bump) / 100).max(1);
+        let min_price = (price + bump).min(u128::from(std::u64::MAX)) as u64;
+
+        if tx.raw.cycles_price < min_price {
+            return Err(MemPoolError::ReplaceUnderpriced {
+                tx_hash: tx.tx_hash.clone(),
+                cycles_price: tx.raw.cycles_price,
+                min_price,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    async fn verify_tx(
+        &self,
+        ctx: Context,
+        tx: &SignedTransaction,
+        from_peer: bool,
+    ) -> ProtocolResult<()> {
+        // Other checks may fail because of state lag between honest peers,
+        // which are not reported.
+        let rst = self.adapter.check_signature(ctx.clone(), tx.clone()).await;
+        if rst.is_err() && from_peer {
+            self.adapter.report_invalid_tx(ctx.clone(), &tx.tx_hash);
+        }
+        rst?;
+
+        self.adapter.check_transaction(ctx, tx.clone()).await
+    }
 }
 
 #[async_trait]
@@ -123,6 +279,20 @@ where
         self.insert_tx(ctx, tx, TxType::NewTx).await
     }
 
+    async fn insert_batch(
+        &self,
+        ctx: Context,
+        txs: Vec<SignedTransaction>,
+    ) -> Vec<ProtocolResult<()>> {
+        // Signatures and checks against the state run on the pool of the
+        // verifier, so the insertions wait for them in parallel.
+        join_all(
+            txs.into_iter()
+                .map(|tx| self.insert_tx(ctx.clone(), tx, TxType::NewTx)),
+        )
+        .await
+    }
+
     async fn package(
         &self,
         ctx: Context,
@@ -158,6 +328,15 @@ where
         );
         self.callback_cache.clear();
 
+        if let Some(journal) = &self.journal {
+            // Failing to write the journal doesn't stop the commit.
+            if journal.need_compact(self.tx_cache.len()) {
+                if let Err(e) = journal.compact(&self.tx_cache.txs()) {
+                    log::error!("[core_mempool]: compact journal failed {:?}", e);
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -193,6 +372,9 @@ where
         ctx: Context,
         order_tx_hashes: Vec<Hash>,
     ) -> ProtocolResult<()> {
+        // Ordered transactions in the pool must stay until the block is
+        // committed.
+        self.tx_cache.mark_packaged(&order_tx_hashes);
         let unknown_hashes = self.show_unknown_txs(order_tx_hashes);
         if !unknown_hashes.is_empty() {
             let unknown_len = unknown_hashes.len();
@@ -206,13 +388,14 @@ where
                 .into());
             }
 
+            // Verify concurrently, so that signatures are verified in batch.
+            let ctx = ctx.mark_pulled_txs();
+            try_join_all(
+                txs.iter()
+                    .map(|signed_tx| self.verify_tx(ctx.clone(), signed_tx, true)),
+            )
+            .await?;
             for signed_tx in txs.into_iter() {
-                self.adapter
-                    .check_signature(ctx.clone(), signed_tx.clone())
-                    .await?;
-                self.adapter
-                    .check_transaction(ctx.clone(), signed_tx.clone())
-                    .await?;
                 self.adapter
                     .check_storage_exist(ctx.clone(), signed_tx.tx_hash.clone())
                     .await?;
@@ -229,6 +412,9 @@ where
         ctx: Context,
         order_tx_hashes: Vec<Hash>,
     ) -> ProtocolResult<()> {
+        // Ordered transactions in the pool must stay until the block is
+        // committed.
+        self.tx_cache.mark_packaged(&order_tx_hashes);
         let unknown_hashes = self.show_unknown_txs(order_tx_hashes);
         if !unknown_hashes.is_empty() {
             let unknown_len = unknown_hashes.len();
@@ -245,13 +431,14 @@ where
                 .into());
             }
 
+            // Verify concurrently, so that signatures are verified in batch.
+            let ctx = ctx.mark_pulled_txs();
+            try_join_all(
+                txs.iter()
+                    .map(|signed_tx| self.verify_tx(ctx.clone(), signed_tx, true)),
+            )
+            .await?;
             for signed_tx in txs.into_iter() {
-                self.adapter
-                    .check_signature(ctx.clone(), signed_tx.clone())
-                    .await?;
-                self.adapter
-                    .check_transaction(ctx.clone(), signed_tx.clone())
-                    .await?;
                 self.adapter
                     .check_storage_exist(ctx.clone(), signed_tx.tx_hash.clone())
                     .await?;
@@ -271,12 +458,14 @@ where
         let unknown_hashes = self.show_unknown_txs(propose_tx_hashes);
         if !unknown_hashes.is_empty() {
             let txs = self.adapter.pull_txs(ctx.clone(), unknown_hashes).await?;
-            // TODO: concurrently insert
-            for tx in txs.into_iter() {
-                // Should not handle error here, it is normal that transactions
-                // response here are exist in pool.
-                let _ = self.insert_tx(ctx.clone(), tx, TxType::ProposeTx).await;
-            }
+            // Should not handle error here, it is normal that transactions
+            // response here are exist in pool.
+            let ctx = ctx.mark_pulled_txs();
+            join_all(
+                txs.into_iter()
+                    .map(|tx| self.insert_tx(ctx.clone(), tx, TxType::ProposeTx)),
+            )
+            .await;
         }
         Ok(())
     }
@@ -286,6 +475,22 @@ where
             .set_args(timeout_gap, cycles_limit, max_tx_size);
         self.timeout_gap.store(timeout_gap, Ordering::Relaxed);
     }
+
+    fn tx_count(&self) -> usize {
+        self.tx_cache.len()
+    }
+
+    fn pending_tx_hashes(&self, limit: usize) -> Vec<Hash> {
+        self.tx_cache.tx_hashes(limit)
+    }
+
+    fn get_pending_tx(&self, tx_hash: &Hash) -> Option<SignedTransaction> {
+        self.tx_cache.get(tx_hash)
+    }
+
+    fn get_proposed_tx(&self, tx_hash: &Hash) -> Option<SignedTransaction> {
+        self.callback_cache.get(tx_hash)
+    }
 }
 
 pub enum TxType {
@@ -325,9 +530,44 @@ pub enum MemPoolError {
     #[display(fmt = "Mempool reaches limit: {}", pool_size)]
     ReachLimit { pool_size: usize },
 
+    #[display(
+        fmt = "Tx: {:?} exceeds limit of sender {:?}, pending txs: {}, bytes: {}",
+        tx_hash,
+        sender,
+        txs,
+        bytes
+    )]
+    ExceedSenderLimit {
+        tx_hash: Hash,
+        sender:  Address,
+        txs:     usize,
+        bytes:   usize,
+    },
+
     #[display(fmt = "Tx: {:?} exists in pool", tx_hash)]
     Dup { tx_hash: Hash },
 
+    #[display(fmt = "Tx: {:?} nonce {:?} is used by a pending tx", tx_hash, nonce)]
+    DupNonce { tx_hash: Hash, nonce: Hash },
+
+    #[display(
+        fmt = "Tx: {:?} replaces a pending tx, cycles price: {}, min: {}",
+        tx_hash,
+        cycles_price,
+        min_price
+    )]
+    ReplaceUnderpriced {
+        tx_hash:      Hash,
+        cycles_price: u64,
+        min_price:    u64,
+    },
+
+    #[display(
+        fmt = "Tx: {:?} is ordered by a proposal and can't be replaced",
+        tx_hash
+    )]
+    ReplaceInFlight { tx_hash: Hash },
+
     #[display(fmt = "Pull txs, require: {}, response: {}", require, response)]
     EnsureBreak { require: usize, response: usize },
 
@@ -354,6 +594,46 @@ pub enum MemPoolError {
 
     #[display(fmt = "Tx: {:?} invalid timeout", tx_hash)]
     InvalidTimeout { tx_hash: Hash },
+
+    #[display(
+        fmt = "Tx: {:?} insufficient balance, balance: {}, max fee: {}, pending fee: {}",
+        tx_hash,
+        balance,
+        max_fee,
+        pending_fee
+    )]
+    InsufficientBalance {
+        tx_hash:     Hash,
+        balance:     u64,
+        max_fee:     u64,
+        pending_fee: u64,
+    },
+
+    #[display(fmt = "Read {}.{} failed: {}", service, method, msg)]
+    ReadState {
+        service: String,
+        method:  String,
+        msg:     String,
+    },
+
+    #[display(fmt = "Decode state failed: {}", _0)]
+    DecodeState(serde_json::Error),
+
+    #[display(fmt = "Journal io error: {:?}", _0)]
+    Journal(std::io::Error),
+
+    #[display(
+        fmt = "Tx: {:?} signature verifier is busy, queue size: {}, retry later",
+        tx_hash,
+        queue_size
+    )]
+    VerifierBusy { tx_hash: Hash, queue_size: usize },
+
+    #[display(fmt = "Signature verifier dropped")]
+    VerifierDropped,
+
+    #[display(fmt = "Build signature verifier failed: {}", _0)]
+    BuildVerifier(rayon::ThreadPoolBuildError),
 }
 
 impl Error for MemPoolError {}
@@ -62,6 +62,17 @@ where
         self.buckets[index].remove(tx_hash);
     }
 
+    pub fn keys(&self, limit: usize) -> Vec<Hash> {
+        let mut keys = Vec::new();
+        for bucket in self.buckets.iter() {
+            if keys.len() >= limit {
+                break;
+            }
+            keys.extend(bucket.keys(limit - keys.len()));
+        }
+        keys
+    }
+
     pub fn len(&self) -> usize {
         let mut len = 0;
         for bucket in self.buckets.iter() {
@@ -123,6 +134,10 @@ where
         store.remove(tx_hash);
     }
 
+    fn keys(&self, limit: usize) -> Vec<Hash> {
+        self.store.read().keys().take(limit).cloned().collect()
+    }
+
     fn len(&self) -> usize {
         self.store.read().len()
     }
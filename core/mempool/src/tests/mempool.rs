@@ -40,6 +40,19 @@ fn test_insert() {
     insert!(invalid(80, 10, 80));
 }
 
+#[test]
+fn test_insert_batch() {
+    let mempool = default_mempool();
+
+    let txs = mock_txs(80, 10, TIMEOUT);
+    let results = executor::block_on(mempool.insert_batch(Context::new(), txs));
+
+    assert_eq!(results.len(), 90);
+    assert!(results[..80].iter().all(|r| r.is_ok()));
+    assert!(results[80..].iter().all(|r| r.is_err()));
+    assert_eq!(mempool.get_tx_cache().len(), 80);
+}
+
 macro_rules! package {
     (normal($tx_num_limit: expr, $insert: expr, $expect_order: expr, $expect_propose: expr)) => {
         package!(inner(
@@ -113,6 +126,193 @@ fn test_package_order_consistent_with_insert_order() {
     assert!(check_order_consistant(&mixed_tx_hashes, reserve_txs));
 }
 
+#[test]
+fn test_package_by_fee_priority() {
+    let mempool = &Arc::new(new_mempool_with_order(
+        POOL_SIZE,
+        TIMEOUT_GAP,
+        CYCLE_LIMIT,
+        MAX_TX_SIZE,
+        PackageOrder::FeePriority,
+    ));
+
+    let txs = mock_txs_with_prices(&[1, 3, 2, 3]);
+    txs.iter()
+        .for_each(|signed_tx| exec_insert(signed_tx, Arc::clone(mempool)));
+    let hashes: Vec<Hash> = txs.iter().map(|tx| tx.tx_hash.clone()).collect();
+
+    // Same price in insertion order.
+    let mixed_tx_hashes = exec_package(Arc::clone(mempool), CYCLE_LIMIT, 3);
+    assert_eq!(mixed_tx_hashes.order_tx_hashes, vec![
+        hashes[1].clone(),
+        hashes[3].clone(),
+        hashes[2].clone()
+    ]);
+    assert_eq!(mixed_tx_hashes.propose_tx_hashes, vec![hashes[0].clone()]);
+
+    // Each tx uses `TX_CYCLE` cycles.
+    let mixed_tx_hashes = exec_package(Arc::clone(mempool), TX_CYCLE * 2, TX_NUM_LIMIT);
+    assert_eq!(mixed_tx_hashes.order_tx_hashes, vec![
+        hashes[1].clone(),
+        hashes[3].clone()
+    ]);
+    assert_eq!(mixed_tx_hashes.propose_tx_hashes, vec![
+        hashes[2].clone(),
+        hashes[0].clone()
+    ]);
+}
+
+#[test]
+fn test_evict_lowest_price() {
+    let mempool = &Arc::new(new_mempool_with_order(
+        2,
+        TIMEOUT_GAP,
+        CYCLE_LIMIT,
+        MAX_TX_SIZE,
+        PackageOrder::FeePriority,
+    ));
+
+    let txs = mock_txs_with_prices(&[2, 1, 3, 1, 2]);
+    let insert =
+        |tx: &SignedTransaction| executor::block_on(mempool.insert(Context::new(), tx.clone()));
+
+    assert!(insert(&txs[0]).is_ok());
+    assert!(insert(&txs[1]).is_ok());
+    // Evicts txs[1]
+    assert!(insert(&txs[2]).is_ok());
+    assert!(mempool.get_pending_tx(&txs[1].tx_hash).is_none());
+    // Nothing cheaper to evict
+    assert!(insert(&txs[3]).is_err());
+    assert!(insert(&txs[4]).is_err());
+    assert_eq!(mempool.tx_count(), 2);
+
+    let mixed_tx_hashes = exec_package(Arc::clone(mempool), CYCLE_LIMIT, TX_NUM_LIMIT);
+    assert_eq!(mixed_tx_hashes.order_tx_hashes, vec![
+        txs[2].tx_hash.clone(),
+        txs[0].tx_hash.clone()
+    ]);
+}
+
+#[test]
+fn test_sender_limit() {
+    let txs = default_mock_txs(5);
+    let insert = |mempool: &HashMemPool<HashMemPoolAdapter>, tx: &SignedTransaction| {
+        executor::block_on(mempool.insert(Context::new(), tx.clone()))
+    };
+
+    let mempool = default_mempool().with_sender_limit(SenderLimit {
+        max_txs:   3,
+        max_bytes: 0,
+    });
+    assert!(txs[..3].iter().all(|tx| insert(&mempool, tx).is_ok()));
+    assert!(insert(&mempool, &txs[3]).is_err());
+    // Other senders are not affected
+    let other_txs = default_mock_txs(3);
+    assert!(other_txs.iter().all(|tx| insert(&mempool, tx).is_ok()));
+
+    // Flushed transactions release the quota
+    executor::block_on(mempool.flush(Context::new(), vec![txs[0].tx_hash.clone()])).unwrap();
+    assert!(insert(&mempool, &txs[3]).is_ok());
+    assert!(insert(&mempool, &txs[4]).is_err());
+
+    // All mock transactions are encoded in the same size
+    let tx_size = txs[0].encode_fixed().unwrap().len();
+    let mempool = default_mempool().with_sender_limit(SenderLimit {
+        max_txs:   0,
+        max_bytes: tx_size * 2,
+    });
+    assert!(txs[..2].iter().all(|tx| insert(&mempool, tx).is_ok()));
+    assert!(insert(&mempool, &txs[2]).is_err());
+    assert_eq!(mempool.tx_count(), 2);
+}
+
+#[test]
+fn test_report_invalid_txs() {
+    let mempool = default_mempool();
+    let txs = mock_txs(2, 3, TIMEOUT);
+
+    let results = executor::block_on(mempool.insert_batch(Context::new(), txs.clone()));
+    assert_eq!(results.iter().filter(|r| r.is_err()).count(), 3);
+    let adapter = mempool.get_adapter();
+    assert_eq!(adapter.reported_txs.load(Ordering::SeqCst), 0);
+
+    // Only invalid transactions from peers are reported
+    let ctx = Context::new().mark_network_origin_new_txs();
+    executor::block_on(mempool.insert_batch(ctx, txs));
+    assert_eq!(adapter.reported_txs.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_replace_by_nonce() {
+    let mempool = default_mempool();
+    let txs = mock_txs_with_same_nonce(&[100, 100, 109, 110, 121]);
+    let insert =
+        |tx: &SignedTransaction| executor::block_on(mempool.insert(Context::new(), tx.clone()));
+
+    assert!(insert(&txs[0]).is_ok());
+    // Same price
+    assert!(insert(&txs[1]).is_err());
+    // Price bump is less than 10 percent
+    assert!(insert(&txs[2]).is_err());
+
+    // Ordered by a proposal until the next flush
+    mempool
+        .get_tx_cache()
+        .mark_packaged(&[txs[0].tx_hash.clone()]);
+    assert!(insert(&txs[3]).is_err());
+    executor::block_on(mempool.flush(Context::new(), vec![])).unwrap();
+
+    assert!(insert(&txs[3]).is_ok());
+    assert_eq!(mempool.tx_count(), 1);
+    assert!(mempool.get_pending_tx(&txs[0].tx_hash).is_none());
+    assert!(mempool.get_pending_tx(&txs[3].tx_hash).is_some());
+    // Replacement is broadcasted
+    assert!(mempool
+        .get_adapter()
+        .network_txs
+        .contains_key(&txs[3].tx_hash));
+
+    // The replaced one can't be inserted again
+    assert!(insert(&txs[0]).is_err());
+
+    // Ordered by a proposal of another node
+    mempool
+        .get_callback_cache()
+        .insert(txs[3].tx_hash.clone(), txs[3].clone());
+    assert!(insert(&txs[4]).is_err());
+    assert!(mempool.get_pending_tx(&txs[3].tx_hash).is_some());
+}
+
+#[test]
+fn test_multisig_tx() {
+    let mempool = default_mempool();
+    let insert = |tx: SignedTransaction| executor::block_on(mempool.insert(Context::new(), tx));
+
+    // 2 of 3
+    assert!(insert(mock_multisig_tx(2, 3, &[0, 2])).is_ok());
+    assert!(insert(mock_multisig_tx(2, 3, &[0, 1, 2])).is_ok());
+    // Not enough signatures
+    assert!(insert(mock_multisig_tx(2, 3, &[1])).is_err());
+    // Signed twice by the same key
+    assert!(insert(mock_multisig_tx(2, 3, &[1, 1])).is_err());
+
+    let mut tx = mock_multisig_tx(2, 3, &[0, 1]);
+    let other = mock_multisig_tx(2, 3, &[0, 1]);
+    tx.signature = other.signature;
+    assert!(insert(tx).is_err());
+    assert_eq!(mempool.tx_count(), 2);
+}
+
+#[test]
+fn test_ed25519_tx() {
+    let mempool = default_mempool();
+    let insert = |tx: SignedTransaction| executor::block_on(mempool.insert(Context::new(), tx));
+
+    assert!(insert(mock_ed25519_tx(true)).is_ok());
+    assert!(insert(mock_ed25519_tx(false)).is_err());
+    assert_eq!(mempool.tx_count(), 1);
+}
+
 #[test]
 fn test_flush() {
     let mempool = Arc::new(default_mempool());
@@ -146,6 +346,61 @@ fn test_flush() {
     assert_eq!(mempool.get_tx_cache().queue_len(), 432);
 }
 
+#[test]
+fn test_reload_journal() {
+    let mut dir = std::env::temp_dir();
+    dir.push("muta-mempool-journal-reload");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mempool = Arc::new(default_mempool().with_journal(TxJournal::new(&dir).unwrap()));
+    let txs = default_mock_txs(100);
+    concurrent_insert(txs.clone(), Arc::clone(&mempool));
+    assert_eq!(mempool.tx_count(), 100);
+
+    let mempool = default_mempool().with_journal(TxJournal::new(&dir).unwrap());
+    executor::block_on(mempool.reload_journal(Context::new())).unwrap();
+    assert_eq!(mempool.tx_count(), 100);
+    assert!(txs
+        .iter()
+        .all(|tx| mempool.get_pending_tx(&tx.tx_hash).is_some()));
+}
+
+#[test]
+fn test_pending_txs() {
+    let mempool = Arc::new(default_mempool());
+
+    let txs = default_mock_txs(100);
+    concurrent_insert(txs.clone(), Arc::clone(&mempool));
+    assert_eq!(mempool.tx_count(), 100);
+    assert_eq!(mempool.pending_tx_hashes(1000).len(), 100);
+    assert_eq!(mempool.pending_tx_hashes(10).len(), 10);
+
+    let tx_hash = &txs[0].tx_hash;
+    assert_eq!(&mempool.get_pending_tx(tx_hash).unwrap().tx_hash, tx_hash);
+
+    exec_flush(vec![tx_hash.clone()], Arc::clone(&mempool));
+    assert_eq!(mempool.tx_count(), 99);
+    assert!(mempool.get_pending_tx(tx_hash).is_none());
+}
+
+#[test]
+fn test_proposed_txs() {
+    let mempool = Arc::new(default_mempool());
+
+    let txs = default_mock_txs(1);
+    let tx_hash = &txs[0].tx_hash;
+    concurrent_broadcast(txs.clone(), Arc::clone(&mempool));
+    exec_ensure_order_txs(vec![tx_hash.clone()], Arc::clone(&mempool));
+
+    // Pulled for the proposal, not waiting in pool
+    assert!(mempool.get_pending_tx(tx_hash).is_none());
+    assert_eq!(&mempool.get_proposed_tx(tx_hash).unwrap().tx_hash, tx_hash);
+    assert_eq!(mempool.pending_tx_hashes(10).len(), 0);
+
+    exec_flush(vec![tx_hash.clone()], Arc::clone(&mempool));
+    assert!(mempool.get_proposed_tx(tx_hash).is_none());
+}
+
 macro_rules! ensure_order_txs {
     ($in_pool: expr, $out_pool: expr) => {
         let mempool = &Arc::new(default_mempool());
@@ -190,6 +445,24 @@ fn test_sync_propose_txs() {
     assert_eq!(mempool.get_tx_cache().len(), 50);
 }
 
+#[test]
+fn test_pulled_txs_skip_balance() {
+    let mempool = &Arc::new(default_mempool());
+
+    let txs = &default_mock_txs(2);
+    let sender = txs[0].sender().unwrap();
+    mempool.get_adapter().unfunded_senders.insert(sender, ());
+    assert!(executor::block_on(mempool.insert(Context::new(), txs[0].clone())).is_err());
+
+    // The block may be executed on a state funding the sender.
+    concurrent_broadcast(txs.to_vec(), Arc::clone(mempool));
+    exec_ensure_order_txs(vec![txs[0].tx_hash.clone()], Arc::clone(mempool));
+    assert_eq!(mempool.get_callback_cache().len(), 1);
+
+    exec_sync_propose_txs(vec![txs[1].tx_hash.clone()], Arc::clone(mempool));
+    assert_eq!(mempool.get_tx_cache().len(), 1);
+}
+
 #[bench]
 fn bench_insert(b: &mut Bencher) {
     let mempool = &Arc::new(default_mempool());
@@ -3,6 +3,7 @@ extern crate test;
 mod mempool;
 
 use std::convert::{From, TryFrom};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -14,15 +15,22 @@ use rayon::iter::IntoParallelRefIterator;
 use rayon::prelude::*;
 
 use common_crypto::{
-    Crypto, PrivateKey, PublicKey, Secp256k1, Secp256k1PrivateKey, Secp256k1PublicKey,
-    Secp256k1Signature, Signature, ToPublicKey,
+    Crypto, Ed25519, Ed25519PrivateKey, PrivateKey, PublicKey, Secp256k1, Secp256k1PrivateKey,
+    Secp256k1PublicKey, Secp256k1Signature, Signature, ToPublicKey,
 };
 use protocol::codec::ProtocolCodec;
+use protocol::fixed_codec::FixedCodec;
 use protocol::traits::{Context, MemPool, MemPoolAdapter, MixedTxHashes};
-use protocol::types::{Hash, RawTransaction, SignedTransaction, TransactionRequest};
+use protocol::types::{
+    Address, Hash, MultiSigPubkey, MultiSigSignature, RawTransaction, SignatureScheme,
+    SignedTransaction, TransactionRequest,
+};
 use protocol::{Bytes, ProtocolResult};
 
-use crate::{HashMemPool, MemPoolError};
+use crate::adapter::verifier::verify_witness;
+use crate::context::TxContext;
+use crate::tx_cache::max_fee;
+use crate::{HashMemPool, MemPoolError, PackageOrder, SenderLimit, TxJournal};
 
 const CYCLE_LIMIT: u64 = 1_000_000;
 const TX_NUM_LIMIT: u64 = 10_000;
@@ -34,13 +42,18 @@ const TIMEOUT_GAP: u64 = 100;
 const TX_CYCLE: u64 = 1;
 
 pub struct HashMemPoolAdapter {
-    network_txs: CHashMap<Hash, SignedTransaction>,
+    network_txs:      CHashMap<Hash, SignedTransaction>,
+    reported_txs:     AtomicUsize,
+    // Senders failing the fee check, which doesn't apply to pulled txs.
+    unfunded_senders: CHashMap<Address, ()>,
 }
 
 impl HashMemPoolAdapter {
     fn new() -> HashMemPoolAdapter {
         HashMemPoolAdapter {
-            network_txs: CHashMap::new(),
+            network_txs:      CHashMap::new(),
+            reported_txs:     AtomicUsize::new(0),
+            unfunded_senders: CHashMap::new(),
         }
     }
 }
@@ -79,7 +92,16 @@ impl MemPoolAdapter for HashMemPoolAdapter {
         check_sig(&tx)
     }
 
-    async fn check_transaction(&self, _ctx: Context, _tx: SignedTransaction) -> ProtocolResult<()> {
+    async fn check_transaction(&self, ctx: Context, tx: SignedTransaction) -> ProtocolResult<()> {
+        if !ctx.is_pulled_txs() && self.unfunded_senders.contains_key(&tx.sender()?) {
+            return Err(MemPoolError::InsufficientBalance {
+                tx_hash:     tx.tx_hash.clone(),
+                balance:     0,
+                max_fee:     max_fee(&tx),
+                pending_fee: ctx.pending_fee(),
+            }
+            .into());
+        }
         Ok(())
     }
 
@@ -91,6 +113,10 @@ impl MemPoolAdapter for HashMemPoolAdapter {
         Ok(CURRENT_HEIGHT)
     }
 
+    fn report_invalid_tx(&self, _ctx: Context, _tx_hash: &Hash) {
+        self.reported_txs.fetch_add(1, Ordering::SeqCst);
+    }
+
     fn set_args(&self, _timeout_gap: u64, _cycles_limit: u64, _max_tx_size: u64) {}
 }
 
@@ -103,11 +129,89 @@ fn mock_txs(valid_size: usize, invalid_size: usize, timeout: u64) -> Vec<SignedT
     let priv_key = Secp256k1PrivateKey::generate(&mut OsRng);
     let pub_key = priv_key.pub_key();
     for i in 0..valid_size + invalid_size {
-        vec.push(mock_signed_tx(&priv_key, &pub_key, timeout, i < valid_size));
+        vec.push(mock_signed_tx(
+            &priv_key,
+            &pub_key,
+            timeout,
+            1,
+            i < valid_size,
+        ));
     }
     vec
 }
 
+fn mock_txs_with_prices(prices: &[u64]) -> Vec<SignedTransaction> {
+    let priv_key = Secp256k1PrivateKey::generate(&mut OsRng);
+    let pub_key = priv_key.pub_key();
+    prices
+        .iter()
+        .map(|price| mock_signed_tx(&priv_key, &pub_key, TIMEOUT, *price, true))
+        .collect()
+}
+
+// Transactions of the same sender and nonce.
+fn mock_txs_with_same_nonce(prices: &[u64]) -> Vec<SignedTransaction> {
+    let priv_key = Secp256k1PrivateKey::generate(&mut OsRng);
+    let pub_key = priv_key.pub_key();
+    let nonce = Hash::digest(Bytes::from(get_random_bytes(10)));
+    prices
+        .iter()
+        .map(|price| mock_signed_tx_with_nonce(&priv_key, &pub_key, nonce.clone(), *price))
+        .collect()
+}
+
+// A transaction of a `threshold` of `keys` multisig account, signed by the
+// keys at `signers`.
+pub fn mock_multisig_tx(threshold: u8, keys: usize, signers: &[u8]) -> SignedTransaction {
+    let priv_keys = (0..keys)
+        .map(|_| Secp256k1PrivateKey::generate(&mut OsRng))
+        .collect::<Vec<_>>();
+    let pubkeys = priv_keys
+        .iter()
+        .map(|key| key.pub_key().to_bytes())
+        .collect::<Vec<_>>();
+
+    let stx = mock_signed_tx(&priv_keys[0], &priv_keys[0].pub_key(), TIMEOUT, 1, true);
+    let signatures = signers
+        .iter()
+        .map(|index| MultiSigSignature {
+            index:     *index,
+            signature: Secp256k1::sign_message(
+                &stx.tx_hash.as_bytes(),
+                &priv_keys[*index as usize].to_bytes(),
+            )
+            .unwrap()
+            .to_bytes(),
+        })
+        .collect::<Vec<_>>();
+
+    let pubkey = MultiSigPubkey::new(threshold, pubkeys).unwrap();
+    SignedTransaction::new_multisig(
+        stx.raw,
+        stx.tx_hash,
+        SignatureScheme::Secp256k1,
+        &pubkey,
+        &signatures,
+    )
+    .unwrap()
+}
+
+pub fn mock_ed25519_tx(valid: bool) -> SignedTransaction {
+    let priv_key = Ed25519PrivateKey::generate(&mut OsRng);
+    let mut stx = default_mock_txs(1).remove(0);
+
+    let signature = if valid {
+        Ed25519::sign_message(&stx.tx_hash.as_bytes(), &priv_key.to_bytes()).unwrap()
+    } else {
+        let other_key = Ed25519PrivateKey::generate(&mut OsRng);
+        Ed25519::sign_message(&stx.tx_hash.as_bytes(), &other_key.to_bytes()).unwrap()
+    };
+    stx.scheme = SignatureScheme::Ed25519;
+    stx.pubkey = priv_key.pub_key().to_bytes();
+    stx.signature = signature.to_bytes();
+    stx
+}
+
 fn default_mempool() -> HashMemPool<HashMemPoolAdapter> {
     new_mempool(POOL_SIZE, TIMEOUT_GAP, CYCLE_LIMIT, MAX_TX_SIZE)
 }
@@ -117,9 +221,25 @@ fn new_mempool(
     timeout_gap: u64,
     cycles_limit: u64,
     max_tx_size: u64,
+) -> HashMemPool<HashMemPoolAdapter> {
+    new_mempool_with_order(
+        pool_size,
+        timeout_gap,
+        cycles_limit,
+        max_tx_size,
+        PackageOrder::Fifo,
+    )
+}
+
+fn new_mempool_with_order(
+    pool_size: usize,
+    timeout_gap: u64,
+    cycles_limit: u64,
+    max_tx_size: u64,
+    order: PackageOrder,
 ) -> HashMemPool<HashMemPoolAdapter> {
     let adapter = HashMemPoolAdapter::new();
-    let mempool = HashMemPool::new(pool_size, adapter);
+    let mempool = HashMemPool::new(pool_size, order, adapter);
     mempool.set_args(timeout_gap, cycles_limit, max_tx_size);
     mempool
 }
@@ -139,7 +259,7 @@ async fn check_hash(tx: SignedTransaction) -> ProtocolResult<()> {
 }
 
 fn check_sig(tx: &SignedTransaction) -> ProtocolResult<()> {
-    if Secp256k1::verify_signature(&tx.tx_hash.as_bytes(), &tx.signature, &tx.pubkey).is_err() {
+    if !verify_witness(tx.scheme, &tx.tx_hash.as_bytes(), &tx.witness()?) {
         return Err(MemPoolError::CheckSig {
             tx_hash: tx.tx_hash.clone(),
         }
@@ -233,10 +353,30 @@ fn mock_signed_tx(
     priv_key: &Secp256k1PrivateKey,
     pub_key: &Secp256k1PublicKey,
     timeout: u64,
+    cycles_price: u64,
     valid: bool,
 ) -> SignedTransaction {
     let nonce = Hash::digest(Bytes::from(get_random_bytes(10)));
+    sign_mock_tx(priv_key, pub_key, nonce, timeout, cycles_price, valid)
+}
+
+fn mock_signed_tx_with_nonce(
+    priv_key: &Secp256k1PrivateKey,
+    pub_key: &Secp256k1PublicKey,
+    nonce: Hash,
+    cycles_price: u64,
+) -> SignedTransaction {
+    sign_mock_tx(priv_key, pub_key, nonce, TIMEOUT, cycles_price, true)
+}
 
+fn sign_mock_tx(
+    priv_key: &Secp256k1PrivateKey,
+    pub_key: &Secp256k1PublicKey,
+    nonce: Hash,
+    timeout: u64,
+    cycles_price: u64,
+    valid: bool,
+) -> SignedTransaction {
     let request = TransactionRequest {
         service_name: "test".to_owned(),
         method:       "test".to_owned(),
@@ -247,7 +387,7 @@ fn mock_signed_tx(
         nonce,
         timeout,
         cycles_limit: TX_CYCLE,
-        cycles_price: 1,
+        cycles_price,
         request,
     };
 
@@ -263,6 +403,7 @@ fn mock_signed_tx(
     SignedTransaction {
         raw,
         tx_hash,
+        scheme: SignatureScheme::Secp256k1,
         pubkey: pub_key.to_bytes(),
         signature: signature.to_bytes(),
     }
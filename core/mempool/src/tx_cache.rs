@@ -1,10 +1,15 @@
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use crossbeam_queue::ArrayQueue;
+use parking_lot::Mutex;
+use serde_derive::Deserialize;
 
+use protocol::fixed_codec::FixedCodec;
 use protocol::traits::MixedTxHashes;
-use protocol::types::{Hash, SignedTransaction};
+use protocol::types::{Address, Hash, SignedTransaction};
 use protocol::ProtocolResult;
 
 use crate::map::Map;
@@ -30,24 +35,54 @@ pub struct TxWrapper {
     /// While collecting propose_tx_hashes during package,
     /// it will skips transactions which marks 'proposed` true.
     proposed: AtomicBool,
+    /// Insertion sequence, breaks ties of `cycles_price` in fee priority
+    /// order.
+    seq:      u64,
+    /// Sender address and encoded size, counted in sender usage.
+    sender:   Address,
+    size:     usize,
+    /// Set when the transaction leaves the map, so that its usage and
+    /// price index entry are only released once.
+    released: AtomicBool,
+    /// One more than the flush generation in which the transaction is
+    /// ordered by a proposal, 0 if never.
+    packaged: AtomicU64,
 }
 
 impl TxWrapper {
     #[allow(dead_code)]
-    pub(crate) fn new(tx: SignedTransaction) -> Self {
-        TxWrapper {
-            tx,
-            removed: AtomicBool::new(false),
-            proposed: AtomicBool::new(false),
-        }
+    pub(crate) fn new(tx: SignedTransaction, seq: u64) -> ProtocolResult<Self> {
+        Self::with_proposed(tx, seq, false)
     }
 
-    pub(crate) fn propose(tx: SignedTransaction) -> Self {
-        TxWrapper {
+    pub(crate) fn propose(tx: SignedTransaction, seq: u64) -> ProtocolResult<Self> {
+        Self::with_proposed(tx, seq, true)
+    }
+
+    fn with_proposed(tx: SignedTransaction, seq: u64, proposed: bool) -> ProtocolResult<Self> {
+        let sender = tx.sender()?;
+        let size = tx_size(&tx)?;
+
+        Ok(TxWrapper {
             tx,
             removed: AtomicBool::new(false),
-            proposed: AtomicBool::new(true),
-        }
+            proposed: AtomicBool::new(proposed),
+            seq,
+            sender,
+            size,
+            released: AtomicBool::new(false),
+            packaged: AtomicU64::new(0),
+        })
+    }
+
+    // Lower key is evicted first: the lowest price, and the latest one among
+    // the same price.
+    fn price_key(&self) -> PriceKey {
+        (
+            self.tx.raw.cycles_price,
+            Reverse(self.seq),
+            self.tx.tx_hash.clone(),
+        )
     }
 
     pub(crate) fn set_removed(&self) {
@@ -59,11 +94,29 @@ impl TxWrapper {
         self.removed.load(Ordering::SeqCst)
     }
 
+    // Returns true only for the first call.
+    #[inline]
+    fn release(&self) -> bool {
+        !self.released.swap(true, Ordering::SeqCst)
+    }
+
     #[inline]
     fn is_proposed(&self) -> bool {
         self.proposed.load(Ordering::SeqCst)
     }
 
+    #[inline]
+    fn set_packaged(&self, generation: u64) {
+        self.packaged.store(generation + 1, Ordering::SeqCst);
+    }
+
+    // Transactions which may be committed in the next block, they must stay
+    // in the pool for `get_full_txs`.
+    #[inline]
+    fn is_in_flight(&self, generation: u64) -> bool {
+        self.is_proposed() || self.packaged.load(Ordering::SeqCst) == generation + 1
+    }
+
     #[inline]
     fn is_timeout(&self, current_height: u64, timeout: u64) -> bool {
         let tx_timeout = self.tx.raw.timeout;
@@ -74,6 +127,81 @@ impl TxWrapper {
 /// Share `TxWrapper` for collections in `TxCache`.
 pub type SharedTx = Arc<TxWrapper>;
 
+type PriceKey = (u64, Reverse<u64>, Hash);
+
+/// The fee charged before executing the transaction.
+pub(crate) fn max_fee(tx: &SignedTransaction) -> u64 {
+    tx.raw.cycles_limit.saturating_mul(tx.raw.cycles_price)
+}
+
+fn tx_size(tx: &SignedTransaction) -> ProtocolResult<usize> {
+    Ok(tx.encode_fixed()?.len())
+}
+
+/// Limits of pending transactions per sender, 0 means unlimited.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SenderLimit {
+    pub max_txs:   usize,
+    pub max_bytes: usize,
+}
+
+impl SenderLimit {
+    fn is_unlimited(&self) -> bool {
+        self.max_txs == 0 && self.max_bytes == 0
+    }
+
+    fn check(
+        &self,
+        usage: &SenderUsage,
+        tx: &SignedTransaction,
+        sender: &Address,
+        size: usize,
+    ) -> ProtocolResult<()> {
+        let exceed_txs = self.max_txs != 0 && usage.txs >= self.max_txs;
+        let exceed_bytes = self.max_bytes != 0 && usage.bytes + size > self.max_bytes;
+
+        if exceed_txs || exceed_bytes {
+            return Err(MemPoolError::ExceedSenderLimit {
+                tx_hash: tx.tx_hash.clone(),
+                sender:  sender.clone(),
+                txs:     usage.txs,
+                bytes:   usage.bytes,
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Pending transactions of a sender in the map.
+#[derive(Default)]
+struct SenderUsage {
+    txs:     usize,
+    bytes:   usize,
+    // Sum of max fees
+    max_fee: u64,
+    // Nonce to transaction hash
+    nonces:  HashMap<Hash, Hash>,
+}
+
+/// The order of transactions while packaging.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageOrder {
+    /// Insertion order. New transactions are rejected if the pool is full.
+    Fifo,
+    /// Highest `cycles_price` first, and insertion order among the same
+    /// price. If the pool is full, the lowest priced transaction is evicted
+    /// for a new one priced higher.
+    FeePriority,
+}
+
+impl Default for PackageOrder {
+    fn default() -> Self {
+        PackageOrder::Fifo
+    }
+}
+
 /// An enum stands for package stage
 #[derive(PartialEq, Eq)]
 enum Stage {
@@ -127,35 +255,76 @@ pub struct TxCache {
     /// still insert into the old queue. We use this state to make sure
     /// switch insertions *happen-before* old queue re-pop.
     concurrent_count: AtomicUsize,
+    /// Order of packaged transactions.
+    order:            PackageOrder,
+    /// Next insertion sequence.
+    seq:              AtomicU64,
+    /// Transactions in the map sorted by price, only maintained in fee
+    /// priority order to find the one to evict.
+    price_index:      Mutex<BTreeSet<PriceKey>>,
+    /// Limits of pending transactions per sender.
+    sender_limit:     SenderLimit,
+    /// Pending transactions of each sender in the map.
+    senders:          Mutex<HashMap<Address, SenderUsage>>,
+    /// Increased by every flush. Transactions ordered by proposals since the
+    /// last flush are not evicted.
+    generation:       AtomicU64,
+    /// Held while taking transactions off the incumbent queue and switching
+    /// queue roles.
+    queue_lock:       Mutex<()>,
 }
 
 impl TxCache {
-    pub fn new(pool_size: usize) -> Self {
+    pub fn new(pool_size: usize, order: PackageOrder) -> Self {
         TxCache {
-            queue_0:          ArrayQueue::new(pool_size * 2),
-            queue_1:          ArrayQueue::new(pool_size * 2),
-            map:              Map::new(pool_size * 2),
-            is_zero:          AtomicBool::new(true),
+            queue_0: ArrayQueue::new(pool_size * 2),
+            queue_1: ArrayQueue::new(pool_size * 2),
+            map: Map::new(pool_size * 2),
+            is_zero: AtomicBool::new(true),
             concurrent_count: AtomicUsize::new(0),
+            order,
+            seq: AtomicU64::new(0),
+            price_index: Mutex::new(BTreeSet::new()),
+            sender_limit: SenderLimit::default(),
+            senders: Mutex::new(HashMap::new()),
+            generation: AtomicU64::new(0),
+            queue_lock: Mutex::new(()),
         }
     }
 
+    pub fn set_sender_limit(&mut self, sender_limit: SenderLimit) {
+        self.sender_limit = sender_limit;
+    }
+
     pub fn len(&self) -> usize {
         self.map.len()
     }
 
     pub fn insert_new_tx(&self, signed_tx: SignedTransaction) -> ProtocolResult<()> {
         let tx_hash = signed_tx.tx_hash.clone();
-        let tx_wrapper = TxWrapper::new(signed_tx);
+        let tx_wrapper = TxWrapper::new(signed_tx, self.next_seq())?;
         let shared_tx = Arc::new(tx_wrapper);
-        self.insert(tx_hash, shared_tx)
+        self.insert(tx_hash, shared_tx, true)
     }
 
     pub fn insert_propose_tx(&self, signed_tx: SignedTransaction) -> ProtocolResult<()> {
         let tx_hash = signed_tx.tx_hash.clone();
-        let tx_wrapper = TxWrapper::propose(signed_tx);
+        // Proposed transactions are not limited, since they are required
+        // by consensus.
+        let tx_wrapper = TxWrapper::propose(signed_tx, self.next_seq())?;
         let shared_tx = Arc::new(tx_wrapper);
-        self.insert(tx_hash, shared_tx)
+        self.insert(tx_hash, shared_tx, false)
+    }
+
+    /// Mark the known transactions ordered by a proposal of another node.
+    pub fn mark_packaged(&self, tx_hashes: &[Hash]) {
+        let _queue_guard = self.queue_lock.lock();
+        let generation = self.generation.load(Ordering::SeqCst);
+        for tx_hash in tx_hashes {
+            if let Some(shared_tx) = self.map.get(tx_hash) {
+                shared_tx.set_packaged(generation);
+            }
+        }
     }
 
     pub fn show_unknown(&self, tx_hashes: Vec<Hash>) -> Vec<Hash> {
@@ -166,42 +335,47 @@ impl TxCache {
     }
 
     pub fn flush(&self, tx_hashes: &[Hash], current_height: u64, timeout: u64) {
+        let mut removed_txs = Vec::new();
         for tx_hash in tx_hashes {
             let opt = self.map.get(tx_hash);
             if let Some(shared_tx) = opt {
                 shared_tx.set_removed();
+                removed_txs.push(shared_tx);
             }
         }
+        self.release(&removed_txs);
         // Dividing set removed and remove into two loops is to avoid lock competition.
         self.map.deletes(tx_hashes);
-        self.flush_incumbent_queue(current_height, timeout);
+        self.generation.fetch_add(1, Ordering::SeqCst);
+
+        let _queue_guard = self.queue_lock.lock();
+        self.requeue(|shared_tx| shared_tx.is_timeout(current_height, timeout));
     }
 
     pub fn package(
         &self,
-        _cycles_limit: u64,
+        cycles_limit: u64,
         tx_num_limit: u64,
         current_height: u64,
         timeout: u64,
     ) -> ProtocolResult<MixedTxHashes> {
+        // Held until the ordered transactions are marked, so they are not
+        // replaced meanwhile.
+        let _queue_guard = self.queue_lock.lock();
         let queue_role = self.get_queue_role();
 
         let mut order_tx_hashes = Vec::new();
         let mut propose_tx_hashes = Vec::new();
-        let mut timeout_tx_hashes = Vec::new();
-
-        let mut tx_count: u64 = 0;
-        let mut stage = Stage::OrderTxs;
+        let mut timeout_txs = Vec::new();
+        let mut valid_txs = Vec::new();
 
         loop {
             if let Ok(shared_tx) = queue_role.incumbent.pop() {
-                let tx_hash = &shared_tx.tx.tx_hash;
-
                 if shared_tx.is_removed() {
                     continue;
                 }
                 if shared_tx.is_timeout(current_height, timeout) {
-                    timeout_tx_hashes.push(tx_hash.clone());
+                    timeout_txs.push(shared_tx);
                     continue;
                 }
                 // After previous filter, tx are valid and should cache in temp_queue.
@@ -214,25 +388,10 @@ impl TxCache {
                         "[core_mempool]: candidate queue is full while package, delete {:?}",
                         &shared_tx.tx.tx_hash
                     );
-                    self.map.remove(&shared_tx.tx.tx_hash);
-                }
-
-                if stage == Stage::Finished
-                    || (stage == Stage::ProposeTxs && shared_tx.is_proposed())
-                {
-                    continue;
-                }
-                tx_count += 1;
-                if tx_count > tx_num_limit {
-                    stage = stage.next();
-                    tx_count = 1;
+                    self.remove_tx(&shared_tx);
                 }
 
-                match stage {
-                    Stage::OrderTxs => order_tx_hashes.push(tx_hash.clone()),
-                    Stage::ProposeTxs => propose_tx_hashes.push(tx_hash.clone()),
-                    Stage::Finished => {}
-                }
+                valid_txs.push(shared_tx);
             } else {
                 // Switch queue_roles
                 let new_role = self.switch_queue_role();
@@ -242,7 +401,49 @@ impl TxCache {
             }
         }
         // Remove timeout tx in map
-        self.map.deletes(&timeout_tx_hashes);
+        self.remove_txs(&timeout_txs);
+
+        // Cycles limit is only checked in fee priority order, since the
+        // cheap transactions behind are not packaged anyway.
+        let fee_priority = self.order == PackageOrder::FeePriority;
+        if fee_priority {
+            // Stable sort keeps insertion order among the same price.
+            valid_txs.sort_by_key(|shared_tx| Reverse(shared_tx.tx.raw.cycles_price));
+        }
+
+        let generation = self.generation.load(Ordering::SeqCst);
+        let mut tx_count: u64 = 0;
+        let mut cycles_count: u64 = 0;
+        let mut stage = Stage::OrderTxs;
+
+        for shared_tx in valid_txs.iter() {
+            if stage == Stage::ProposeTxs && shared_tx.is_proposed() {
+                continue;
+            }
+            let tx_cycles = if fee_priority {
+                shared_tx.tx.raw.cycles_limit
+            } else {
+                0
+            };
+
+            tx_count += 1;
+            cycles_count = cycles_count.saturating_add(tx_cycles);
+            if tx_count > tx_num_limit || cycles_count > cycles_limit {
+                stage = stage.next();
+                tx_count = 1;
+                cycles_count = tx_cycles;
+            }
+
+            let tx_hash = shared_tx.tx.tx_hash.clone();
+            match stage {
+                Stage::OrderTxs => {
+                    shared_tx.set_packaged(generation);
+                    order_tx_hashes.push(tx_hash);
+                }
+                Stage::ProposeTxs => propose_tx_hashes.push(tx_hash),
+                Stage::Finished => break,
+            }
+        }
 
         Ok(MixedTxHashes {
             order_tx_hashes,
@@ -261,11 +462,137 @@ impl TxCache {
         Ok(())
     }
 
+    /// Check whether a transaction of `cycles_price` can be inserted. In fee
+    /// priority order, a full pool accepts the transaction if it can evict a
+    /// cheaper one, see `evict_for`.
     #[inline]
-    pub fn check_reach_limit(&self, pool_size: usize) -> ProtocolResult<()> {
-        if self.len() >= pool_size {
-            return Err(MemPoolError::ReachLimit { pool_size }.into());
+    pub fn check_reach_limit(&self, pool_size: usize, cycles_price: u64) -> ProtocolResult<()> {
+        if self.len() < pool_size {
+            return Ok(());
         }
+
+        match self.order {
+            PackageOrder::Fifo => Err(MemPoolError::ReachLimit { pool_size }.into()),
+            PackageOrder::FeePriority => {
+                let lowest_price = self.price_index.lock().iter().next().map(|key| key.0);
+                match lowest_price {
+                    Some(price) if price < cycles_price => Ok(()),
+                    _ => Err(MemPoolError::ReachLimit { pool_size }.into()),
+                }
+            }
+        }
+    }
+
+    /// Check whether the sender of the transaction reaches its limit. The
+    /// limit is checked again while inserting.
+    pub fn check_sender_limit(&self, tx: &SignedTransaction) -> ProtocolResult<()> {
+        if self.sender_limit.is_unlimited() {
+            return Ok(());
+        }
+
+        let sender = tx.sender()?;
+        let size = tx_size(tx)?;
+        let empty = SenderUsage::default();
+        let senders = self.senders.lock();
+        let usage = senders.get(&sender).unwrap_or(&empty);
+        self.sender_limit.check(usage, tx, &sender, size)
+    }
+
+    /// Sum of the max fees of pending transactions from the sender.
+    pub fn pending_max_fee(&self, sender: &Address) -> u64 {
+        self.senders
+            .lock()
+            .get(sender)
+            .map_or(0, |usage| usage.max_fee)
+    }
+
+    /// Returns the pending transaction of the same sender and nonce.
+    pub fn get_same_nonce(
+        &self,
+        tx: &SignedTransaction,
+    ) -> ProtocolResult<Option<SignedTransaction>> {
+        let sender = tx.sender()?;
+        let tx_hash = self
+            .senders
+            .lock()
+            .get(&sender)
+            .and_then(|usage| usage.nonces.get(&tx.raw.nonce).cloned());
+
+        Ok(tx_hash.and_then(|tx_hash| self.get(&tx_hash)))
+    }
+
+    /// Remove a transaction, which is replaced by a new one. Fails if it may
+    /// be committed in the next block.
+    pub fn replace(&self, tx_hash: &Hash) -> ProtocolResult<()> {
+        let queue_guard = self.queue_lock.lock();
+        if let Some(shared_tx) = self.map.get(tx_hash) {
+            if shared_tx.is_in_flight(self.generation.load(Ordering::SeqCst)) {
+                return Err(MemPoolError::ReplaceInFlight {
+                    tx_hash: tx_hash.clone(),
+                }
+                .into());
+            }
+            shared_tx.set_removed();
+            self.remove_tx(&shared_tx);
+        }
+        drop(queue_guard);
+
+        self.compact_queue();
+        Ok(())
+    }
+
+    /// Whether the transaction may be committed in the next block.
+    pub fn is_in_flight(&self, tx_hash: &Hash) -> bool {
+        let generation = self.generation.load(Ordering::SeqCst);
+        self.map
+            .get(tx_hash)
+            .map_or(false, |shared_tx| shared_tx.is_in_flight(generation))
+    }
+
+    /// Evict the lowest priced transaction to make room for a transaction of
+    /// `cycles_price`, if the pool is full. Transactions which may be
+    /// committed in the next block are not evicted. Fails if there is nothing
+    /// cheaper to evict.
+    pub fn evict_for(&self, pool_size: usize, cycles_price: u64) -> ProtocolResult<()> {
+        if self.len() < pool_size || self.order == PackageOrder::Fifo {
+            return self.check_reach_limit(pool_size, cycles_price);
+        }
+
+        let generation = self.generation.load(Ordering::SeqCst);
+        let mut price_index = self.price_index.lock();
+        let mut stale_keys = vec![];
+        let mut evicted = None;
+
+        for key in price_index.iter() {
+            if key.0 >= cycles_price {
+                break;
+            }
+            match self.map.get(&key.2) {
+                Some(shared_tx) if shared_tx.is_in_flight(generation) => continue,
+                Some(shared_tx) => {
+                    evicted = Some((key.clone(), shared_tx));
+                    break;
+                }
+                // Index entries of removed transactions are dropped.
+                None => stale_keys.push(key.clone()),
+            }
+        }
+        for key in stale_keys.iter() {
+            price_index.remove(key);
+        }
+
+        let (key, shared_tx) = match evicted {
+            Some(evicted) => evicted,
+            None => return Err(MemPoolError::ReachLimit { pool_size }.into()),
+        };
+        price_index.remove(&key);
+        drop(price_index);
+
+        log::debug!("[core_mempool]: evict {:?} of price {}", key.2, key.0);
+        shared_tx.set_removed();
+        self.map.remove(&key.2);
+        self.release(&[shared_tx]);
+        self.compact_queue();
         Ok(())
     }
 
@@ -279,6 +606,18 @@ impl TxCache {
         self.map.get(tx_hash).map(|shared_tx| shared_tx.tx.clone())
     }
 
+    pub fn tx_hashes(&self, limit: usize) -> Vec<Hash> {
+        self.map.keys(limit)
+    }
+
+    pub fn txs(&self) -> Vec<SignedTransaction> {
+        self.map
+            .keys(self.len())
+            .iter()
+            .filter_map(|tx_hash| self.get(tx_hash))
+            .collect()
+    }
+
     #[allow(dead_code)]
     pub fn queue_len(&self) -> usize {
         if self.is_zero.load(Ordering::Relaxed) {
@@ -288,7 +627,9 @@ impl TxCache {
         }
     }
 
-    fn insert(&self, tx_hash: Hash, shared_tx: SharedTx) -> ProtocolResult<()> {
+    fn insert(&self, tx_hash: Hash, shared_tx: SharedTx, limited: bool) -> ProtocolResult<()> {
+        self.acquire(&shared_tx, limited)?;
+
         // If multiple transactions exactly the same insert concurrently,
         // this will prevent them to be both insert successfully into queue.
         if self
@@ -296,6 +637,7 @@ impl TxCache {
             .insert(tx_hash.clone(), Arc::<TxWrapper>::clone(&shared_tx))
             .is_some()
         {
+            self.release(&[shared_tx]);
             return Err(MemPoolError::Dup { tx_hash }.into());
         }
 
@@ -310,9 +652,108 @@ impl TxCache {
         if rst.is_err() {
             // If tx_hash exists, it will panic. So repeat check must do before insertion.
             self.map.remove(&tx_hash);
+            self.release(&[shared_tx]);
             Err(MemPoolError::Insert { tx_hash }.into())
         } else {
+            if self.order == PackageOrder::FeePriority {
+                self.price_index.lock().insert(shared_tx.price_key());
+            }
+            Ok(())
+        }
+    }
+
+    #[inline]
+    fn next_seq(&self) -> u64 {
+        self.seq.fetch_add(1, Ordering::SeqCst)
+    }
+
+    fn remove_tx(&self, shared_tx: &SharedTx) {
+        self.map.remove(&shared_tx.tx.tx_hash);
+        self.release(std::slice::from_ref(shared_tx));
+    }
+
+    fn remove_txs(&self, shared_txs: &[SharedTx]) {
+        let tx_hashes = shared_txs
+            .iter()
+            .map(|shared_tx| shared_tx.tx.tx_hash.clone())
+            .collect::<Vec<_>>();
+        self.map.deletes(&tx_hashes);
+        self.release(shared_txs);
+    }
+
+    // Count the transaction in sender usage. If `limited`, fails if the
+    // sender reaches its limit or the nonce is used by a pending transaction.
+    fn acquire(&self, shared_tx: &SharedTx, limited: bool) -> ProtocolResult<()> {
+        let tx = &shared_tx.tx;
+        let mut senders = self.senders.lock();
+        let usage = senders.entry(shared_tx.sender.clone()).or_default();
+
+        let rst = if !limited {
             Ok(())
+        } else if usage.nonces.contains_key(&tx.raw.nonce) {
+            Err(MemPoolError::DupNonce {
+                tx_hash: tx.tx_hash.clone(),
+                nonce:   tx.raw.nonce.clone(),
+            }
+            .into())
+        } else {
+            self.sender_limit
+                .check(usage, tx, &shared_tx.sender, shared_tx.size)
+        };
+        if rst.is_err() {
+            if usage.txs == 0 {
+                senders.remove(&shared_tx.sender);
+            }
+            return rst;
+        }
+
+        usage.txs += 1;
+        usage.bytes += shared_tx.size;
+        usage.max_fee = usage.max_fee.saturating_add(max_fee(tx));
+        usage
+            .nonces
+            .entry(tx.raw.nonce.clone())
+            .or_insert_with(|| tx.tx_hash.clone());
+        Ok(())
+    }
+
+    // Release sender usage and price index entries of transactions which
+    // leave the map.
+    fn release(&self, shared_txs: &[SharedTx]) {
+        let released = shared_txs
+            .iter()
+            .filter(|shared_tx| shared_tx.release())
+            .collect::<Vec<_>>();
+        if released.is_empty() {
+            return;
+        }
+
+        if self.order == PackageOrder::FeePriority {
+            let mut price_index = self.price_index.lock();
+            for shared_tx in released.iter() {
+                price_index.remove(&shared_tx.price_key());
+            }
+        }
+
+        let mut senders = self.senders.lock();
+        for shared_tx in released.iter() {
+            let drained = match senders.get_mut(&shared_tx.sender) {
+                Some(usage) => {
+                    usage.txs -= 1;
+                    usage.bytes -= shared_tx.size;
+                    usage.max_fee = usage.max_fee.saturating_sub(max_fee(&shared_tx.tx));
+
+                    let nonce = &shared_tx.tx.raw.nonce;
+                    if usage.nonces.get(nonce) == Some(&shared_tx.tx.tx_hash) {
+                        usage.nonces.remove(nonce);
+                    }
+                    usage.txs == 0
+                }
+                None => false,
+            };
+            if drained {
+                senders.remove(&shared_tx.sender);
+            }
         }
     }
 
@@ -332,7 +773,7 @@ impl TxCache {
                             "[core_mempool]: incumbent queue is full while process_omission_txs, delete {:?}",
                             &shared_tx.tx.tx_hash
                         );
-                        self.map.remove(&shared_tx.tx.tx_hash);
+                        self.remove_tx(&shared_tx);
                     }
                 }
                 break 'outer;
@@ -340,19 +781,34 @@ impl TxCache {
         }
     }
 
-    fn flush_incumbent_queue(&self, current_height: u64, timeout: u64) {
+    // Evicted and replaced transactions stay in the queue until it is popped.
+    // Drop them once the queue is three quarters full, so that it is not
+    // filled up by them between blocks. Skipped if the queue is being popped
+    // anyway.
+    fn compact_queue(&self) {
+        let queue = self.get_queue_role().incumbent;
+        if queue.len() * 4 < queue.capacity() * 3 {
+            return;
+        }
+        if let Some(_queue_guard) = self.queue_lock.try_lock() {
+            self.requeue(|_| false);
+        }
+    }
+
+    // Move transactions from the incumbent queue to the candidate one and
+    // switch their roles, removed and expired transactions are dropped. Must
+    // be called with `queue_lock` held.
+    fn requeue<F: Fn(&TxWrapper) -> bool>(&self, is_expired: F) {
         let queue_role = self.get_queue_role();
-        let mut timeout_tx_hashes = Vec::new();
+        let mut timeout_txs = Vec::new();
 
         loop {
             if let Ok(shared_tx) = queue_role.incumbent.pop() {
-                let tx_hash = &shared_tx.tx.tx_hash;
-
                 if shared_tx.is_removed() {
                     continue;
                 }
-                if shared_tx.is_timeout(current_height, timeout) {
-                    timeout_tx_hashes.push(tx_hash.clone());
+                if is_expired(&shared_tx) {
+                    timeout_txs.push(shared_tx);
                     continue;
                 }
                 // After previous filter, tx are valid and should cache in temp_queue.
@@ -362,10 +818,10 @@ impl TxCache {
                     .is_err()
                 {
                     log::error!(
-                        "[core_mempool]: candidate queue is full while flush_incumbent_queue, delete {:?}",
+                        "[core_mempool]: candidate queue is full while requeue, delete {:?}",
                         &shared_tx.tx.tx_hash
                     );
-                    self.map.remove(&shared_tx.tx.tx_hash);
+                    self.remove_tx(&shared_tx);
                 }
             } else {
                 // Switch queue_roles
@@ -376,7 +832,7 @@ impl TxCache {
             }
         }
         // Remove timeout tx in map
-        self.map.deletes(&timeout_tx_hashes);
+        self.remove_txs(&timeout_txs);
     }
 
     fn switch_queue_role(&self) -> QueueRole {
@@ -414,7 +870,7 @@ mod tests {
     use protocol::Bytes;
 
     use crate::map::Map;
-    use crate::tx_cache::{TxCache, TxWrapper};
+    use crate::tx_cache::{PackageOrder, TxCache, TxWrapper};
     use std::thread::JoinHandle;
 
     const POOL_SIZE: usize = 1000;
@@ -463,6 +919,7 @@ mod tests {
         SignedTransaction {
             raw,
             tx_hash,
+            scheme: Default::default(),
             pubkey: bytes.clone(),
             signature: bytes,
         }
@@ -506,7 +963,7 @@ mod tests {
                     .collect::<Vec<SignedTransaction>>()
             })
             .collect();
-        let tx_cache = TxCache::new(POOL_SIZE);
+        let tx_cache = TxCache::new(POOL_SIZE, PackageOrder::Fifo);
         concurrent_insert(txs, &tx_cache);
         assert_eq!(tx_cache.len(), POOL_SIZE / 2);
     }
@@ -517,18 +974,69 @@ mod tests {
         let tx = txs.get(0).unwrap();
         let map = Map::new(POOL_SIZE);
 
-        let tx_wrapper_0 = TxWrapper::new(tx.clone());
+        let tx_wrapper_0 = TxWrapper::new(tx.clone(), 0).unwrap();
         tx_wrapper_0.set_removed();
         map.insert(tx.tx_hash.clone(), Arc::new(tx_wrapper_0));
         let shared_tx_0 = map.get(&tx.tx_hash).unwrap();
         assert!(shared_tx_0.is_removed());
 
-        let tx_wrapper_1 = TxWrapper::new(tx.clone());
+        let tx_wrapper_1 = TxWrapper::new(tx.clone(), 1).unwrap();
         map.insert(tx.tx_hash.clone(), Arc::new(tx_wrapper_1));
         let shared_tx_1 = map.get(&tx.tx_hash).unwrap();
         assert!(shared_tx_1.is_removed());
     }
 
+    #[test]
+    fn test_pending_max_fee() {
+        let txs = gen_signed_txs(3);
+        let sender = txs[0].sender().unwrap();
+        let tx_cache = TxCache::new(POOL_SIZE, PackageOrder::Fifo);
+
+        for tx in txs.iter() {
+            tx_cache.insert_new_tx(tx.clone()).unwrap();
+        }
+        assert_eq!(tx_cache.pending_max_fee(&sender), 3 * TX_CYCLE);
+
+        tx_cache.flush(&[txs[0].tx_hash.clone()], CURRENT_H, CURRENT_H + TIMEOUT);
+        assert_eq!(tx_cache.pending_max_fee(&sender), 2 * TX_CYCLE);
+    }
+
+    #[test]
+    fn test_evict_packaged() {
+        let tx_cache = TxCache::new(2, PackageOrder::FeePriority);
+        let priced_tx = |cycles_price| {
+            let mut tx = mock_signed_tx(gen_bytes());
+            tx.raw.cycles_price = cycles_price;
+            tx
+        };
+
+        let cheap = priced_tx(1);
+        let packaged = priced_tx(2);
+        tx_cache.insert_new_tx(cheap.clone()).unwrap();
+        tx_cache.insert_new_tx(packaged.clone()).unwrap();
+
+        // Only the highest priced one is ordered
+        let order_tx_hashes = tx_cache
+            .package(CYCLE_LIMIT, 1, CURRENT_H, TIMEOUT)
+            .unwrap()
+            .order_tx_hashes;
+        assert_eq!(order_tx_hashes, vec![packaged.tx_hash.clone()]);
+
+        tx_cache.evict_for(2, 3).unwrap();
+        assert!(!tx_cache.contain(&cheap.tx_hash));
+        let expensive = priced_tx(3);
+        tx_cache.insert_new_tx(expensive).unwrap();
+
+        // The packaged one is skipped, and the other one is not cheaper
+        assert!(tx_cache.evict_for(2, 3).is_err());
+        assert!(tx_cache.contain(&packaged.tx_hash));
+
+        // Evictable again once the block is committed without it
+        tx_cache.flush(&[], CURRENT_H, CURRENT_H + TIMEOUT);
+        tx_cache.evict_for(2, 3).unwrap();
+        assert!(!tx_cache.contain(&packaged.tx_hash));
+    }
+
     #[bench]
     fn bench_gen_txs(b: &mut Bencher) {
         b.iter(|| {
@@ -540,7 +1048,7 @@ mod tests {
     fn bench_insert(b: &mut Bencher) {
         let txs = gen_signed_txs(TX_NUM);
         b.iter(|| {
-            let tx_cache = TxCache::new(POOL_SIZE);
+            let tx_cache = TxCache::new(POOL_SIZE, PackageOrder::Fifo);
             concurrent_insert(txs.clone(), &tx_cache);
             assert_eq!(tx_cache.len(), TX_NUM);
             assert_eq!(tx_cache.queue_len(), TX_NUM);
@@ -555,7 +1063,7 @@ mod tests {
             .map(|signed_tx| signed_tx.tx_hash.clone())
             .collect();
         b.iter(|| {
-            let tx_cache = TxCache::new(POOL_SIZE);
+            let tx_cache = TxCache::new(POOL_SIZE, PackageOrder::Fifo);
             concurrent_insert(txs.clone(), &tx_cache);
             assert_eq!(tx_cache.len(), TX_NUM);
             assert_eq!(tx_cache.queue_len(), TX_NUM);
@@ -574,7 +1082,7 @@ mod tests {
             .map(|signed_tx| signed_tx.tx_hash.clone())
             .collect();
         b.iter(|| {
-            let tx_cache = Arc::new(TxCache::new(POOL_SIZE));
+            let tx_cache = Arc::new(TxCache::new(POOL_SIZE, PackageOrder::Fifo));
             concurrent_insert(txs_base.clone(), &tx_cache);
             let handle = concurrent_flush(&tx_cache, txs_flush.clone(), CURRENT_H);
             concurrent_insert(txs_insert.clone(), &tx_cache);
@@ -587,7 +1095,7 @@ mod tests {
     #[bench]
     fn bench_package(b: &mut Bencher) {
         let txs = gen_signed_txs(TX_NUM);
-        let tx_cache = TxCache::new(POOL_SIZE);
+        let tx_cache = TxCache::new(POOL_SIZE, PackageOrder::Fifo);
         concurrent_insert(txs, &tx_cache);
         b.iter(|| {
             let mixed_tx_hashes = tx_cache
@@ -605,7 +1113,7 @@ mod tests {
         let txs = gen_signed_txs(TX_NUM / 2);
         let txs_insert = gen_signed_txs(TX_NUM / 2);
         b.iter(|| {
-            let tx_cache = Arc::new(TxCache::new(POOL_SIZE));
+            let tx_cache = Arc::new(TxCache::new(POOL_SIZE, PackageOrder::Fifo));
             concurrent_insert(txs.clone(), &tx_cache);
             let handle = concurrent_package(&tx_cache);
             concurrent_insert(txs_insert.clone(), &tx_cache);
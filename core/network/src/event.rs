@@ -80,6 +80,11 @@ pub enum MisbehaviorKind {
     // Maybe message codec or nonce incorrect
     #[display(fmt = "ping unexpect")]
     PingUnexpect,
+
+    // Invalid data reported by upper services, for example invalid
+    // transactions
+    #[display(fmt = "bad message")]
+    BadMessage,
 }
 
 #[derive(Debug, Display, PartialEq, Eq)]
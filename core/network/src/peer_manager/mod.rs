@@ -78,6 +78,8 @@ const MAX_RETRY_COUNT: u8 = 30;
 const SHORT_ALIVE_SESSION: u64 = 3; // seconds
 const WHITELIST_TIMEOUT: u64 = 2 * 60 * 60; // 2 hour
 const MAX_CONNECTING_MARGIN: usize = 10;
+const MAX_BAD_REPORTS: usize = 32;
+const BAD_REPORT_DECAY: u64 = 60; // seconds
 
 #[derive(Debug, Clone, Display, Serialize, Deserialize)]
 #[display(fmt = "{}", _0)]
@@ -560,6 +562,10 @@ pub struct PeerManagerHandle {
 }
 
 impl PeerManagerHandle {
+    pub fn connected(&self) -> usize {
+        self.inner.connected()
+    }
+
     pub fn peer_id(&self, sid: SessionId) -> Option<PeerId> {
         self.inner.session(sid).map(|s| s.peer.owned_id())
     }
@@ -906,6 +912,13 @@ impl PeerManager {
             }
         };
 
+        // Tolerate a few bad messages, which may be caused by state lag
+        if let BadMessage = kind {
+            if peer.inc_bad_reports() < MAX_BAD_REPORTS {
+                return;
+            }
+        }
+
         let sid = peer.session_id();
         if sid == SessionId::new(0) {
             // Impossible, connected session always bigger than 0
@@ -919,7 +932,7 @@ impl PeerManager {
         self.disconnect_session(sid);
 
         match kind {
-            PingTimeout => peer.retry.inc(),
+            PingTimeout | BadMessage => peer.retry.inc(),
             PingUnexpect | Discovery => peer.set_connectedness(Connectedness::Unconnectable), /* Give up this peer */
         }
     }
@@ -1,4 +1,4 @@
-use super::{time, PeerAddrSet, Retry, MAX_RETRY_COUNT};
+use super::{time, PeerAddrSet, Retry, BAD_REPORT_DECAY, MAX_RETRY_COUNT};
 
 use std::{
     borrow::Borrow,
@@ -13,7 +13,7 @@ use std::{
 };
 
 use derive_more::Display;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use protocol::{types::Address, Bytes};
 use tentacle::{
     secio::{PeerId, PublicKey},
@@ -24,6 +24,12 @@ use crate::error::ErrorKind;
 
 const CONNECTEDNESS_MASK: usize = 0b1110;
 
+#[derive(Debug, Default)]
+struct BadReports {
+    count:      usize,
+    decayed_at: u64,
+}
+
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Display)]
 #[repr(usize)]
 pub enum Connectedness {
@@ -79,6 +85,9 @@ pub struct Peer {
     connected_at:    AtomicU64,
     disconnected_at: AtomicU64,
     alive:           AtomicU64,
+    // Number of bad messages reported since last disconnection, one
+    // report decays every BAD_REPORT_DECAY seconds.
+    bad_reports:     Mutex<BadReports>,
 }
 
 impl Peer {
@@ -94,6 +103,7 @@ impl Peer {
             connected_at:    AtomicU64::new(0),
             disconnected_at: AtomicU64::new(0),
             alive:           AtomicU64::new(0),
+            bad_reports:     Mutex::new(BadReports::default()),
         }
     }
 
@@ -184,6 +194,27 @@ impl Peer {
         self.alive.store(live, Ordering::SeqCst);
     }
 
+    /// Returns the number of bad reports, including this one.
+    pub fn inc_bad_reports(&self) -> usize {
+        self.inc_bad_reports_at(time::now())
+    }
+
+    pub(super) fn inc_bad_reports_at(&self, now: u64) -> usize {
+        let mut reports = self.bad_reports.lock();
+
+        let decayed = now.saturating_sub(reports.decayed_at) / BAD_REPORT_DECAY;
+        if decayed as usize >= reports.count {
+            reports.count = 0;
+            reports.decayed_at = now;
+        } else {
+            reports.count -= decayed as usize;
+            reports.decayed_at += decayed * BAD_REPORT_DECAY;
+        }
+
+        reports.count += 1;
+        reports.count
+    }
+
     pub fn pubkey_to_chain_addr(pubkey: &PublicKey) -> Result<Address, ErrorKind> {
         let pubkey_bytes = Bytes::from(pubkey.inner_ref().clone());
 
@@ -203,6 +234,7 @@ impl Peer {
     pub fn mark_disconnected(&self) {
         self.set_connectedness(Connectedness::CanConnect);
         self.set_session_id(0.into());
+        *self.bad_reports.lock() = BadReports::default();
         self.update_disconnected();
         self.update_alive();
     }
@@ -1,7 +1,7 @@
 use super::{
     time, ArcPeer, Connectedness, ConnectingAttempt, Inner, MisbehaviorKind, PeerManager,
-    PeerManagerConfig, PeerMultiaddr, TestExpireTime, MAX_RETRY_COUNT, REPEATED_CONNECTION_TIMEOUT,
-    SHORT_ALIVE_SESSION, WHITELIST_TIMEOUT,
+    PeerManagerConfig, PeerMultiaddr, TestExpireTime, BAD_REPORT_DECAY, MAX_BAD_REPORTS,
+    MAX_RETRY_COUNT, REPEATED_CONNECTION_TIMEOUT, SHORT_ALIVE_SESSION, WHITELIST_TIMEOUT,
 };
 use crate::{
     common::ConnectedAddr,
@@ -1196,6 +1196,49 @@ async fn should_give_up_peer_for_discovery_on_misbehave() {
     );
 }
 
+#[tokio::test]
+async fn should_tolerate_bad_messages_until_max_reports_on_misbehave() {
+    let (mut mgr, _conn_rx) = make_manager(0, 20);
+    let remote_peers = make_sessions(&mut mgr, 1, 5000).await;
+
+    let test_peer = remote_peers.first().expect("get first peer");
+    for _ in 1..MAX_BAD_REPORTS {
+        let peer_misbehave = PeerManagerEvent::Misbehave {
+            pid:  test_peer.owned_id(),
+            kind: MisbehaviorKind::BadMessage,
+        };
+        mgr.poll_event(peer_misbehave).await;
+    }
+
+    let inner = mgr.core_inner();
+    assert_eq!(inner.connected(), 1, "should keep session");
+
+    let peer_misbehave = PeerManagerEvent::Misbehave {
+        pid:  test_peer.owned_id(),
+        kind: MisbehaviorKind::BadMessage,
+    };
+    mgr.poll_event(peer_misbehave).await;
+
+    assert_eq!(inner.connected(), 0, "should disconnect session");
+    assert_eq!(test_peer.retry.count(), 1, "should increase retry");
+}
+
+#[test]
+fn should_decay_bad_reports_over_time() {
+    let peer = make_peer(9527);
+    let now = 1_000;
+
+    for _ in 0..3 {
+        peer.inc_bad_reports_at(now);
+    }
+    assert_eq!(peer.inc_bad_reports_at(now + BAD_REPORT_DECAY - 1), 4);
+    assert_eq!(peer.inc_bad_reports_at(now + BAD_REPORT_DECAY * 2), 3);
+    assert_eq!(peer.inc_bad_reports_at(now + BAD_REPORT_DECAY * 100), 1);
+
+    peer.mark_disconnected();
+    assert_eq!(peer.inc_bad_reports(), 1, "should reset on disconnect");
+}
+
 #[tokio::test]
 async fn should_mark_session_blocked_on_session_blocked() {
     let (mut mgr, _conn_rx) = make_manager(0, 20);
@@ -15,7 +15,9 @@ use futures::{
 };
 use log::{debug, error, info};
 use protocol::{
-    traits::{Context, Gossip, MessageCodec, MessageHandler, Priority, Rpc},
+    traits::{
+        Context, Gossip, MessageCodec, MessageHandler, NetworkStatus, PeerTrust, Priority, Rpc,
+    },
     types::Address,
     ProtocolResult,
 };
@@ -28,23 +30,64 @@ use crate::{
     },
     endpoint::{Endpoint, EndpointScheme},
     error::NetworkError,
-    event::{ConnectionEvent, PeerManagerEvent},
+    event::{ConnectionEvent, MisbehaviorKind, PeerManagerEvent},
     message::RawSessionMessage,
     outbound::{NetworkGossip, NetworkRpc},
     peer_manager::{
-        DiscoveryAddrManager, IdentifyCallback, PeerManager, PeerManagerConfig, SharedSessions,
+        DiscoveryAddrManager, IdentifyCallback, PeerManager, PeerManagerConfig, PeerManagerHandle,
+        SharedSessions,
     },
     protocols::CoreProtocol,
     reactor::{MessageRouter, Reactor},
     rpc_map::RpcMap,
     selfcheck::SelfCheck,
+    traits::NetworkContext,
     NetworkConfig,
 };
 
 #[derive(Clone)]
 pub struct NetworkServiceHandle {
-    gossip: NetworkGossip<ConnectionServiceControl<CoreProtocol, SharedSessions>, Snappy>,
-    rpc:    NetworkRpc<ConnectionServiceControl<CoreProtocol, SharedSessions>, Snappy>,
+    gossip:   NetworkGossip<ConnectionServiceControl<CoreProtocol, SharedSessions>, Snappy>,
+    rpc:      NetworkRpc<ConnectionServiceControl<CoreProtocol, SharedSessions>, Snappy>,
+    peer_mgr: PeerManagerHandle,
+    mgr_tx:   UnboundedSender<PeerManagerEvent>,
+}
+
+impl NetworkStatus for NetworkServiceHandle {
+    fn connected_peers(&self) -> usize {
+        self.peer_mgr.connected()
+    }
+}
+
+impl PeerTrust for NetworkServiceHandle {
+    fn report_bad(&self, cx: Context, reason: &str) {
+        // Message handlers have remote peer id, rpc callers have session id
+        let pid = match cx.remote_peer_id() {
+            Ok(pid) => Some(pid),
+            Err(_) => cx
+                .session_id()
+                .ok()
+                .and_then(|sid| self.peer_mgr.peer_id(sid)),
+        };
+
+        let pid = match pid {
+            Some(pid) => pid,
+            None => {
+                debug!("network: no peer to report: {}", reason);
+                return;
+            }
+        };
+
+        debug!("network: report peer {:?}: {}", pid, reason);
+        let kind = MisbehaviorKind::BadMessage;
+        if self
+            .mgr_tx
+            .unbounded_send(PeerManagerEvent::Misbehave { pid, kind })
+            .is_err()
+        {
+            error!("network: peer manager dropped");
+        }
+    }
 }
 
 #[async_trait]
@@ -114,9 +157,10 @@ pub struct NetworkService {
     config: NetworkConfig,
 
     // Public service components
-    gossip:  NetworkGossip<ConnectionServiceControl<CoreProtocol, SharedSessions>, Snappy>,
-    rpc:     NetworkRpc<ConnectionServiceControl<CoreProtocol, SharedSessions>, Snappy>,
-    rpc_map: Arc<RpcMap>,
+    gossip:          NetworkGossip<ConnectionServiceControl<CoreProtocol, SharedSessions>, Snappy>,
+    rpc:             NetworkRpc<ConnectionServiceControl<CoreProtocol, SharedSessions>, Snappy>,
+    rpc_map:         Arc<RpcMap>,
+    peer_mgr_handle: PeerManagerHandle,
 
     // Core service
     net_conn_srv: Option<NetworkConnectionService>,
@@ -160,7 +204,7 @@ impl NetworkService {
         // Build service protocol
         let disc_sync_interval = config.discovery_sync_interval;
         let disc_addr_mgr = DiscoveryAddrManager::new(peer_mgr_handle.clone(), mgr_tx.clone());
-        let ident_callback = IdentifyCallback::new(peer_mgr_handle, mgr_tx.clone());
+        let ident_callback = IdentifyCallback::new(peer_mgr_handle.clone(), mgr_tx.clone());
         let proto = CoreProtocol::build()
             .ping(config.ping_interval, config.ping_timeout, mgr_tx.clone())
             .identify(ident_callback)
@@ -197,6 +241,7 @@ impl NetworkService {
             gossip,
             rpc,
             rpc_map,
+            peer_mgr_handle,
 
             net_conn_srv: Some(NetworkConnectionService::NoListen(conn_srv)),
             peer_mgr: Some(peer_mgr),
@@ -258,8 +303,10 @@ impl NetworkService {
 
     pub fn handle(&self) -> NetworkServiceHandle {
         NetworkServiceHandle {
-            gossip: self.gossip.clone(),
-            rpc:    self.rpc.clone(),
+            gossip:   self.gossip.clone(),
+            rpc:      self.rpc.clone(),
+            peer_mgr: self.peer_mgr_handle.clone(),
+            mgr_tx:   self.mgr_tx.clone(),
         }
     }
 
@@ -28,6 +28,7 @@ impl RocksAdapter {
             map_category(StorageCategory::Receipt),
             map_category(StorageCategory::SignedTransaction),
             map_category(StorageCategory::Wal),
+            map_category(StorageCategory::AddressIndex),
         ];
 
         let db = DB::open_cf(&opts, path, categories.iter()).map_err(RocksAdapterError::from)?;
@@ -167,6 +168,7 @@ const C_BLOCKS: &str = "c1";
 const C_SIGNED_TRANSACTIONS: &str = "c2";
 const C_RECEIPTS: &str = "c3";
 const C_WALS: &str = "c4";
+const C_ADDRESS_INDEXES: &str = "c5";
 
 fn map_category(c: StorageCategory) -> &'static str {
     match c {
@@ -174,6 +176,7 @@ fn map_category(c: StorageCategory) -> &'static str {
         StorageCategory::Receipt => C_RECEIPTS,
         StorageCategory::SignedTransaction => C_SIGNED_TRANSACTIONS,
         StorageCategory::Wal => C_WALS,
+        StorageCategory::AddressIndex => C_ADDRESS_INDEXES,
     }
 }
 
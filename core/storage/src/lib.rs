@@ -5,6 +5,8 @@ mod tests;
 
 pub mod adapter;
 
+use std::cmp;
+use std::collections::{HashMap, HashSet};
 use std::convert::From;
 use std::error::Error;
 use std::sync::Arc;
@@ -18,7 +20,7 @@ use protocol::fixed_codec::FixedCodec;
 use protocol::traits::{
     Storage, StorageAdapter, StorageBatchModify, StorageCategory, StorageSchema,
 };
-use protocol::types::{Block, Hash, Proof, Receipt, SignedTransaction};
+use protocol::types::{Address, Block, Hash, Proof, Receipt, SignedTransaction, GENESIS_HEIGHT};
 use protocol::Bytes;
 use protocol::{ProtocolError, ProtocolErrorKind, ProtocolResult};
 
@@ -26,13 +28,23 @@ lazy_static! {
     pub static ref LATEST_BLOCK_KEY: Hash = Hash::digest(Bytes::from("latest_hash"));
     pub static ref LATEST_PROOF_KEY: Hash = Hash::digest(Bytes::from("latest_proof"));
     pub static ref OVERLORD_WAL_KEY: Hash = Hash::digest(Bytes::from("overlord_wal"));
+    pub static ref PRUNED_HEIGHT_KEY: Hash = Hash::digest(Bytes::from("pruned_height"));
 }
 
+// Max number of blocks pruned on inserting a block, so that a node which has
+// just enabled pruning catches up gradually.
+const MAX_PRUNE_BLOCKS_PER_INSERT: u64 = 64;
+
 #[derive(Debug)]
 pub struct ImplStorage<Adapter> {
     adapter: Arc<Adapter>,
 
     latest_block: RwLock<Option<Block>>,
+
+    // Only the latest blocks are kept if set, 0 means keeping all.
+    keep_blocks:   u64,
+    // Blocks from 1 to it have been pruned, the genesis block is always kept.
+    pruned_height: RwLock<Option<u64>>,
 }
 
 impl<Adapter: StorageAdapter> ImplStorage<Adapter> {
@@ -40,8 +52,17 @@ impl<Adapter: StorageAdapter> ImplStorage<Adapter> {
         Self {
             adapter,
             latest_block: RwLock::new(None),
+            keep_blocks: 0,
+            pruned_height: RwLock::new(None),
         }
     }
+
+    /// Prune blocks older than the latest `keep_blocks` ones, with their
+    /// transactions and receipts.
+    pub fn with_keep_blocks(mut self, keep_blocks: u64) -> Self {
+        self.keep_blocks = keep_blocks;
+        self
+    }
 }
 
 macro_rules! impl_storage_schema_for {
@@ -71,6 +92,9 @@ impl_storage_schema_for!(HashBlockSchema, Hash, u64, Block);
 impl_storage_schema_for!(LatestBlockSchema, Hash, Block, Block);
 impl_storage_schema_for!(LatestProofSchema, Hash, Proof, Block);
 impl_storage_schema_for!(OverlordWalSchema, Hash, Bytes, Wal);
+impl_storage_schema_for!(AddressIndexSchema, Hash, Bytes, AddressIndex);
+impl_storage_schema_for!(PrunedHeightSchema, Hash, u64, Block);
+impl_storage_schema_for!(PrunedTxSchema, Hash, u64, SignedTransaction);
 
 macro_rules! batch_insert {
     ($self_: ident,$vec: expr, $schema: ident) => {
@@ -92,6 +116,21 @@ macro_rules! batch_insert {
     };
 }
 
+macro_rules! batch_remove {
+    ($self_: ident, $keys: expr, $schema: ident) => {
+        let keys = $keys;
+        let batch_remove = keys
+            .iter()
+            .map(|_| StorageBatchModify::Remove)
+            .collect::<Vec<_>>();
+
+        $self_
+            .adapter
+            .batch_modify::<$schema>(keys, batch_remove)
+            .await?;
+    };
+}
+
 macro_rules! get_batch {
     ($self_: ident, $keys: expr, $schema: ident) => {{
         let opt = $self_.adapter.get_batch::<$schema>($keys).await?;
@@ -106,9 +145,147 @@ macro_rules! get {
     }};
 }
 
+impl<Adapter: StorageAdapter> ImplStorage<Adapter> {
+    // Transactions sent by an address are numbered by the order of commit,
+    // each one is indexed by the address and its sequence number. A marker of
+    // the address and transaction makes indexing a transaction again a no-op.
+    // Entries, markers and counts are written in one batch.
+    async fn index_transactions_by_address(
+        &self,
+        signed_txs: &[SignedTransaction],
+    ) -> ProtocolResult<()> {
+        let mut counts: HashMap<Address, u64> = HashMap::new();
+        let mut markers = HashSet::new();
+        let mut keys = Vec::with_capacity(signed_txs.len() * 2);
+        let mut values = Vec::with_capacity(signed_txs.len() * 2);
+
+        for stx in signed_txs.iter() {
+            let sender = stx.sender()?;
+
+            let marker_key = address_tx_marker_key(&sender, &stx.tx_hash);
+            if !markers.insert(marker_key.clone())
+                || self
+                    .adapter
+                    .contains::<AddressIndexSchema>(marker_key.clone())
+                    .await?
+            {
+                continue;
+            }
+
+            let seq = match counts.get(&sender) {
+                Some(count) => *count,
+                None => self.get_address_tx_count(&sender).await?,
+            };
+
+            keys.push(address_tx_key(&sender, seq));
+            values.push(StorageBatchModify::Insert(stx.tx_hash.as_bytes()));
+            keys.push(marker_key);
+            values.push(StorageBatchModify::Insert(encode_seq(seq)));
+            counts.insert(sender, seq + 1);
+        }
+
+        for (address, count) in counts.into_iter() {
+            keys.push(address_tx_count_key(&address));
+            values.push(StorageBatchModify::Insert(encode_seq(count)));
+        }
+
+        self.adapter
+            .batch_modify::<AddressIndexSchema>(keys, values)
+            .await
+    }
+
+    async fn get_address_tx_count(&self, address: &Address) -> ProtocolResult<u64> {
+        match self
+            .adapter
+            .get::<AddressIndexSchema>(address_tx_count_key(address))
+            .await?
+        {
+            Some(bytes) => decode_seq(&bytes),
+            None => Ok(0),
+        }
+    }
+
+    async fn get_pruned_height(&self) -> ProtocolResult<u64> {
+        if let Some(height) = *self.pruned_height.read().await {
+            return Ok(height);
+        }
+
+        let height = self
+            .adapter
+            .get::<PrunedHeightSchema>(PRUNED_HEIGHT_KEY.clone())
+            .await?
+            .unwrap_or(GENESIS_HEIGHT);
+        self.pruned_height.write().await.replace(height);
+        Ok(height)
+    }
+
+    // Blocks older than the latest `keep_blocks` ones are deleted, with their
+    // transactions and receipts. The address index is kept, and each pruned
+    // transaction leaves the height of its block, so that querying it fails
+    // with `StorageError::Pruned`.
+    async fn prune_blocks(&self, latest_height: u64) -> ProtocolResult<()> {
+        if self.keep_blocks == 0 || latest_height <= self.keep_blocks {
+            return Ok(());
+        }
+
+        let pruned_height = self.get_pruned_height().await?;
+        let target = cmp::min(
+            latest_height - self.keep_blocks,
+            pruned_height + MAX_PRUNE_BLOCKS_PER_INSERT,
+        );
+        if target <= pruned_height {
+            return Ok(());
+        }
+
+        let mut heights = Vec::new();
+        let mut block_hashes = Vec::new();
+        let mut tx_hashes = Vec::new();
+        let mut tx_heights = Vec::new();
+        for height in pruned_height + 1..=target {
+            if let Some(block) = self.adapter.get::<BlockSchema>(height).await? {
+                block_hashes.push(Hash::digest(block.encode_fixed()?));
+                for tx_hash in block.ordered_tx_hashes {
+                    tx_heights.push(StorageBatchModify::Insert(height));
+                    tx_hashes.push(tx_hash);
+                }
+            }
+            heights.push(height);
+        }
+
+        let pruned_tx_keys = tx_hashes.iter().map(pruned_tx_key).collect();
+        self.adapter
+            .batch_modify::<PrunedTxSchema>(pruned_tx_keys, tx_heights)
+            .await?;
+        batch_remove!(self, tx_hashes.clone(), TransactionSchema);
+        batch_remove!(self, tx_hashes, ReceiptSchema);
+        batch_remove!(self, block_hashes, HashBlockSchema);
+        batch_remove!(self, heights, BlockSchema);
+
+        // Saved at last, so that an interrupted pruning is done again.
+        self.adapter
+            .insert::<PrunedHeightSchema>(PRUNED_HEIGHT_KEY.clone(), target)
+            .await?;
+        self.pruned_height.write().await.replace(target);
+        Ok(())
+    }
+
+    async fn check_tx_pruned(&self, tx_hash: &Hash) -> ProtocolResult<()> {
+        let key = pruned_tx_key(tx_hash);
+        if let Some(height) = self.adapter.get::<PrunedTxSchema>(key).await? {
+            return Err(StorageError::Pruned {
+                height,
+                pruned_height: self.get_pruned_height().await?,
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl<Adapter: StorageAdapter> Storage for ImplStorage<Adapter> {
     async fn insert_transactions(&self, signed_txs: Vec<SignedTransaction>) -> ProtocolResult<()> {
+        self.index_transactions_by_address(&signed_txs).await?;
         batch_insert!(self, signed_txs, TransactionSchema);
         Ok(())
     }
@@ -128,6 +305,7 @@ impl<Adapter: StorageAdapter> Storage for ImplStorage<Adapter> {
             .await?;
 
         self.latest_block.write().await.replace(block);
+        self.prune_blocks(height).await?;
 
         Ok(())
     }
@@ -145,8 +323,24 @@ impl<Adapter: StorageAdapter> Storage for ImplStorage<Adapter> {
     }
 
     async fn get_transaction_by_hash(&self, tx_hash: Hash) -> ProtocolResult<SignedTransaction> {
-        let stx = get!(self, tx_hash, TransactionSchema);
-        Ok(stx)
+        match self.try_get_transaction_by_hash(tx_hash).await? {
+            Some(stx) => Ok(stx),
+            None => Err(StorageError::GetNone.into()),
+        }
+    }
+
+    async fn try_get_transaction_by_hash(
+        &self,
+        tx_hash: Hash,
+    ) -> ProtocolResult<Option<SignedTransaction>> {
+        let opt_stx = self
+            .adapter
+            .get::<TransactionSchema>(tx_hash.clone())
+            .await?;
+        if opt_stx.is_none() {
+            self.check_tx_pruned(&tx_hash).await?;
+        }
+        Ok(opt_stx)
     }
 
     async fn get_transactions(&self, hashes: Vec<Hash>) -> ProtocolResult<Vec<SignedTransaction>> {
@@ -154,6 +348,40 @@ impl<Adapter: StorageAdapter> Storage for ImplStorage<Adapter> {
         Ok(stxs)
     }
 
+    async fn get_transactions_by_address(
+        &self,
+        address: Address,
+        offset: u64,
+        limit: u64,
+    ) -> ProtocolResult<Vec<SignedTransaction>> {
+        let count = self.get_address_tx_count(&address).await?;
+
+        let keys = (0..count)
+            .rev()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|seq| address_tx_key(&address, seq))
+            .collect::<Vec<_>>();
+        let hashes = get_batch!(self, keys, AddressIndexSchema)
+            .into_iter()
+            .map(Hash::from_bytes)
+            .collect::<ProtocolResult<Vec<_>>>()?;
+
+        // Pages don't skip the pruned transactions.
+        let opt_stxs = self
+            .adapter
+            .get_batch::<TransactionSchema>(hashes.clone())
+            .await?;
+        let mut stxs = Vec::with_capacity(opt_stxs.len());
+        for (tx_hash, opt_stx) in hashes.iter().zip(opt_stxs) {
+            match opt_stx {
+                Some(stx) => stxs.push(stx),
+                None => self.check_tx_pruned(tx_hash).await?,
+            }
+        }
+        Ok(stxs)
+    }
+
     async fn get_latest_block(&self) -> ProtocolResult<Block> {
         let opt_block = { self.latest_block.read().await.clone() };
 
@@ -165,8 +393,19 @@ impl<Adapter: StorageAdapter> Storage for ImplStorage<Adapter> {
     }
 
     async fn get_block_by_height(&self, height: u64) -> ProtocolResult<Block> {
-        let block = get!(self, height, BlockSchema);
-        Ok(block)
+        if let Some(block) = self.adapter.get::<BlockSchema>(height).await? {
+            return Ok(block);
+        }
+
+        let pruned_height = self.get_pruned_height().await?;
+        if height != GENESIS_HEIGHT && height <= pruned_height {
+            return Err(StorageError::Pruned {
+                height,
+                pruned_height,
+            }
+            .into());
+        }
+        Err(StorageError::GetNone.into())
     }
 
     async fn get_block_by_hash(&self, block_hash: Hash) -> ProtocolResult<Block> {
@@ -176,8 +415,13 @@ impl<Adapter: StorageAdapter> Storage for ImplStorage<Adapter> {
     }
 
     async fn get_receipt(&self, hash: Hash) -> ProtocolResult<Receipt> {
-        let receipt = get!(self, hash, ReceiptSchema);
-        Ok(receipt)
+        match self.adapter.get::<ReceiptSchema>(hash.clone()).await? {
+            Some(receipt) => Ok(receipt),
+            None => {
+                self.check_tx_pruned(&hash).await?;
+                Err(StorageError::GetNone.into())
+            }
+        }
     }
 
     async fn get_receipts(&self, hashes: Vec<Hash>) -> ProtocolResult<Vec<Receipt>> {
@@ -203,6 +447,45 @@ impl<Adapter: StorageAdapter> Storage for ImplStorage<Adapter> {
     }
 }
 
+fn address_tx_count_key(address: &Address) -> Hash {
+    Hash::digest(address.as_bytes())
+}
+
+fn address_tx_key(address: &Address, seq: u64) -> Hash {
+    let mut key = address.as_bytes().to_vec();
+    key.extend_from_slice(&seq.to_be_bytes());
+
+    Hash::digest(Bytes::from(key))
+}
+
+fn address_tx_marker_key(address: &Address, tx_hash: &Hash) -> Hash {
+    let mut key = address.as_bytes().to_vec();
+    key.extend_from_slice(&tx_hash.as_bytes());
+
+    Hash::digest(Bytes::from(key))
+}
+
+fn pruned_tx_key(tx_hash: &Hash) -> Hash {
+    let mut key = b"pruned".to_vec();
+    key.extend_from_slice(&tx_hash.as_bytes());
+
+    Hash::digest(Bytes::from(key))
+}
+
+fn encode_seq(seq: u64) -> Bytes {
+    Bytes::from(seq.to_be_bytes().to_vec())
+}
+
+fn decode_seq(bytes: &Bytes) -> ProtocolResult<u64> {
+    if bytes.len() != 8 {
+        return Err(StorageError::InvalidAddressIndex.into());
+    }
+
+    let mut seq = [0u8; 8];
+    seq.copy_from_slice(bytes);
+    Ok(u64::from_be_bytes(seq))
+}
+
 fn opts_to_flat<T>(values: Vec<Option<T>>) -> Vec<T> {
     values
         .into_iter()
@@ -219,6 +502,19 @@ fn check_none<T>(opt: Option<T>) -> ProtocolResult<T> {
 pub enum StorageError {
     #[display(fmt = "get none")]
     GetNone,
+
+    #[display(fmt = "invalid address index")]
+    InvalidAddressIndex,
+
+    #[display(
+        fmt = "block {} has been pruned, blocks up to {} are pruned",
+        height,
+        pruned_height
+    )]
+    Pruned {
+        height:        u64,
+        pruned_height: u64,
+    },
 }
 
 impl Error for StorageError {}
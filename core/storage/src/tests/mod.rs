@@ -39,6 +39,7 @@ fn mock_signed_tx(tx_hash: Hash) -> SignedTransaction {
     SignedTransaction {
         raw,
         tx_hash,
+        scheme: Default::default(),
         pubkey: Default::default(),
         signature: Default::default(),
     }
@@ -61,6 +62,7 @@ fn mock_receipt(tx_hash: Hash) -> Receipt {
         height: 10,
         tx_hash,
         cycles_used: 10,
+        fee: 0,
         events: vec![],
         response,
     }
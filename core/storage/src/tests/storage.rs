@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use protocol::fixed_codec::FixedCodec;
 use protocol::traits::Storage;
-use protocol::types::Hash;
+use protocol::types::{Address, Hash};
 
 use crate::adapter::memory::MemoryAdapter;
 use crate::tests::{get_random_bytes, mock_block, mock_proof, mock_receipt, mock_signed_tx};
@@ -78,6 +78,57 @@ fn test_storage_transactions_insert() {
     }
 }
 
+#[test]
+fn test_storage_transactions_by_address() {
+    let storage = ImplStorage::new(Arc::new(MemoryAdapter::new()));
+
+    let mut hashes = Vec::new();
+    for _ in 0..2 {
+        let mut transactions = Vec::new();
+
+        for _ in 0..5 {
+            let tx_hash = Hash::digest(get_random_bytes(10));
+            hashes.push(tx_hash.clone());
+            transactions.push(mock_signed_tx(tx_hash));
+        }
+
+        let mut other = mock_signed_tx(Hash::digest(get_random_bytes(10)));
+        other.pubkey = get_random_bytes(33);
+        transactions.push(other);
+
+        exec!(storage.insert_transactions(transactions));
+    }
+
+    let address = Address::from_pubkey_bytes(Default::default()).unwrap();
+    hashes.reverse();
+
+    let transactions = exec!(storage.get_transactions_by_address(address.clone(), 0, 100));
+    assert_eq!(transactions.len(), 10);
+
+    let transactions = exec!(storage.get_transactions_by_address(address, 3, 4));
+    assert_eq!(transactions.len(), 4);
+    for i in 0..4 {
+        assert_eq!(transactions.get(i).unwrap().tx_hash, hashes[3 + i]);
+    }
+}
+
+#[test]
+fn test_storage_index_transactions_again() {
+    let storage = ImplStorage::new(Arc::new(MemoryAdapter::new()));
+
+    let transactions = (0..5)
+        .map(|_| mock_signed_tx(Hash::digest(get_random_bytes(10))))
+        .collect::<Vec<_>>();
+    exec!(storage.insert_transactions(transactions.clone()));
+    // Inserted again on sync or on commit after restart
+    exec!(storage.insert_transactions(transactions.clone()));
+
+    let address = Address::from_pubkey_bytes(Default::default()).unwrap();
+    let indexed = exec!(storage.get_transactions_by_address(address, 0, 100));
+    assert_eq!(indexed.len(), 5);
+    assert_eq!(indexed[0].tx_hash, transactions[4].tx_hash);
+}
+
 #[test]
 fn test_storage_latest_proof_insert() {
     let storage = ImplStorage::new(Arc::new(MemoryAdapter::new()));
@@ -100,3 +151,43 @@ fn test_storage_wal_insert() {
     let info_2 = exec!(storage.load_overlord_wal());
     assert_eq!(info, info_2);
 }
+
+#[test]
+fn test_storage_prune_blocks() {
+    let storage = ImplStorage::new(Arc::new(MemoryAdapter::new())).with_keep_blocks(3);
+
+    let mut hashes = Vec::new();
+    for height in 1..=10 {
+        let tx_hash = Hash::digest(get_random_bytes(10));
+        hashes.push(tx_hash.clone());
+
+        let mut block = mock_block(height, Hash::digest(get_random_bytes(10)));
+        block.ordered_tx_hashes = vec![tx_hash.clone()];
+
+        exec!(storage.insert_transactions(vec![mock_signed_tx(tx_hash)]));
+        exec!(storage.insert_block(block));
+    }
+
+    for height in 1..=7 {
+        let res = futures::executor::block_on(storage.get_block_by_height(height));
+        assert!(res.unwrap_err().to_string().contains("Pruned"));
+    }
+    for height in 8..=10 {
+        let block = exec!(storage.get_block_by_height(height));
+        assert_eq!(height, block.header.height);
+    }
+
+    let res = futures::executor::block_on(storage.get_transaction_by_hash(hashes[6].clone()));
+    assert!(res.unwrap_err().to_string().contains("Pruned"));
+    let res = futures::executor::block_on(storage.try_get_transaction_by_hash(hashes[6].clone()));
+    assert!(res.unwrap_err().to_string().contains("Pruned"));
+    let stx = exec!(storage.get_transaction_by_hash(hashes[7].clone()));
+    assert_eq!(stx.tx_hash, hashes[7]);
+
+    // Pages of the address index fail instead of skipping pruned ones
+    let address = Address::from_pubkey_bytes(Default::default()).unwrap();
+    let stxs = exec!(storage.get_transactions_by_address(address.clone(), 0, 3));
+    assert_eq!(stxs.len(), 3);
+    let res = futures::executor::block_on(storage.get_transactions_by_address(address, 0, 4));
+    assert!(res.unwrap_err().to_string().contains("Pruned"));
+}
@@ -0,0 +1,160 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use derive_more::Display;
+
+use protocol::traits::AdmissionControl;
+use protocol::types::{Address, Admission, ServiceContext};
+use protocol::{ProtocolError, ProtocolErrorKind, ProtocolResult};
+
+// Only the listed senders may submit transactions.
+pub struct SenderAllowList {
+    senders: HashSet<Address>,
+}
+
+impl SenderAllowList {
+    pub fn new(senders: Vec<Address>) -> Self {
+        Self {
+            senders: senders.into_iter().collect(),
+        }
+    }
+}
+
+impl AdmissionControl for SenderAllowList {
+    fn next(&self, ctx: &ServiceContext) -> ProtocolResult<()> {
+        let caller = ctx.get_caller();
+        if self.senders.contains(&caller) {
+            Ok(())
+        } else {
+            Err(AdmissionError::SenderNotAllowed(caller).into())
+        }
+    }
+}
+
+// The listed senders may not submit transactions.
+pub struct SenderDenyList {
+    senders: HashSet<Address>,
+}
+
+impl SenderDenyList {
+    pub fn new(senders: Vec<Address>) -> Self {
+        Self {
+            senders: senders.into_iter().collect(),
+        }
+    }
+}
+
+impl AdmissionControl for SenderDenyList {
+    fn next(&self, ctx: &ServiceContext) -> ProtocolResult<()> {
+        let caller = ctx.get_caller();
+        if self.senders.contains(&caller) {
+            Err(AdmissionError::SenderDenied(caller).into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+pub struct MinCyclesPrice {
+    min: u64,
+}
+
+impl MinCyclesPrice {
+    pub fn new(min: u64) -> Self {
+        Self { min }
+    }
+}
+
+impl AdmissionControl for MinCyclesPrice {
+    fn next(&self, ctx: &ServiceContext) -> ProtocolResult<()> {
+        let price = ctx.get_cycles_price();
+        if price < self.min {
+            return Err(AdmissionError::CyclesPriceTooLow {
+                price,
+                min: self.min,
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+// Restricts the methods which can be called of the listed services, other
+// services are not restricted.
+pub struct MethodAllowList {
+    methods: HashMap<String, HashSet<String>>,
+}
+
+impl MethodAllowList {
+    pub fn new(methods: HashMap<String, Vec<String>>) -> Self {
+        let methods = methods
+            .into_iter()
+            .map(|(service, methods)| (service, methods.into_iter().collect()))
+            .collect();
+
+        Self { methods }
+    }
+}
+
+impl AdmissionControl for MethodAllowList {
+    fn next(&self, ctx: &ServiceContext) -> ProtocolResult<()> {
+        let service = ctx.get_service_name();
+        let method = ctx.get_service_method();
+
+        match self.methods.get(service) {
+            Some(methods) if !methods.contains(method) => Err(AdmissionError::MethodNotAllowed {
+                service: service.to_owned(),
+                method:  method.to_owned(),
+            }
+            .into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Controls of the configured checks, in the order of the fields.
+pub fn admission_controls(admission: &Admission) -> Vec<Arc<dyn AdmissionControl>> {
+    let mut controls: Vec<Arc<dyn AdmissionControl>> = vec![];
+
+    if let Some(senders) = &admission.allow_senders {
+        controls.push(Arc::new(SenderAllowList::new(senders.clone())));
+    }
+    if !admission.deny_senders.is_empty() {
+        controls.push(Arc::new(SenderDenyList::new(
+            admission.deny_senders.clone(),
+        )));
+    }
+    if admission.min_cycles_price > 0 {
+        controls.push(Arc::new(MinCyclesPrice::new(admission.min_cycles_price)));
+    }
+    if !admission.allowed_methods.is_empty() {
+        controls.push(Arc::new(MethodAllowList::new(
+            admission.allowed_methods.clone().into_iter().collect(),
+        )));
+    }
+
+    controls
+}
+
+#[derive(Debug, Display)]
+pub enum AdmissionError {
+    #[display(fmt = "sender {:?} is not allowed", _0)]
+    SenderNotAllowed(Address),
+
+    #[display(fmt = "sender {:?} is denied", _0)]
+    SenderDenied(Address),
+
+    #[display(fmt = "cycles price {} is lower than {}", price, min)]
+    CyclesPriceTooLow { price: u64, min: u64 },
+
+    #[display(fmt = "method {} of service {} is not allowed", method, service)]
+    MethodNotAllowed { service: String, method: String },
+}
+
+impl std::error::Error for AdmissionError {}
+
+impl From<AdmissionError> for ProtocolError {
+    fn from(err: AdmissionError) -> ProtocolError {
+        ProtocolError::new(ProtocolErrorKind::Binding, Box::new(err))
+    }
+}
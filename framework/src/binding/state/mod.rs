@@ -7,12 +7,13 @@ pub use trie_db::{RocksTrieDB, RocksTrieDBError};
 use std::collections::HashMap;
 
 use bytes::Bytes;
-use cita_trie::DB as TrieDB;
+use cita_trie::{MemoryDB, DB as TrieDB};
+use derive_more::Display;
 
 use protocol::fixed_codec::FixedCodec;
-use protocol::traits::ServiceState;
+use protocol::traits::{ServiceState, StateProof};
 use protocol::types::{Address, Hash, MerkleRoot};
-use protocol::ProtocolResult;
+use protocol::{ProtocolError, ProtocolErrorKind, ProtocolResult};
 
 pub struct GeneralServiceState<DB: TrieDB> {
     trie: MPTTrie<DB>,
@@ -69,6 +70,10 @@ impl<DB: TrieDB> ServiceState for GeneralServiceState<DB> {
         self.trie.contains(&encoded_key)
     }
 
+    fn get_proof<Key: FixedCodec>(&self, key: &Key) -> ProtocolResult<Vec<Bytes>> {
+        self.trie.get_proof(&key.encode_fixed()?)
+    }
+
     // Insert a pair of key / value
     // Note: This key/value pair will go into the cache first
     // and will not be persisted to MPT until `commit` is called.
@@ -127,9 +132,47 @@ impl<DB: TrieDB> ServiceState for GeneralServiceState<DB> {
     }
 }
 
-fn get_address_key<Key: FixedCodec>(address: &Address, key: &Key) -> ProtocolResult<Hash> {
+/// Verify both levels of the state proof, and returns the value of the key in
+/// the service state, `None` means the key does not exist.
+pub fn verify_state_proof(proof: &StateProof) -> ProtocolResult<Option<Bytes>> {
+    let service_key = proof.service_name.encode_fixed()?;
+    let service_root = MPTTrie::<MemoryDB>::verify_proof(
+        &proof.state_root,
+        &service_key,
+        proof.root_proof.clone(),
+    )?
+    .ok_or_else(|| StateProofError::ServiceNotFound(proof.service_name.clone()))?;
+
+    if MerkleRoot::decode_fixed(service_root)? != proof.service_root {
+        return Err(StateProofError::ServiceRootMismatch.into());
+    }
+
+    MPTTrie::<MemoryDB>::verify_proof(&proof.service_root, &proof.key, proof.proof.clone())
+}
+
+pub(crate) fn get_address_key<Key: FixedCodec>(
+    address: &Address,
+    key: &Key,
+) -> ProtocolResult<Hash> {
     let mut hash_bytes = address.as_bytes().to_vec();
     hash_bytes.extend_from_slice(key.encode_fixed()?.as_ref());
 
     Ok(Hash::digest(Bytes::from(hash_bytes)))
 }
+
+#[derive(Debug, Display)]
+pub enum StateProofError {
+    #[display(fmt = "service {:?} was not found in the state root", _0)]
+    ServiceNotFound(String),
+
+    #[display(fmt = "service root mismatch")]
+    ServiceRootMismatch,
+}
+
+impl std::error::Error for StateProofError {}
+
+impl From<StateProofError> for ProtocolError {
+    fn from(err: StateProofError) -> ProtocolError {
+        ProtocolError::new(ProtocolErrorKind::Binding, Box::new(err))
+    }
+}
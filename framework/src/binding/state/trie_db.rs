@@ -1,15 +1,45 @@
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
 
 use bytes::Bytes;
 use derive_more::{Display, From};
-use rocksdb::{Options, WriteBatch, DB};
+use rlp::Rlp;
+use rocksdb::{Direction, IteratorMode, Options, WriteBatch, DB};
 
+use protocol::fixed_codec::FixedCodec;
+use protocol::types::MerkleRoot;
 use protocol::{ProtocolError, ProtocolErrorKind, ProtocolResult};
 
+const HASH_LENGTH: usize = 32;
+const PRUNE_BATCH_SIZE: usize = 1024;
+
+// Keys of the pruning data, none of them is as long as a hash, which is the
+// key of a trie node.
+const REFS_PREFIX: &[u8] = b"refs-";
+const FRESH_PREFIX: &[u8] = b"fresh-";
+const DEAD_PREFIX: &[u8] = b"dead-";
+const PINNED_KEY: &[u8] = b"pinned-roots";
+const GENERATION_KEY: &[u8] = b"prune-generation";
+
 pub struct RocksTrieDB {
     light: bool,
     db:    Arc<DB>,
+    // Reference counting of nodes, `None` if pruning is disabled.
+    prune: Option<Mutex<PruneState>>,
+}
+
+// A node is referenced by the stored nodes having it as a child or a value,
+// and by the pinned roots. Nodes without references are either roots of new
+// states, kept as fresh for two rounds, or dead and deleted.
+struct PruneState {
+    // References are counted on writing nodes once they are counted for the
+    // existing nodes, see `RocksTrieDB::init_prune`.
+    ready:      bool,
+    // Roots of the kept states.
+    pinned:     HashSet<Vec<u8>>,
+    // Number of pruning rounds.
+    generation: u64,
 }
 
 impl RocksTrieDB {
@@ -24,8 +54,430 @@ impl RocksTrieDB {
         Ok(RocksTrieDB {
             light,
             db: Arc::new(db),
+            prune: None,
         })
     }
+
+    /// Count references of the written nodes, so that the nodes of the states
+    /// no longer kept can be deleted by `prune`. Not for a `light` one, which
+    /// removes nodes regardless of their references.
+    pub fn with_prune(mut self) -> ProtocolResult<Self> {
+        let pinned = match self.db.get(PINNED_KEY).map_err(to_store_err)? {
+            Some(pinned) => Some(
+                Rlp::new(&pinned)
+                    .as_list::<Vec<u8>>()
+                    .map_err(RocksTrieDBError::from)?,
+            ),
+            None => None,
+        };
+        let generation: u64 = match self.db.get(GENERATION_KEY).map_err(to_store_err)? {
+            Some(generation) => rlp::decode(&generation).map_err(RocksTrieDBError::from)?,
+            None => 0,
+        };
+
+        self.prune = Some(Mutex::new(PruneState {
+            ready: pinned.is_some(),
+            pinned: pinned.unwrap_or_default().into_iter().collect(),
+            generation,
+        }));
+        Ok(self)
+    }
+
+    /// Count references of the nodes reachable from the given roots of the
+    /// root trie, and delete the other nodes, returns the number of deleted
+    /// nodes. It walks the whole state once when pruning is enabled for the
+    /// first time, and does nothing later.
+    pub fn init_prune(&self, roots: &[MerkleRoot]) -> ProtocolResult<usize> {
+        let state = match &self.prune {
+            Some(state) => state,
+            None => return Ok(0),
+        };
+        let mut state = lock(state);
+        if state.ready {
+            return Ok(0);
+        }
+
+        // Left by an interrupted run.
+        for prefix in &[REFS_PREFIX, FRESH_PREFIX, DEAD_PREFIX] {
+            self.delete_prefix(prefix)?;
+        }
+
+        // A node is walked on its first reference.
+        let pinned = roots
+            .iter()
+            .map(|root| root.as_bytes().to_vec())
+            .collect::<HashSet<_>>();
+        let mut refs = RefCounts::new(&self.db);
+        let mut stack = Vec::new();
+        for root in pinned.iter() {
+            if refs.add(root)? == 1 {
+                stack.push(root.clone());
+            }
+        }
+        while let Some(hash) = stack.pop() {
+            if let Some(node) = self.db.get(&hash).map_err(to_store_err)? {
+                for child in node_refs(&node)? {
+                    if refs.add(&child)? == 1 {
+                        stack.push(child);
+                    }
+                }
+            }
+
+            if refs.len() >= PRUNE_BATCH_SIZE {
+                refs.flush()?;
+            }
+        }
+        refs.flush()?;
+
+        let mut deleted = 0;
+        let mut batch = WriteBatch::default();
+        for (key, _) in self.db.iterator(IteratorMode::Start) {
+            if key.len() != HASH_LENGTH || refs.get(&key)? != 0 {
+                continue;
+            }
+
+            batch.delete(&key).map_err(to_store_err)?;
+            deleted += 1;
+            if deleted % PRUNE_BATCH_SIZE == 0 {
+                self.db.write(batch).map_err(to_store_err)?;
+                batch = WriteBatch::default();
+            }
+        }
+        self.save_pinned(&mut batch, &pinned, 0)?;
+        self.db.write(batch).map_err(to_store_err)?;
+
+        state.ready = true;
+        state.pinned = pinned;
+        state.generation = 0;
+        Ok(deleted)
+    }
+
+    /// Keep the states of the given roots of the root trie, and drop the ones
+    /// kept before, returns the number of deleted nodes. Only the nodes which
+    /// are no longer referenced are visited.
+    ///
+    /// Roots of new states which are not kept are deleted two rounds later,
+    /// so that the states being committed are never deleted.
+    pub fn prune(&self, roots: &[MerkleRoot]) -> ProtocolResult<usize> {
+        let state = match &self.prune {
+            Some(state) => state,
+            None => return Ok(0),
+        };
+
+        {
+            let mut state = lock(state);
+            if !state.ready {
+                return Ok(0);
+            }
+
+            let pinned = roots
+                .iter()
+                .map(|root| root.as_bytes().to_vec())
+                .collect::<HashSet<_>>();
+            let mut refs = RefCounts::new(&self.db);
+            let mut batch = WriteBatch::default();
+            for root in pinned.difference(&state.pinned) {
+                refs.add(root)?;
+            }
+            for root in state.pinned.difference(&pinned) {
+                if refs.sub(root)? == 0 {
+                    batch
+                        .put(prefixed(DEAD_PREFIX, root), b"")
+                        .map_err(to_store_err)?;
+                }
+            }
+
+            let mode = IteratorMode::From(FRESH_PREFIX, Direction::Forward);
+            for (key, value) in self.db.iterator(mode) {
+                if !key.starts_with(FRESH_PREFIX) {
+                    break;
+                }
+                let generation: u64 = rlp::decode(&value).map_err(RocksTrieDBError::from)?;
+                if generation + 2 > state.generation {
+                    continue;
+                }
+
+                let hash = &key[FRESH_PREFIX.len()..];
+                if refs.get(hash)? == 0 {
+                    batch
+                        .put(prefixed(DEAD_PREFIX, hash), b"")
+                        .map_err(to_store_err)?;
+                }
+                batch.delete(&key).map_err(to_store_err)?;
+            }
+
+            refs.write_to(&mut batch)?;
+            self.save_pinned(&mut batch, &pinned, state.generation + 1)?;
+            self.db.write(batch).map_err(to_store_err)?;
+
+            state.pinned = pinned;
+            state.generation += 1;
+        }
+
+        let mut deleted = 0;
+        loop {
+            let (handled, count) = self.delete_dead(state)?;
+            deleted += count;
+            if handled == 0 {
+                return Ok(deleted);
+            }
+        }
+    }
+
+    // Delete a batch of dead nodes, returns the numbers of handled entries and
+    // deleted nodes. Their children losing the last reference are dead next.
+    // The lock is released between batches, so that writing nodes isn't
+    // blocked for long.
+    fn delete_dead(&self, state: &Mutex<PruneState>) -> ProtocolResult<(usize, usize)> {
+        let _state = lock(state);
+
+        let mode = IteratorMode::From(DEAD_PREFIX, Direction::Forward);
+        let keys = self
+            .db
+            .iterator(mode)
+            .map(|(key, _)| key)
+            .take_while(|key| key.starts_with(DEAD_PREFIX))
+            .take(PRUNE_BATCH_SIZE)
+            .collect::<Vec<_>>();
+
+        let mut refs = RefCounts::new(&self.db);
+        let mut batch = WriteBatch::default();
+        let mut deleted = 0;
+        for key in keys.iter() {
+            batch.delete(key).map_err(to_store_err)?;
+
+            // Referenced again since it's dead.
+            let hash = &key[DEAD_PREFIX.len()..];
+            if refs.get(hash)? != 0 {
+                continue;
+            }
+
+            if let Some(node) = self.db.get(hash).map_err(to_store_err)? {
+                for child in node_refs(&node)? {
+                    if refs.sub(&child)? == 0 {
+                        batch
+                            .put(prefixed(DEAD_PREFIX, &child), b"")
+                            .map_err(to_store_err)?;
+                    }
+                }
+                batch.delete(hash).map_err(to_store_err)?;
+                deleted += 1;
+            }
+        }
+        refs.write_to(&mut batch)?;
+        self.db.write(batch).map_err(to_store_err)?;
+
+        Ok((keys.len(), deleted))
+    }
+
+    fn write_nodes(&self, keys: &[Vec<u8>], values: &[Vec<u8>]) -> Result<(), RocksTrieDBError> {
+        let mut batch = WriteBatch::default();
+        for (key, value) in keys.iter().zip(values.iter()) {
+            batch.put(key, value).map_err(to_store_err)?;
+        }
+
+        // Counted and written with the lock held, so that a node is never
+        // deleted after it's written again.
+        if let Some(state) = &self.prune {
+            let state = lock(state);
+            if state.ready {
+                self.count_refs(&state, keys, values, &mut batch)?;
+                return self.db.write(batch).map_err(to_store_err);
+            }
+        }
+
+        self.db.write(batch).map_err(to_store_err)
+    }
+
+    // Nodes written for the first time add references to their children.
+    // Nodes without references are the roots of new states, or nodes of
+    // dropped states written again, they are fresh and no longer dead.
+    fn count_refs(
+        &self,
+        state: &PruneState,
+        keys: &[Vec<u8>],
+        values: &[Vec<u8>],
+        batch: &mut WriteBatch,
+    ) -> Result<(), RocksTrieDBError> {
+        let mut refs = RefCounts::new(&self.db);
+        let mut written = HashSet::new();
+        for (key, value) in keys.iter().zip(values.iter()) {
+            if !written.insert(key) || self.db.get(key).map_err(to_store_err)?.is_some() {
+                continue;
+            }
+
+            for child in node_refs(value)? {
+                refs.add(&child)?;
+            }
+        }
+
+        for key in written {
+            if refs.get(key)? == 0 {
+                batch
+                    .delete(prefixed(DEAD_PREFIX, key))
+                    .map_err(to_store_err)?;
+                batch
+                    .put(prefixed(FRESH_PREFIX, key), rlp::encode(&state.generation))
+                    .map_err(to_store_err)?;
+            }
+        }
+        refs.write_to(batch)
+    }
+
+    fn save_pinned(
+        &self,
+        batch: &mut WriteBatch,
+        pinned: &HashSet<Vec<u8>>,
+        generation: u64,
+    ) -> Result<(), RocksTrieDBError> {
+        let pinned = pinned.iter().cloned().collect::<Vec<_>>();
+        batch
+            .put(PINNED_KEY, rlp::encode_list::<Vec<u8>, _>(&pinned))
+            .map_err(to_store_err)?;
+        batch
+            .put(GENERATION_KEY, rlp::encode(&generation))
+            .map_err(to_store_err)
+    }
+
+    fn delete_prefix(&self, prefix: &[u8]) -> Result<(), RocksTrieDBError> {
+        let mode = IteratorMode::From(prefix, Direction::Forward);
+        let mut batch = WriteBatch::default();
+        for (key, _) in self.db.iterator(mode) {
+            if !key.starts_with(prefix) {
+                break;
+            }
+            batch.delete(&key).map_err(to_store_err)?;
+        }
+        self.db.write(batch).map_err(to_store_err)
+    }
+}
+
+// Reference counts read from the db, changes are written in a batch.
+struct RefCounts<'a> {
+    db:      &'a DB,
+    changes: HashMap<Vec<u8>, u64>,
+}
+
+impl<'a> RefCounts<'a> {
+    fn new(db: &'a DB) -> Self {
+        RefCounts {
+            db,
+            changes: HashMap::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    fn get(&self, hash: &[u8]) -> Result<u64, RocksTrieDBError> {
+        if let Some(count) = self.changes.get(hash) {
+            return Ok(*count);
+        }
+
+        match self
+            .db
+            .get(prefixed(REFS_PREFIX, hash))
+            .map_err(to_store_err)?
+        {
+            Some(count) => Ok(rlp::decode(&count)?),
+            None => Ok(0),
+        }
+    }
+
+    fn add(&mut self, hash: &[u8]) -> Result<u64, RocksTrieDBError> {
+        let count = self.get(hash)? + 1;
+        self.changes.insert(hash.to_vec(), count);
+        Ok(count)
+    }
+
+    fn sub(&mut self, hash: &[u8]) -> Result<u64, RocksTrieDBError> {
+        let count = self.get(hash)?.saturating_sub(1);
+        self.changes.insert(hash.to_vec(), count);
+        Ok(count)
+    }
+
+    fn write_to(&mut self, batch: &mut WriteBatch) -> Result<(), RocksTrieDBError> {
+        for (hash, count) in self.changes.drain() {
+            let key = prefixed(REFS_PREFIX, &hash);
+            if count == 0 {
+                batch.delete(key).map_err(to_store_err)?;
+            } else {
+                batch.put(key, rlp::encode(&count)).map_err(to_store_err)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), RocksTrieDBError> {
+        let mut batch = WriteBatch::default();
+        self.write_to(&mut batch)?;
+        self.db.write(batch).map_err(to_store_err)
+    }
+}
+
+// Hashes referenced by a node. Values which are hashes are counted as well,
+// since the values of the root trie are the roots of service tries, other
+// ones only keep a node longer.
+fn node_refs(node: &[u8]) -> Result<Vec<Vec<u8>>, RocksTrieDBError> {
+    let mut refs = Vec::new();
+    collect_refs(&Rlp::new(node), &mut refs)?;
+    Ok(refs)
+}
+
+fn collect_refs(node: &Rlp, refs: &mut Vec<Vec<u8>>) -> Result<(), RocksTrieDBError> {
+    match node.item_count()? {
+        // A leaf or an extension, told by the flag of the compact key.
+        2 => {
+            let key = node.at(0)?.data()?;
+            let child = node.at(1)?;
+
+            if key.first().map_or(false, |b| b >> 4 >= 2) {
+                collect_value(&child, refs)
+            } else {
+                collect_child(&child, refs)
+            }
+        }
+        // A branch, the last item is the value.
+        17 => {
+            for i in 0..16 {
+                collect_child(&node.at(i)?, refs)?;
+            }
+            collect_value(&node.at(16)?, refs)
+        }
+        _ => Err(RocksTrieDBError::InvalidNode),
+    }
+}
+
+// A child is either the hash of a node, or a node shorter than a hash.
+fn collect_child(child: &Rlp, refs: &mut Vec<Vec<u8>>) -> Result<(), RocksTrieDBError> {
+    if child.is_list() {
+        return collect_refs(child, refs);
+    }
+
+    let hash = child.data()?;
+    if hash.len() == HASH_LENGTH {
+        refs.push(hash.to_vec());
+    }
+    Ok(())
+}
+
+fn collect_value(value: &Rlp, refs: &mut Vec<Vec<u8>>) -> Result<(), RocksTrieDBError> {
+    let value = value.data()?;
+    if value.is_empty() {
+        return Ok(());
+    }
+
+    if let Ok(root) = MerkleRoot::decode_fixed(Bytes::from(value.to_vec())) {
+        refs.push(root.as_bytes().to_vec());
+    }
+    Ok(())
+}
+
+fn prefixed(prefix: &[u8], hash: &[u8]) -> Vec<u8> {
+    let mut key = prefix.to_vec();
+    key.extend_from_slice(hash);
+    key
 }
 
 impl cita_trie::DB for RocksTrieDB {
@@ -40,10 +492,7 @@ impl cita_trie::DB for RocksTrieDB {
     }
 
     fn insert(&self, key: Vec<u8>, value: Vec<u8>) -> Result<(), Self::Error> {
-        self.db
-            .put(Bytes::from(key), Bytes::from(value))
-            .map_err(to_store_err)?;
-        Ok(())
+        self.write_nodes(&[key], &[value])
     }
 
     fn insert_batch(&self, keys: Vec<Vec<u8>>, values: Vec<Vec<u8>>) -> Result<(), Self::Error> {
@@ -51,15 +500,7 @@ impl cita_trie::DB for RocksTrieDB {
             return Err(RocksTrieDBError::BatchLengthMismatch);
         }
 
-        let mut batch = WriteBatch::default();
-        for i in 0..keys.len() {
-            let key = &keys[i];
-            let value = &values[i];
-            batch.put(key, value).map_err(to_store_err)?;
-        }
-
-        self.db.write(batch).map_err(to_store_err)?;
-        Ok(())
+        self.write_nodes(&keys, &values)
     }
 
     fn remove(&self, key: &[u8]) -> Result<(), Self::Error> {
@@ -100,6 +541,12 @@ pub enum RocksTrieDBError {
 
     #[display(fmt = "batch length dont match")]
     BatchLengthMismatch,
+
+    #[display(fmt = "rlp {}", _0)]
+    Decode(rlp::DecoderError),
+
+    #[display(fmt = "invalid trie node")]
+    InvalidNode,
 }
 
 impl std::error::Error for RocksTrieDBError {}
@@ -110,6 +557,12 @@ impl From<RocksTrieDBError> for ProtocolError {
     }
 }
 
+// A poisoned lock only means a panic before writing a batch, the state is
+// still consistent with the db.
+fn lock(state: &Mutex<PruneState>) -> MutexGuard<PruneState> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
 fn to_store_err(e: rocksdb::Error) -> RocksTrieDBError {
     log::error!("[framework] trie db {:?}", e);
     RocksTrieDBError::Store
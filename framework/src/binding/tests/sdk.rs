@@ -127,10 +127,26 @@ impl Storage for MockStorage {
         Ok(mock_signed_tx())
     }
 
+    async fn try_get_transaction_by_hash(
+        &self,
+        _tx_hash: Hash,
+    ) -> ProtocolResult<Option<SignedTransaction>> {
+        Ok(Some(mock_signed_tx()))
+    }
+
     async fn get_transactions(&self, _hashes: Vec<Hash>) -> ProtocolResult<Vec<SignedTransaction>> {
         Err(StoreError::GetNone.into())
     }
 
+    async fn get_transactions_by_address(
+        &self,
+        _: Address,
+        _: u64,
+        _: u64,
+    ) -> ProtocolResult<Vec<SignedTransaction>> {
+        Err(StoreError::GetNone.into())
+    }
+
     async fn get_latest_block(&self) -> ProtocolResult<Block> {
         Ok(mock_block(1))
     }
@@ -208,6 +224,7 @@ pub fn mock_signed_tx() -> SignedTransaction {
     SignedTransaction {
         raw:       mock_raw_tx(),
         tx_hash:   mock_hash(),
+        scheme:    Default::default(),
         pubkey:    Default::default(),
         signature: Default::default(),
     }
@@ -223,6 +240,7 @@ pub fn mock_receipt() -> Receipt {
         height:      13,
         tx_hash:     mock_hash(),
         cycles_used: 100,
+        fee:         100,
         events:      vec![mock_event()],
         response:    mock_receipt_response(),
     }
@@ -1,12 +1,16 @@
+use std::env;
+use std::fs;
 use std::sync::Arc;
 
 use bytes::Bytes;
 use cita_trie::MemoryDB;
 
+use protocol::fixed_codec::FixedCodec;
 use protocol::traits::ServiceState;
 use protocol::types::{Address, Hash, MerkleRoot};
+use protocol::ProtocolResult;
 
-use crate::binding::state::{GeneralServiceState, MPTTrie};
+use crate::binding::state::{GeneralServiceState, MPTTrie, RocksTrieDB};
 
 #[test]
 fn test_state_insert() {
@@ -56,6 +60,115 @@ fn test_state_account() {
     assert_eq!(val, value);
 }
 
+#[test]
+fn test_state_proof() {
+    let memdb = Arc::new(MemoryDB::new(false));
+    let mut state = new_state(Arc::clone(&memdb), None);
+
+    let key = Hash::digest(Bytes::from("key".to_owned()));
+    let value = Hash::digest(Bytes::from("value".to_owned()));
+    state.insert(key.clone(), value.clone()).unwrap();
+    state.stash().unwrap();
+    let root = state.commit().unwrap();
+
+    let encoded_key = key.encode_fixed().unwrap();
+    let proof = state.get_proof(&key).unwrap();
+    let val = MPTTrie::<MemoryDB>::verify_proof(&root, &encoded_key, proof.clone()).unwrap();
+    assert_eq!(val, Some(value.encode_fixed().unwrap()));
+
+    let other_key = Hash::digest(Bytes::from("other_key".to_owned()));
+    let other_proof = state.get_proof(&other_key).unwrap();
+    let val =
+        MPTTrie::<MemoryDB>::verify_proof(&root, &other_key.encode_fixed().unwrap(), other_proof)
+            .unwrap();
+    assert_eq!(val, None);
+
+    let wrong_root = Hash::digest(Bytes::from("root".to_owned()));
+    assert!(MPTTrie::<MemoryDB>::verify_proof(&wrong_root, &encoded_key, proof).is_err());
+}
+
+#[test]
+fn test_trie_db_prune() {
+    let mut path = env::temp_dir();
+    path.push("muta-framework-trie-prune");
+    let _ = fs::remove_dir_all(&path);
+    let trie_db = Arc::new(
+        RocksTrieDB::new(&path, false, 64)
+            .unwrap()
+            .with_prune()
+            .unwrap(),
+    );
+
+    // States not kept are deleted on the first run
+    let old_root = commit_root(&trie_db, 0);
+    let root = commit_root(&trie_db, 1);
+    assert!(trie_db.init_prune(&[root.clone()]).unwrap() > 0);
+    assert_eq!(trie_db.init_prune(&[root.clone()]).unwrap(), 0);
+    assert!(get_service_value(&trie_db, &old_root).is_err());
+    assert_eq!(
+        get_service_value(&trie_db, &root).unwrap(),
+        service_value(1)
+    );
+
+    // Nodes of a dropped state are deleted at once
+    let next_root = commit_root(&trie_db, 2);
+    assert_eq!(
+        trie_db.prune(&[root.clone(), next_root.clone()]).unwrap(),
+        0
+    );
+    assert!(trie_db.prune(&[next_root.clone()]).unwrap() > 0);
+    assert!(get_service_value(&trie_db, &root).is_err());
+
+    // New states not kept are deleted two rounds later
+    let new_root = commit_root(&trie_db, 3);
+    assert_eq!(trie_db.prune(&[next_root.clone()]).unwrap(), 0);
+    assert_eq!(trie_db.prune(&[next_root.clone()]).unwrap(), 0);
+    assert!(trie_db.prune(&[next_root.clone()]).unwrap() > 0);
+    assert!(get_service_value(&trie_db, &new_root).is_err());
+
+    // A state written again is kept
+    assert_eq!(commit_root(&trie_db, 2), next_root);
+    assert_eq!(trie_db.prune(&[next_root.clone()]).unwrap(), 0);
+    assert_eq!(
+        get_service_value(&trie_db, &next_root).unwrap(),
+        service_value(2)
+    );
+}
+
+fn get_service_value(trie_db: &Arc<RocksTrieDB>, root: &MerkleRoot) -> ProtocolResult<Bytes> {
+    let service_root = MPTTrie::from(root.clone(), Arc::clone(trie_db))?
+        .get(&Bytes::from("test"))?
+        .expect("service root");
+    let service = MPTTrie::from(MerkleRoot::decode_fixed(service_root)?, Arc::clone(trie_db))?;
+
+    let key = Hash::digest(Bytes::from("key-0")).encode_fixed()?;
+    Ok(service.get(&key)?.expect("service value"))
+}
+
+fn service_value(round: u64) -> Bytes {
+    Hash::digest(Bytes::from(format!("value-0-{}", round)))
+        .encode_fixed()
+        .unwrap()
+}
+
+// A root trie with one service, values of the service differ by the round.
+fn commit_root(trie_db: &Arc<RocksTrieDB>, round: u64) -> MerkleRoot {
+    let mut service = MPTTrie::new(Arc::clone(trie_db));
+    for i in 0..100 {
+        let key = Hash::digest(Bytes::from(format!("key-{}", i)));
+        let value = Hash::digest(Bytes::from(format!("value-{}-{}", i, round)));
+        service
+            .insert(key.encode_fixed().unwrap(), value.encode_fixed().unwrap())
+            .unwrap();
+    }
+    let service_root = service.commit().unwrap();
+
+    let mut root = MPTTrie::new(Arc::clone(trie_db));
+    root.insert(Bytes::from("test"), service_root.encode_fixed().unwrap())
+        .unwrap();
+    root.commit().unwrap()
+}
+
 pub fn new_state(memdb: Arc<MemoryDB>, root: Option<MerkleRoot>) -> GeneralServiceState<MemoryDB> {
     let trie = match root {
         Some(root) => MPTTrie::from(root, memdb).unwrap(),
@@ -13,19 +13,39 @@ use std::sync::Arc;
 use cita_trie::DB as TrieDB;
 use derive_more::{Display, From};
 
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
+use protocol::fixed_codec::FixedCodec;
 use protocol::traits::{
-    Dispatcher, Executor, ExecutorParams, ExecutorResp, NoopDispatcher, ServiceMapping,
-    ServiceResponse, ServiceState, Storage,
+    AdmissionControl, Dispatcher, Executor, ExecutorParams, ExecutorResp, NoopDispatcher,
+    ServiceMapping, ServiceResponse, ServiceState, StateProof, Storage,
 };
 use protocol::types::{
-    Address, Bloom, BloomInput, Hash, MerkleRoot, Receipt, ReceiptResponse, ServiceContext,
-    ServiceContextParams, ServiceParam, SignedTransaction, TransactionRequest,
+    Address, Bloom, BloomInput, Hash, MerkleRoot, Metadata, RawTransaction, Receipt,
+    ReceiptResponse, ServiceContext, ServiceContextParams, ServiceParam, SignedTransaction,
+    TransactionRequest, METADATA_KEY,
 };
 use protocol::{ProtocolError, ProtocolErrorKind, ProtocolResult};
 
+use crate::admission;
 use crate::binding::sdk::{DefalutServiceSDK, DefaultChainQuerier};
-use crate::binding::state::{GeneralServiceState, MPTTrie};
+use crate::binding::state::{get_address_key, GeneralServiceState, MPTTrie};
+
+// Fees are moved by the `transfer` method of the asset service, as configured
+// in the metadata service.
+const FEE_SERVICE: &str = "asset";
+const FEE_METHOD: &str = "transfer";
+const METADATA_SERVICE: &str = "metadata";
+// Error code of the receipt if the fee can't be charged for other reasons
+// than the asset service returns.
+const FEE_ERROR_CODE: u64 = 1000;
+// Error code of the receipt if the transaction is rejected by admission
+// controls.
+const ADMISSION_ERROR_CODE: u64 = 1001;
+
+struct FeeConfig {
+    asset_id:  Hash,
+    recipient: Address,
+}
 
 enum HookType {
     Before,
@@ -299,6 +319,157 @@ impl<S: 'static + Storage, DB: 'static + TrieDB, Mapping: 'static + ServiceMappi
         }
     }
 
+    fn metadata(&self) -> ProtocolResult<Option<Metadata>> {
+        match self.states.get(METADATA_SERVICE) {
+            Some(state) => state.borrow().get(&METADATA_KEY.to_owned()),
+            None => Ok(None),
+        }
+    }
+
+    fn fee_config(&self, params: &ExecutorParams) -> ProtocolResult<Option<FeeConfig>> {
+        let fee_config = self.metadata()?.and_then(|metadata| {
+            Some(FeeConfig {
+                asset_id:  metadata.fee_asset_id?,
+                recipient: metadata
+                    .fee_treasury
+                    .unwrap_or_else(|| params.proposer.clone()),
+            })
+        });
+        Ok(fee_config)
+    }
+
+    // Tx hooks are not run for fee transfers, and the changes are reverted if
+    // the transfer fails.
+    fn transfer_fee(
+        &mut self,
+        params: &ExecutorParams,
+        fee_config: &FeeConfig,
+        from: &Address,
+        to: &Address,
+        value: u64,
+    ) -> ProtocolResult<ServiceResponse<String>> {
+        let payload = serde_json::json!({
+            "asset_id": fee_config.asset_id,
+            "to": to,
+            "value": value,
+        });
+        let request = TransactionRequest {
+            service_name: FEE_SERVICE.to_owned(),
+            method:       FEE_METHOD.to_owned(),
+            payload:      payload.to_string(),
+        };
+        let context = self.get_context(None, None, from, 0, std::u64::MAX, params, &request)?;
+
+        let resp = panic::catch_unwind(AssertUnwindSafe(|| self.call(context, ExecType::Write)))
+            .unwrap_or_else(|e| {
+                ServiceResponse::from_error(FEE_ERROR_CODE, format!("charge fee failed: {:?}", e))
+            });
+
+        if resp.is_error() {
+            self.revert_cache()?;
+        } else {
+            self.stash()?;
+        }
+        Ok(resp)
+    }
+
+    // The max fee `cycles_limit * cycles_price` is charged before the
+    // transaction is executed, and the unused part is refunded after. The
+    // transaction is not executed if it is rejected by admission controls, or
+    // the caller can't afford the max fee.
+    //
+    // Simulation goes through the same checks and charges, which are dropped
+    // along with the uncommitted changes.
+    fn exec_tx(
+        &mut self,
+        params: &ExecutorParams,
+        admission: &[Arc<dyn AdmissionControl>],
+        fee_config: Option<&FeeConfig>,
+        caller: &Address,
+        tx_hash: &Hash,
+        raw: &RawTransaction,
+    ) -> ProtocolResult<Receipt> {
+        let context = self.get_context(
+            Some(tx_hash.clone()),
+            Some(raw.nonce.clone()),
+            caller,
+            raw.cycles_price,
+            raw.cycles_limit,
+            params,
+            &raw.request,
+        )?;
+
+        if let Err(e) = admission
+            .iter()
+            .try_for_each(|control| control.next(&context))
+        {
+            let resp = ServiceResponse::from_error(ADMISSION_ERROR_CODE, e.to_string());
+            return Ok(rejected_receipt(&context, tx_hash, resp));
+        }
+
+        let charged = match fee_config {
+            Some(fee_config) if &fee_config.recipient != caller => {
+                let max_fee = raw.cycles_limit.saturating_mul(raw.cycles_price);
+                let resp =
+                    self.transfer_fee(params, fee_config, caller, &fee_config.recipient, max_fee)?;
+
+                if resp.is_error() {
+                    return Ok(rejected_receipt(&context, tx_hash, resp));
+                }
+                Some((fee_config, max_fee))
+            }
+            _ => None,
+        };
+
+        let exec_resp = self.catch_call(context.clone(), ExecType::Write)?;
+
+        let charged_fee = match charged {
+            Some((fee_config, max_fee)) => {
+                let fee = context.get_cycles_used().saturating_mul(raw.cycles_price);
+                let refund = max_fee.saturating_sub(fee);
+
+                if refund == 0 {
+                    fee
+                } else {
+                    let resp = self.transfer_fee(
+                        params,
+                        fee_config,
+                        &fee_config.recipient,
+                        caller,
+                        refund,
+                    )?;
+
+                    // The recipient may have spent the fee within the transaction.
+                    if resp.is_error() {
+                        log::warn!(
+                            "[executor]: refund fee of {:?} failed: {}",
+                            tx_hash,
+                            resp.error_message
+                        );
+                        max_fee
+                    } else {
+                        fee
+                    }
+                }
+            }
+            None => 0,
+        };
+
+        Ok(Receipt {
+            state_root:  MerkleRoot::from_empty(),
+            height:      context.get_current_height(),
+            tx_hash:     tx_hash.clone(),
+            cycles_used: context.get_cycles_used(),
+            fee:         charged_fee,
+            events:      context.get_events(),
+            response:    ReceiptResponse {
+                service_name: context.get_service_name().to_owned(),
+                method:       context.get_service_method().to_owned(),
+                response:     exec_resp,
+            },
+        })
+    }
+
     fn logs_bloom(&self, receipts: &[Receipt]) -> Bloom {
         let mut bloom = Bloom::default();
         for receipt in receipts {
@@ -326,34 +497,20 @@ impl<S: 'static + Storage, DB: 'static + TrieDB, Mapping: 'static + ServiceMappi
     ) -> ProtocolResult<ExecutorResp> {
         self.hook(HookType::Before, params)?;
 
+        let admission = self.admission_controls()?;
+        let fee_config = self.fee_config(params)?;
         let mut receipts = txs
             .iter()
             .map(|stx| {
-                let caller = Address::from_pubkey_bytes(stx.pubkey.clone())?;
-                let context = self.get_context(
-                    Some(stx.tx_hash.clone()),
-                    Some(stx.raw.nonce.clone()),
-                    &caller,
-                    stx.raw.cycles_price,
-                    stx.raw.cycles_limit,
+                let caller = stx.sender()?;
+                self.exec_tx(
                     params,
-                    &stx.raw.request,
-                )?;
-
-                let exec_resp = self.catch_call(context.clone(), ExecType::Write)?;
-
-                Ok(Receipt {
-                    state_root:  MerkleRoot::from_empty(),
-                    height:      context.get_current_height(),
-                    tx_hash:     stx.tx_hash.clone(),
-                    cycles_used: context.get_cycles_used(),
-                    events:      context.get_events(),
-                    response:    ReceiptResponse {
-                        service_name: context.get_service_name().to_owned(),
-                        method:       context.get_service_method().to_owned(),
-                        response:     exec_resp,
-                    },
-                })
+                    &admission,
+                    fee_config.as_ref(),
+                    &caller,
+                    &stx.tx_hash,
+                    &stx.raw,
+                )
             })
             .collect::<Result<Vec<Receipt>, ProtocolError>>()?;
 
@@ -395,6 +552,70 @@ impl<S: 'static + Storage, DB: 'static + TrieDB, Mapping: 'static + ServiceMappi
         panic::catch_unwind(AssertUnwindSafe(|| self.call(context, ExecType::Read)))
             .map_err(|e| ProtocolError::from(ExecutorError::QueryService(format!("{:?}", e))))
     }
+
+    fn simulate(
+        &mut self,
+        params: &ExecutorParams,
+        caller: &Address,
+        tx_hash: Hash,
+        raw: &RawTransaction,
+    ) -> ProtocolResult<Receipt> {
+        let admission = self.admission_controls()?;
+        let fee_config = self.fee_config(params)?;
+
+        let mut receipt = self.exec_tx(
+            params,
+            &admission,
+            fee_config.as_ref(),
+            caller,
+            &tx_hash,
+            raw,
+        )?;
+        receipt.state_root = params.state_root.clone();
+
+        Ok(receipt)
+    }
+
+    fn get_state_proof(
+        &self,
+        params: &ExecutorParams,
+        service_name: &str,
+        address: Option<&Address>,
+        key: Bytes,
+    ) -> ProtocolResult<StateProof> {
+        let not_found = || ExecutorError::NotFoundService {
+            service: service_name.to_owned(),
+        };
+
+        let state = self.states.get(service_name).ok_or_else(not_found)?;
+        let root_state = self.root_state.borrow();
+        let service_root: MerkleRoot = root_state
+            .get(&service_name.to_owned())?
+            .ok_or_else(not_found)?;
+
+        let key = match address {
+            Some(address) => get_address_key(address, &key)?.encode_fixed()?,
+            None => key,
+        };
+
+        Ok(StateProof {
+            state_root: params.state_root.clone(),
+            service_name: service_name.to_owned(),
+            service_root,
+            root_proof: root_state.get_proof(&service_name.to_owned())?,
+            value: state.borrow().get(&key)?,
+            proof: state.borrow().get_proof(&key)?,
+            key,
+        })
+    }
+
+    fn admission_controls(&self) -> ProtocolResult<Vec<Arc<dyn AdmissionControl>>> {
+        let mut controls = self.service_mapping.admission_controls();
+        if let Some(metadata) = self.metadata()? {
+            controls.extend(admission::admission_controls(&metadata.admission));
+        }
+        Ok(controls)
+    }
 }
 
 impl<S: 'static + Storage, DB: 'static + TrieDB, Mapping: 'static + ServiceMapping> Dispatcher
@@ -409,6 +630,27 @@ impl<S: 'static + Storage, DB: 'static + TrieDB, Mapping: 'static + ServiceMappi
     }
 }
 
+// Receipt of a transaction which is not executed, nothing is charged.
+fn rejected_receipt(
+    context: &ServiceContext,
+    tx_hash: &Hash,
+    resp: ServiceResponse<String>,
+) -> Receipt {
+    Receipt {
+        state_root:  MerkleRoot::from_empty(),
+        height:      context.get_current_height(),
+        tx_hash:     tx_hash.clone(),
+        cycles_used: 0,
+        fee:         0,
+        events:      vec![],
+        response:    ReceiptResponse {
+            service_name: context.get_service_name().to_owned(),
+            method:       context.get_service_method().to_owned(),
+            response:     resp,
+        },
+    }
+}
+
 #[derive(Debug, Display, From)]
 pub enum ExecutorError {
     #[display(fmt = "service {:?} was not found", service)]
@@ -13,13 +13,18 @@ use test::Bencher;
 use asset::types::{Asset, GetBalanceResponse};
 use asset::AssetService;
 use metadata::MetadataService;
-use protocol::traits::{Executor, ExecutorParams, Service, ServiceMapping, ServiceSDK, Storage};
+use protocol::fixed_codec::FixedCodec;
+use protocol::traits::{
+    AdmissionControl, Executor, ExecutorParams, Service, ServiceMapping, ServiceSDK, Storage,
+};
 use protocol::types::{
-    Address, Block, Genesis, Hash, Proof, RawTransaction, Receipt, SignedTransaction,
-    TransactionRequest,
+    Address, Block, BloomInput, Genesis, Hash, Metadata, Proof, RawTransaction, Receipt,
+    ServiceParam, SignedTransaction, TransactionRequest,
 };
 use protocol::ProtocolResult;
 
+use crate::admission::MinCyclesPrice;
+use crate::binding::state::verify_state_proof;
 use crate::executor::ServiceExecutor;
 use test_service::TestService;
 
@@ -50,6 +55,7 @@ fn test_create_genesis() {
         height:       1,
         timestamp:    0,
         cycles_limit: std::u64::MAX,
+        proposer:     Address::default(),
     };
     let caller = Address::from_hex("0xf8389d774afdad8755ef8e629e5a154fddc6325a").unwrap();
     let request = TransactionRequest {
@@ -93,6 +99,7 @@ fn test_exec() {
         height:       1,
         timestamp:    0,
         cycles_limit: std::u64::MAX,
+        proposer:     Address::default(),
     };
 
     let stx = mock_signed_tx();
@@ -105,6 +112,327 @@ fn test_exec() {
     assert_eq!(asset.name, "MutaToken2");
     assert_eq!(asset.symbol, "MT2");
     assert_eq!(asset.supply, 320_000_011);
+
+    let event = &receipt.events[0];
+    let event_hash = Hash::digest(Bytes::from(event.service.clone() + &event.data));
+    assert!(executor_resp
+        .logs_bloom
+        .contains_input(BloomInput::Raw(event_hash.as_bytes().as_ref())));
+}
+
+#[test]
+fn test_exec_with_fee() {
+    let toml_str = include_str!("./genesis_services.toml");
+    let mut genesis: Genesis = toml::from_str(toml_str).unwrap();
+
+    let asset_id =
+        Hash::from_hex("0xf56924db538e77bb5951eb5ff0d02b88983c49c45eea30e8ae3e7234b311436c")
+            .unwrap();
+    let treasury = Address::from_hex("0x0000000000000000000000000000000000000001").unwrap();
+    let metadata = Metadata {
+        fee_asset_id: Some(asset_id.clone()),
+        fee_treasury: Some(treasury.clone()),
+        ..Default::default()
+    };
+    genesis.services.push(ServiceParam {
+        name:    "metadata".to_owned(),
+        payload: serde_json::to_string(&metadata).unwrap(),
+    });
+
+    let db = Arc::new(MemoryDB::new(false));
+
+    let root = ServiceExecutor::create_genesis(
+        genesis.services,
+        Arc::clone(&db),
+        Arc::new(MockStorage {}),
+        Arc::new(MockServiceMapping {}),
+    )
+    .unwrap();
+
+    let mut executor = ServiceExecutor::with_root(
+        root.clone(),
+        Arc::clone(&db),
+        Arc::new(MockStorage {}),
+        Arc::new(MockServiceMapping {}),
+    )
+    .unwrap();
+
+    let params = ExecutorParams {
+        state_root:   root,
+        height:       1,
+        timestamp:    0,
+        cycles_limit: std::u64::MAX,
+        proposer:     Address::default(),
+    };
+
+    let mut stx = mock_signed_tx();
+    stx.raw.cycles_limit = 1_000_000;
+    stx.raw.cycles_price = 2;
+    // Can't afford the max fee of 1_000_000 * 1_000.
+    let mut poor_stx = stx.clone();
+    poor_stx.raw.cycles_price = 1_000;
+
+    let executor_resp = executor
+        .exec(&params, &[stx.clone(), poor_stx.clone()])
+        .unwrap();
+    let receipt = &executor_resp.receipts[0];
+    assert_eq!(receipt.response.response.code, 0);
+    assert_eq!(receipt.fee, receipt.cycles_used * 2);
+
+    let rejected = &executor_resp.receipts[1];
+    assert!(rejected.response.response.is_error());
+    assert_eq!(rejected.cycles_used, 0);
+    assert_eq!(rejected.fee, 0);
+
+    let balance_of = |user: &Address| {
+        let request = TransactionRequest {
+            service_name: "asset".to_owned(),
+            method:       "get_balance".to_owned(),
+            payload:      format!(
+                r#"{{"asset_id": "{}", "user": "{}"}}"#,
+                asset_id.as_hex(),
+                user.as_hex()
+            ),
+        };
+        let res = executor.read(&params, user, 1, &request).unwrap();
+        let resp: GetBalanceResponse = serde_json::from_str(&res.succeed_data).unwrap();
+        resp.balance
+    };
+
+    let caller = Address::from_pubkey_bytes(mock_signed_tx().pubkey).unwrap();
+    assert_eq!(balance_of(&caller), 320_000_011 - receipt.fee);
+    assert_eq!(balance_of(&treasury), receipt.fee);
+
+    // Simulation applies the same fee checks
+    let mut executor = ServiceExecutor::with_root(
+        params.state_root.clone(),
+        Arc::clone(&db),
+        Arc::new(MockStorage {}),
+        Arc::new(MockServiceMapping {}),
+    )
+    .unwrap();
+    let simulated = executor
+        .simulate(&params, &caller, stx.tx_hash.clone(), &stx.raw)
+        .unwrap();
+    assert_eq!(simulated.response.response.code, 0);
+    assert_eq!(simulated.fee, simulated.cycles_used * 2);
+    let simulated = executor
+        .simulate(&params, &caller, poor_stx.tx_hash.clone(), &poor_stx.raw)
+        .unwrap();
+    assert!(simulated.response.response.is_error());
+    assert_eq!(simulated.fee, 0);
+}
+
+#[test]
+fn test_exec_admission() {
+    let toml_str = include_str!("./genesis_services.toml");
+    let genesis: Genesis = toml::from_str(toml_str).unwrap();
+
+    let db = Arc::new(MemoryDB::new(false));
+    let mapping = Arc::new(MockAdmissionMapping {});
+
+    let root = ServiceExecutor::create_genesis(
+        genesis.services,
+        Arc::clone(&db),
+        Arc::new(MockStorage {}),
+        Arc::clone(&mapping),
+    )
+    .unwrap();
+
+    let mut executor = ServiceExecutor::with_root(
+        root.clone(),
+        Arc::clone(&db),
+        Arc::new(MockStorage {}),
+        mapping,
+    )
+    .unwrap();
+
+    let params = ExecutorParams {
+        state_root:   root,
+        height:       1,
+        timestamp:    0,
+        cycles_limit: std::u64::MAX,
+        proposer:     Address::default(),
+    };
+
+    let mut stx = mock_signed_tx();
+    stx.raw.cycles_price = 2;
+    let cheap_stx = mock_signed_tx();
+
+    // Simulation is rejected the same way
+    let caller = Address::from_pubkey_bytes(cheap_stx.pubkey.clone()).unwrap();
+    let simulated = executor
+        .simulate(&params, &caller, cheap_stx.tx_hash.clone(), &cheap_stx.raw)
+        .unwrap();
+    assert_eq!(simulated.response.response.code, 1001);
+
+    let executor_resp = executor.exec(&params, &[cheap_stx, stx]).unwrap();
+
+    let rejected = &executor_resp.receipts[0];
+    assert_eq!(rejected.response.response.code, 1001);
+    assert_eq!(rejected.cycles_used, 0);
+    assert!(rejected.events.is_empty());
+
+    assert_eq!(executor_resp.receipts[1].response.response.code, 0);
+}
+
+#[test]
+fn test_exec_chain_admission() {
+    let toml_str = include_str!("./genesis_services.toml");
+    let mut genesis: Genesis = toml::from_str(toml_str).unwrap();
+
+    // Only another sender is allowed by the metadata of the chain
+    let mut metadata = Metadata::default();
+    metadata.admission.allow_senders = Some(vec![Address::from_hex(
+        "0x0000000000000000000000000000000000000001",
+    )
+    .unwrap()]);
+    genesis.services.push(ServiceParam {
+        name:    "metadata".to_owned(),
+        payload: serde_json::to_string(&metadata).unwrap(),
+    });
+
+    let db = Arc::new(MemoryDB::new(false));
+
+    let root = ServiceExecutor::create_genesis(
+        genesis.services,
+        Arc::clone(&db),
+        Arc::new(MockStorage {}),
+        Arc::new(MockServiceMapping {}),
+    )
+    .unwrap();
+
+    let mut executor = ServiceExecutor::with_root(
+        root.clone(),
+        Arc::clone(&db),
+        Arc::new(MockStorage {}),
+        Arc::new(MockServiceMapping {}),
+    )
+    .unwrap();
+    assert_eq!(executor.admission_controls().unwrap().len(), 1);
+
+    let params = ExecutorParams {
+        state_root:   root,
+        height:       1,
+        timestamp:    0,
+        cycles_limit: std::u64::MAX,
+        proposer:     Address::default(),
+    };
+
+    let executor_resp = executor.exec(&params, &[mock_signed_tx()]).unwrap();
+    let rejected = &executor_resp.receipts[0];
+    assert_eq!(rejected.response.response.code, 1001);
+    assert_eq!(rejected.cycles_used, 0);
+}
+
+#[test]
+fn test_simulate() {
+    let toml_str = include_str!("./genesis_services.toml");
+    let genesis: Genesis = toml::from_str(toml_str).unwrap();
+
+    let db = Arc::new(MemoryDB::new(false));
+
+    let root = ServiceExecutor::create_genesis(
+        genesis.services,
+        Arc::clone(&db),
+        Arc::new(MockStorage {}),
+        Arc::new(MockServiceMapping {}),
+    )
+    .unwrap();
+
+    let mut executor = ServiceExecutor::with_root(
+        root.clone(),
+        Arc::clone(&db),
+        Arc::new(MockStorage {}),
+        Arc::new(MockServiceMapping {}),
+    )
+    .unwrap();
+
+    let params = ExecutorParams {
+        state_root:   root.clone(),
+        height:       1,
+        timestamp:    0,
+        cycles_limit: std::u64::MAX,
+        proposer:     Address::default(),
+    };
+
+    let stx = mock_signed_tx();
+    let caller = Address::from_pubkey_bytes(stx.pubkey.clone()).unwrap();
+    let receipt = executor
+        .simulate(&params, &caller, stx.tx_hash.clone(), &stx.raw)
+        .unwrap();
+
+    assert_eq!(receipt.response.response.code, 0);
+    assert_eq!(receipt.events.len(), 1);
+    assert!(receipt.cycles_used > 0);
+    let asset: Asset = serde_json::from_str(&receipt.response.response.succeed_data).unwrap();
+
+    // The created asset must not be visible from the original root.
+    let executor = ServiceExecutor::with_root(
+        root,
+        Arc::clone(&db),
+        Arc::new(MockStorage {}),
+        Arc::new(MockServiceMapping {}),
+    )
+    .unwrap();
+    let request = TransactionRequest {
+        service_name: "asset".to_owned(),
+        method:       "get_asset".to_owned(),
+        payload:      format!(r#"{{"id": "{}"}}"#, asset.id.as_hex()),
+    };
+    let res = executor.read(&params, &caller, 1, &request).unwrap();
+    assert_eq!(res.code, 101);
+}
+
+#[test]
+fn test_state_proof() {
+    let toml_str = include_str!("./genesis_services.toml");
+    let genesis: Genesis = toml::from_str(toml_str).unwrap();
+
+    let db = Arc::new(MemoryDB::new(false));
+
+    let root = ServiceExecutor::create_genesis(
+        genesis.services,
+        Arc::clone(&db),
+        Arc::new(MockStorage {}),
+        Arc::new(MockServiceMapping {}),
+    )
+    .unwrap();
+
+    let executor = ServiceExecutor::with_root(
+        root.clone(),
+        Arc::clone(&db),
+        Arc::new(MockStorage {}),
+        Arc::new(MockServiceMapping {}),
+    )
+    .unwrap();
+    let params = ExecutorParams {
+        state_root:   root,
+        height:       1,
+        timestamp:    0,
+        cycles_limit: std::u64::MAX,
+        proposer:     Address::default(),
+    };
+
+    let issuer = Address::from_hex("0xf8389d774afdad8755ef8e629e5a154fddc6325a").unwrap();
+    let asset_id =
+        Hash::from_hex("0xf56924db538e77bb5951eb5ff0d02b88983c49c45eea30e8ae3e7234b311436c")
+            .unwrap();
+    let proof = executor
+        .get_state_proof(
+            &params,
+            "asset",
+            Some(&issuer),
+            asset_id.encode_fixed().unwrap(),
+        )
+        .unwrap();
+
+    assert!(proof.value.is_some());
+    assert_eq!(verify_state_proof(&proof).unwrap(), proof.value);
+
+    let mut wrong_proof = proof.clone();
+    wrong_proof.service_name = "metadata".to_owned();
+    assert!(verify_state_proof(&wrong_proof).is_err());
 }
 
 #[test]
@@ -135,6 +463,7 @@ fn test_tx_hook() {
         height:       1,
         timestamp:    0,
         cycles_limit: std::u64::MAX,
+        proposer:     Address::default(),
     };
 
     // no tx hook
@@ -216,6 +545,7 @@ fn bench_execute(b: &mut Bencher) {
             height:       1,
             timestamp:    0,
             cycles_limit: std::u64::MAX,
+            proposer:     Address::default(),
         };
         let txs = txs.clone();
         executor.exec(&params, &txs).unwrap();
@@ -240,6 +570,7 @@ fn mock_signed_tx() -> SignedTransaction {
     SignedTransaction {
         raw,
         tx_hash: Hash::from_empty(),
+        scheme: Default::default(),
         pubkey: Bytes::from(
             hex::decode("031288a6788678c25952eba8693b2f278f66e2187004b64ac09416d07f83f96d5b")
                 .unwrap(),
@@ -271,6 +602,27 @@ impl ServiceMapping for MockServiceMapping {
     }
 }
 
+// Registers an admission control, like a service of the chain does.
+struct MockAdmissionMapping;
+
+impl ServiceMapping for MockAdmissionMapping {
+    fn get_service<SDK: 'static + ServiceSDK>(
+        &self,
+        name: &str,
+        sdk: SDK,
+    ) -> ProtocolResult<Box<dyn Service>> {
+        MockServiceMapping.get_service(name, sdk)
+    }
+
+    fn list_service_name(&self) -> Vec<String> {
+        MockServiceMapping.list_service_name()
+    }
+
+    fn admission_controls(&self) -> Vec<Arc<dyn AdmissionControl>> {
+        vec![Arc::new(MinCyclesPrice::new(2))]
+    }
+}
+
 struct MockStorage;
 
 #[async_trait]
@@ -295,10 +647,26 @@ impl Storage for MockStorage {
         unimplemented!()
     }
 
+    async fn try_get_transaction_by_hash(
+        &self,
+        _: Hash,
+    ) -> ProtocolResult<Option<SignedTransaction>> {
+        unimplemented!()
+    }
+
     async fn get_transactions(&self, _: Vec<Hash>) -> ProtocolResult<Vec<SignedTransaction>> {
         unimplemented!()
     }
 
+    async fn get_transactions_by_address(
+        &self,
+        _: Address,
+        _: u64,
+        _: u64,
+    ) -> ProtocolResult<Vec<SignedTransaction>> {
+        unimplemented!()
+    }
+
     async fn get_latest_block(&self) -> ProtocolResult<Block> {
         unimplemented!()
     }
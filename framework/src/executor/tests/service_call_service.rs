@@ -12,7 +12,7 @@ use protocol::traits::{
     Executor, ExecutorParams, Service, ServiceMapping, ServiceResponse, ServiceSDK,
 };
 use protocol::types::{
-    Genesis, Hash, RawTransaction, ServiceContext, SignedTransaction, TransactionRequest,
+    Address, Genesis, Hash, RawTransaction, ServiceContext, SignedTransaction, TransactionRequest,
 };
 use protocol::ProtocolResult;
 
@@ -48,6 +48,7 @@ fn test_service_call_service() {
         height:       1,
         timestamp:    0,
         cycles_limit: std::u64::MAX,
+        proposer:     Address::default(),
     };
 
     let raw = RawTransaction {
@@ -66,6 +67,7 @@ fn test_service_call_service() {
     let stx = SignedTransaction {
         raw,
         tx_hash: Hash::from_empty(),
+        scheme: Default::default(),
         pubkey: Bytes::from(
             hex::decode("031288a6788678c25952eba8693b2f278f66e2187004b64ac09416d07f83f96d5b")
                 .unwrap(),
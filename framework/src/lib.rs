@@ -1,5 +1,6 @@
 #![feature(vec_remove_item)]
 #![feature(test)]
 
+pub mod admission;
 pub mod binding;
 pub mod executor;
@@ -35,6 +35,9 @@ pub struct Receipt {
 
     #[prost(message, tag = "6")]
     pub response: Option<ReceiptResponse>,
+
+    #[prost(uint64, tag = "7")]
+    pub fee: u64,
 }
 
 #[derive(Clone, Message)]
@@ -117,6 +120,7 @@ impl From<receipt::Receipt> for Receipt {
             cycles_used: receipt.cycles_used,
             events,
             response,
+            fee: receipt.fee,
         }
     }
 }
@@ -139,6 +143,7 @@ impl TryFrom<Receipt> for receipt::Receipt {
             height: receipt.height,
             tx_hash: protocol_primitive::Hash::try_from(tx_hash)?,
             cycles_used: receipt.cycles_used,
+            fee: receipt.fee,
             events,
             response: receipt::ReceiptResponse::try_from(response)?,
         };
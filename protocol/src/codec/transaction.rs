@@ -56,6 +56,10 @@ pub struct SignedTransaction {
 
     #[prost(bytes, tag = "4")]
     pub signature: Vec<u8>,
+
+    // Id of the signature scheme, 0 is secp256k1.
+    #[prost(uint32, tag = "5")]
+    pub scheme: u32,
 }
 
 // #################
@@ -141,6 +145,7 @@ impl From<transaction::SignedTransaction> for SignedTransaction {
             tx_hash:   Some(tx_hash),
             pubkey:    stx.pubkey.to_vec(),
             signature: stx.signature.to_vec(),
+            scheme:    u32::from(stx.scheme.id()),
         }
     }
 }
@@ -151,10 +156,13 @@ impl TryFrom<SignedTransaction> for transaction::SignedTransaction {
     fn try_from(stx: SignedTransaction) -> Result<transaction::SignedTransaction, Self::Error> {
         let raw = field!(stx.raw, "SignedTransaction", "raw")?;
         let tx_hash = field!(stx.tx_hash, "SignedTransaction", "tx_hash")?;
+        let scheme =
+            u8::try_from(stx.scheme).map_err(|_| CodecError::InvalidSignatureScheme(stx.scheme))?;
 
         let stx = transaction::SignedTransaction {
             raw:       transaction::RawTransaction::try_from(raw)?,
             tx_hash:   protocol_primitive::Hash::try_from(tx_hash)?,
+            scheme:    transaction::SignatureScheme::from_id(scheme)?,
             pubkey:    Bytes::from(stx.pubkey),
             signature: Bytes::from(stx.signature),
         };
@@ -4,7 +4,7 @@ use byteorder::{ByteOrder, LittleEndian};
 use bytes::{Bytes, BytesMut};
 
 use crate::fixed_codec::{FixedCodec, FixedCodecError};
-use crate::types::{Address, Hash, Hex, Metadata, ValidatorExtend};
+use crate::types::{Address, Admission, Hash, Hex, Metadata, ValidatorExtend};
 use crate::{impl_default_fixed_codec_for, ProtocolResult};
 
 // Impl FixedCodec trait for types
@@ -137,7 +137,19 @@ impl rlp::Decodable for Address {
 
 impl rlp::Encodable for Metadata {
     fn rlp_append(&self, s: &mut rlp::RlpStream) {
-        s.begin_list(13)
+        // Metadata without fee or admission fields keeps the encoding from
+        // before them.
+        let with_admission = self.admission != Admission::default();
+        let with_fee = with_admission || self.fee_asset_id.is_some() || self.fee_treasury.is_some();
+        let len = if with_admission {
+            16
+        } else if with_fee {
+            15
+        } else {
+            13
+        };
+
+        s.begin_list(len)
             .append(&self.chain_id)
             .append(&self.common_ref)
             .append(&self.timeout_gap)
@@ -151,6 +163,13 @@ impl rlp::Encodable for Metadata {
             .append(&self.brake_ratio)
             .append(&self.tx_num_limit)
             .append(&self.max_tx_size);
+        if with_fee {
+            append_option(s, &self.fee_asset_id);
+            append_option(s, &self.fee_treasury);
+        }
+        if with_admission {
+            s.append(&self.admission);
+        }
     }
 }
 
@@ -170,6 +189,18 @@ impl rlp::Decodable for Metadata {
         let tx_num_limit: u64 = r.at(11)?.as_val()?;
         let max_tx_size: u64 = r.at(12)?.as_val()?;
 
+        // Metadata encoded before fees were introduced has no fee fields.
+        let (fee_asset_id, fee_treasury) = if r.item_count()? > 13 {
+            (decode_option(&r.at(13)?)?, decode_option(&r.at(14)?)?)
+        } else {
+            (None, None)
+        };
+        let admission = if r.item_count()? > 15 {
+            r.at(15)?.as_val()?
+        } else {
+            Admission::default()
+        };
+
         Ok(Self {
             chain_id,
             common_ref,
@@ -184,10 +215,65 @@ impl rlp::Decodable for Metadata {
             brake_ratio,
             tx_num_limit,
             max_tx_size,
+            fee_asset_id,
+            fee_treasury,
+            admission,
         })
     }
 }
 
+impl rlp::Encodable for Admission {
+    fn rlp_append(&self, s: &mut rlp::RlpStream) {
+        s.begin_list(4);
+        match &self.allow_senders {
+            Some(senders) => s.begin_list(1).append_list(senders),
+            None => s.begin_list(0),
+        };
+        s.append_list(&self.deny_senders)
+            .append(&self.min_cycles_price)
+            .begin_list(self.allowed_methods.len());
+        for (service, methods) in self.allowed_methods.iter() {
+            s.begin_list(2)
+                .append(service)
+                .append_list::<String, String>(methods);
+        }
+    }
+}
+
+impl rlp::Decodable for Admission {
+    fn decode(r: &rlp::Rlp) -> Result<Self, rlp::DecoderError> {
+        let allow_senders = if r.at(0)?.item_count()? > 0 {
+            Some(r.at(0)?.list_at(0)?)
+        } else {
+            None
+        };
+        let allowed_methods = r
+            .at(3)?
+            .iter()
+            .map(|item| Ok((item.val_at(0)?, item.list_at(1)?)))
+            .collect::<Result<_, rlp::DecoderError>>()?;
+
+        Ok(Self {
+            allow_senders,
+            deny_senders: r.list_at(1)?,
+            min_cycles_price: r.val_at(2)?,
+            allowed_methods,
+        })
+    }
+}
+
+// An option is encoded as a list of zero or one item.
+fn append_option<T: rlp::Encodable>(s: &mut rlp::RlpStream, value: &Option<T>) {
+    match value {
+        Some(value) => s.begin_list(1).append(value),
+        None => s.begin_list(0),
+    };
+}
+
+fn decode_option<T: rlp::Decodable>(r: &rlp::Rlp) -> Result<Option<T>, rlp::DecoderError> {
+    Ok(r.as_list::<T>()?.into_iter().next())
+}
+
 impl rlp::Encodable for ValidatorExtend {
     fn rlp_append(&self, s: &mut rlp::RlpStream) {
         s.begin_list(4)
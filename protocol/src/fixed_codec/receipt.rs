@@ -7,7 +7,11 @@ impl_default_fixed_codec_for!(receipt, [Receipt, ReceiptResponse]);
 
 impl rlp::Encodable for Receipt {
     fn rlp_append(&self, s: &mut rlp::RlpStream) {
-        s.begin_list(6);
+        // Receipts without a fee keep the encoding from before fees, so their
+        // hashes and the receipt roots of old blocks don't change.
+        let with_fee = self.fee != 0;
+
+        s.begin_list(if with_fee { 7 } else { 6 });
         s.append(&self.cycles_used);
         s.append(&self.height);
         s.begin_list(self.events.len());
@@ -17,6 +21,9 @@ impl rlp::Encodable for Receipt {
         s.append(&self.response);
         s.append(&self.state_root);
         s.append(&self.tx_hash);
+        if with_fee {
+            s.append(&self.fee);
+        }
     }
 }
 
@@ -32,6 +39,12 @@ impl rlp::Decodable for Receipt {
         let response: ReceiptResponse = rlp::decode(r.at(3)?.as_raw())?;
         let state_root = rlp::decode(r.at(4)?.as_raw())?;
         let tx_hash = rlp::decode(r.at(5)?.as_raw())?;
+        // Receipts saved before fees were introduced paid nothing.
+        let fee = if r.item_count()? > 6 {
+            r.at(6)?.as_val()?
+        } else {
+            0
+        };
 
         Ok(Receipt {
             state_root,
@@ -39,6 +52,7 @@ impl rlp::Decodable for Receipt {
             events,
             tx_hash,
             cycles_used,
+            fee,
             response,
         })
     }
@@ -62,6 +62,56 @@ fn test_fixed_codec() {
     test_eq!(receipt, Receipt, mock_receipt);
 }
 
+#[test]
+fn test_signed_tx_scheme() {
+    // Secp256k1 transactions are encoded without the scheme
+    let stx = mock_sign_tx();
+    let rlp_bytes = stx.encode_fixed().unwrap();
+    assert_eq!(rlp::Rlp::new(&rlp_bytes).item_count().unwrap(), 4);
+
+    let mut stx = mock_sign_tx();
+    stx.scheme = types::SignatureScheme::Ed25519;
+    let rlp_bytes = stx.encode_fixed().unwrap();
+    assert_eq!(rlp::Rlp::new(&rlp_bytes).item_count().unwrap(), 5);
+    assert_eq!(
+        types::SignedTransaction::decode_fixed(rlp_bytes).unwrap(),
+        stx
+    );
+}
+
+#[test]
+fn test_receipt_fee() {
+    // Receipts without a fee are encoded as before fees were introduced
+    let mut receipt = mock_receipt();
+    receipt.fee = 0;
+    let rlp_bytes = receipt.encode_fixed().unwrap();
+    assert_eq!(rlp::Rlp::new(&rlp_bytes).item_count().unwrap(), 6);
+    assert_eq!(types::Receipt::decode_fixed(rlp_bytes).unwrap(), receipt);
+
+    let receipt = mock_receipt();
+    let rlp_bytes = receipt.encode_fixed().unwrap();
+    assert_eq!(rlp::Rlp::new(&rlp_bytes).item_count().unwrap(), 7);
+    assert_eq!(types::Receipt::decode_fixed(rlp_bytes).unwrap(), receipt);
+}
+
+#[test]
+fn test_metadata_admission() {
+    // Metadata without fees or admission is encoded as before them
+    let mut metadata = types::Metadata::default();
+    let rlp_bytes = metadata.encode_fixed().unwrap();
+    assert_eq!(rlp::Rlp::new(&rlp_bytes).item_count().unwrap(), 13);
+
+    metadata.admission.allow_senders = Some(vec![mock_address()]);
+    metadata.admission.min_cycles_price = 1;
+    metadata
+        .admission
+        .allowed_methods
+        .insert("asset".to_owned(), vec!["transfer".to_owned()]);
+    let rlp_bytes = metadata.encode_fixed().unwrap();
+    assert_eq!(rlp::Rlp::new(&rlp_bytes).item_count().unwrap(), 16);
+    assert_eq!(types::Metadata::decode_fixed(rlp_bytes).unwrap(), metadata);
+}
+
 #[test]
 fn test_signed_tx_serialize_size() {
     let txs: Vec<Bytes> = (0..50_000)
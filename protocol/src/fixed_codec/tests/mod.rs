@@ -53,6 +53,7 @@ pub fn mock_receipt() -> Receipt {
         height:      13,
         tx_hash:     mock_hash(),
         cycles_used: 100,
+        fee:         100,
         events:      vec![mock_event()],
         response:    mock_receipt_response(),
     }
@@ -92,6 +93,7 @@ pub fn mock_sign_tx() -> SignedTransaction {
     SignedTransaction {
         raw:       mock_raw_tx(),
         tx_hash:   mock_hash(),
+        scheme:    Default::default(),
         pubkey:    Default::default(),
         signature: Default::default(),
     }
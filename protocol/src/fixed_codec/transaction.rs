@@ -2,11 +2,18 @@ use bytes::BytesMut;
 
 use crate::fixed_codec::{FixedCodec, FixedCodecError};
 use crate::types::primitive::Hash;
-use crate::types::transaction::{RawTransaction, SignedTransaction, TransactionRequest};
+use crate::types::transaction::{
+    MultiSigPubkey, MultiSigSignature, RawTransaction, SignatureScheme, SignedTransaction,
+    TransactionRequest,
+};
 use crate::{impl_default_fixed_codec_for, ProtocolResult};
 
 // Impl FixedCodec trait for types
-impl_default_fixed_codec_for!(transaction, [RawTransaction, SignedTransaction]);
+impl_default_fixed_codec_for!(transaction, [
+    RawTransaction,
+    SignedTransaction,
+    MultiSigPubkey
+]);
 
 impl rlp::Encodable for RawTransaction {
     fn rlp_append(&self, s: &mut rlp::RlpStream) {
@@ -51,13 +58,20 @@ impl rlp::Decodable for RawTransaction {
     }
 }
 
+// Transactions of secp256k1 are encoded without the scheme, so that they are
+// the same as before signature schemes are introduced.
 impl rlp::Encodable for SignedTransaction {
     fn rlp_append(&self, s: &mut rlp::RlpStream) {
-        s.begin_list(4)
+        let with_scheme = self.scheme != SignatureScheme::Secp256k1;
+
+        s.begin_list(if with_scheme { 5 } else { 4 })
             .append(&self.pubkey.to_vec())
             .append(&self.raw)
             .append(&self.signature.to_vec())
             .append(&self.tx_hash);
+        if with_scheme {
+            s.append(&self.scheme.id());
+        }
     }
 }
 
@@ -71,12 +85,67 @@ impl rlp::Decodable for SignedTransaction {
         let raw: RawTransaction = rlp::decode(r.at(1)?.as_raw())?;
         let signature = BytesMut::from(r.at(2)?.data()?).freeze();
         let tx_hash = rlp::decode(r.at(3)?.as_raw())?;
+        let scheme = if r.item_count()? > 4 {
+            SignatureScheme::from_id(r.at(4)?.as_val()?)
+                .map_err(|_| rlp::DecoderError::Custom("unknown signature scheme"))?
+        } else {
+            SignatureScheme::Secp256k1
+        };
 
         Ok(SignedTransaction {
             raw,
             tx_hash,
+            scheme,
             pubkey,
             signature,
         })
     }
 }
+
+impl rlp::Encodable for MultiSigPubkey {
+    fn rlp_append(&self, s: &mut rlp::RlpStream) {
+        s.begin_list(2).append(&self.threshold);
+        s.begin_list(self.pubkeys.len());
+        for pubkey in self.pubkeys.iter() {
+            s.append(&pubkey.to_vec());
+        }
+    }
+}
+
+impl rlp::Decodable for MultiSigPubkey {
+    fn decode(r: &rlp::Rlp) -> Result<Self, rlp::DecoderError> {
+        if !r.is_list() || r.item_count()? != 2 {
+            return Err(rlp::DecoderError::RlpIncorrectListLen);
+        }
+
+        let threshold = r.at(0)?.as_val()?;
+        let pubkeys = r
+            .at(1)?
+            .iter()
+            .map(|pk| Ok(BytesMut::from(pk.data()?).freeze()))
+            .collect::<Result<_, rlp::DecoderError>>()?;
+
+        Ok(MultiSigPubkey { threshold, pubkeys })
+    }
+}
+
+impl rlp::Encodable for MultiSigSignature {
+    fn rlp_append(&self, s: &mut rlp::RlpStream) {
+        s.begin_list(2)
+            .append(&self.index)
+            .append(&self.signature.to_vec());
+    }
+}
+
+impl rlp::Decodable for MultiSigSignature {
+    fn decode(r: &rlp::Rlp) -> Result<Self, rlp::DecoderError> {
+        if !r.is_list() || r.item_count()? != 2 {
+            return Err(rlp::DecoderError::RlpIncorrectListLen);
+        }
+
+        Ok(MultiSigSignature {
+            index:     r.at(0)?.as_val()?,
+            signature: BytesMut::from(r.at(1)?.data()?).freeze(),
+        })
+    }
+}
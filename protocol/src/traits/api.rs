@@ -1,9 +1,37 @@
 use async_trait::async_trait;
+use bytes::Bytes;
 
-use crate::traits::{Context, ServiceResponse};
-use crate::types::{Address, Block, Hash, Receipt, SignedTransaction};
+use crate::traits::{Context, ServiceResponse, StateProof};
+use crate::types::{
+    Address, Block, Hash, MerkleProof, Metadata, Proof, RawTransaction, Receipt, SignedTransaction,
+};
 use crate::ProtocolResult;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionStatus {
+    /// Waiting in mempool.
+    Pending,
+    /// Ordered by a proposal, the block is not committed yet.
+    Proposed,
+    /// Included in a committed block.
+    Committed,
+    /// Neither in mempool nor on chain, it may have been dropped.
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChainInfo {
+    pub latest_height: u64,
+    pub exec_height:   u64,
+    pub latest_proof:  Proof,
+    pub metadata:      Metadata,
+    pub peer_count:    u64,
+    pub mempool_size:  u64,
+    pub is_syncing:    bool,
+    /// `None` if the node status is not set to the adapter.
+    pub self_address:  Option<Address>,
+}
+
 #[async_trait]
 pub trait APIAdapter: Send + Sync {
     async fn insert_signed_txs(
@@ -12,9 +40,17 @@ pub trait APIAdapter: Send + Sync {
         signed_tx: SignedTransaction,
     ) -> ProtocolResult<()>;
 
+    async fn insert_batch_signed_txs(
+        &self,
+        ctx: Context,
+        signed_txs: Vec<SignedTransaction>,
+    ) -> Vec<ProtocolResult<()>>;
+
     async fn get_block_by_height(&self, ctx: Context, height: Option<u64>)
         -> ProtocolResult<Block>;
 
+    async fn get_block_by_hash(&self, ctx: Context, block_hash: Hash) -> ProtocolResult<Block>;
+
     async fn get_receipt_by_tx_hash(&self, ctx: Context, tx_hash: Hash) -> ProtocolResult<Receipt>;
 
     async fn get_transaction_by_hash(
@@ -23,10 +59,84 @@ pub trait APIAdapter: Send + Sync {
         tx_hash: Hash,
     ) -> ProtocolResult<SignedTransaction>;
 
-    async fn query_service(
+    /// Get at most `limit` transactions of the block, starting from the
+    /// `offset`th one of its ordered transaction hashes.
+    async fn get_transactions_in_block(
+        &self,
+        ctx: Context,
+        height: u64,
+        offset: u64,
+        limit: u64,
+    ) -> ProtocolResult<Vec<SignedTransaction>>;
+
+    /// Same as `get_transactions_in_block` but returns receipts, the block
+    /// must have been executed.
+    async fn get_receipts_in_block(
         &self,
         ctx: Context,
         height: u64,
+        offset: u64,
+        limit: u64,
+    ) -> ProtocolResult<Vec<Receipt>>;
+
+    /// Get at most `limit` transactions sent by the address, the latest one
+    /// comes first.
+    async fn get_transactions_by_address(
+        &self,
+        ctx: Context,
+        address: Address,
+        offset: u64,
+        limit: u64,
+    ) -> ProtocolResult<Vec<SignedTransaction>>;
+
+    /// Get the receipts of transactions executed between `from_height` and
+    /// `to_height` (inclusive) which emit events of the service. Events of
+    /// other services, or whose data is not the topic, are stripped from the
+    /// receipts.
+    async fn get_events(
+        &self,
+        ctx: Context,
+        from_height: u64,
+        to_height: u64,
+        service: String,
+        topic: Option<String>,
+    ) -> ProtocolResult<Vec<Receipt>>;
+
+    /// Merkle proof of the transaction against the `order_root` of its block.
+    async fn get_transaction_proof(
+        &self,
+        ctx: Context,
+        tx_hash: Hash,
+    ) -> ProtocolResult<MerkleProof>;
+
+    /// Merkle proof of the receipt against the `receipt_root`, which is
+    /// recorded by a later block. Leaves are the hashes of encoded receipts.
+    async fn get_receipt_proof(&self, ctx: Context, tx_hash: Hash) -> ProtocolResult<MerkleProof>;
+
+    async fn get_chain_info(&self, ctx: Context) -> ProtocolResult<ChainInfo>;
+
+    async fn get_mempool_tx_count(&self, ctx: Context) -> ProtocolResult<u64>;
+
+    async fn get_pending_tx_hashes(&self, ctx: Context, limit: u64) -> ProtocolResult<Vec<Hash>>;
+
+    async fn get_pending_transaction(
+        &self,
+        ctx: Context,
+        tx_hash: Hash,
+    ) -> ProtocolResult<Option<SignedTransaction>>;
+
+    async fn get_transaction_status(
+        &self,
+        ctx: Context,
+        tx_hash: Hash,
+    ) -> ProtocolResult<TransactionStatus>;
+
+    /// Read the service at the state right after the block of `height` was
+    /// executed, or at the latest state if `height` is `None`.
+    async fn query_service(
+        &self,
+        ctx: Context,
+        height: Option<u64>,
         cycles_limit: u64,
         cycles_price: u64,
         caller: Address,
@@ -34,4 +144,24 @@ pub trait APIAdapter: Send + Sync {
         method: String,
         payload: String,
     ) -> ProtocolResult<ServiceResponse<String>>;
+
+    /// Prove the key of the service state right after the block of `height`
+    /// was executed, or of the latest state if `height` is `None`.
+    async fn get_state_proof(
+        &self,
+        ctx: Context,
+        height: Option<u64>,
+        service_name: String,
+        address: Option<Address>,
+        key: Bytes,
+    ) -> ProtocolResult<StateProof>;
+
+    /// Execute the transaction against the latest state without committing,
+    /// so the response, events and cycles used are known before sending it.
+    async fn simulate_transaction(
+        &self,
+        ctx: Context,
+        caller: Address,
+        raw: RawTransaction,
+    ) -> ProtocolResult<Receipt>;
 }
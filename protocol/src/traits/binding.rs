@@ -1,4 +1,5 @@
 use std::iter::Iterator;
+use std::sync::Arc;
 
 use bytes::Bytes;
 
@@ -15,6 +16,11 @@ pub trait ServiceMapping: Send + Sync {
     ) -> ProtocolResult<Box<dyn Service>>;
 
     fn list_service_name(&self) -> Vec<String>;
+
+    // Admission controls which every transaction must pass, in order.
+    fn admission_controls(&self) -> Vec<Arc<dyn AdmissionControl>> {
+        Vec::new()
+    }
 }
 
 // `ServiceState` provides access to` world state` and `account` for` service`.
@@ -27,6 +33,10 @@ pub trait ServiceState {
 
     fn contains<Key: FixedCodec>(&self, key: &Key) -> ProtocolResult<bool>;
 
+    // Get the merkle proof of the key from the committed state, the values
+    // in cache and stash are not included.
+    fn get_proof<Key: FixedCodec>(&self, key: &Key) -> ProtocolResult<Vec<Bytes>>;
+
     // Insert a pair of key / value
     // Note: This key/value pair will go into the cache first
     // and will not be persisted to MPT until `commit` is called.
@@ -68,9 +78,13 @@ pub trait ChainQuerier {
     fn get_receipt_by_hash(&self, tx_hash: &Hash) -> ProtocolResult<Option<Receipt>>;
 }
 
-// Admission control will be called before entering service
-pub trait AdmissionControl {
-    fn next<SDK: ServiceSDK>(&self, ctx: ServiceContext, sdk: SDK) -> ProtocolResult<()>;
+// Admission control will be called before a transaction enters the mempool,
+// and again before the executor dispatches it to the service. A transaction
+// rejected by the executor gets an error receipt.
+//
+// Checks must be deterministic, they are run by every node.
+pub trait AdmissionControl: Send + Sync {
+    fn next(&self, ctx: &ServiceContext) -> ProtocolResult<()>;
 }
 
 // Developers can use service to customize blockchain business
@@ -39,6 +39,9 @@ pub trait Consensus: Send + Sync {
 #[async_trait]
 pub trait Synchronization: Send + Sync {
     async fn receive_remote_block(&self, ctx: Context, remote_height: u64) -> ProtocolResult<()>;
+
+    /// Whether the node is catching up with remote blocks.
+    fn is_syncing(&self) -> bool;
 }
 
 #[async_trait]
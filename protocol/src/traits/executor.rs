@@ -1,8 +1,11 @@
 use std::sync::Arc;
 
-use crate::traits::{ServiceMapping, Storage};
+use bytes::Bytes;
+
+use crate::traits::{AdmissionControl, ServiceMapping, Storage};
 use crate::types::{
-    Address, Bloom, MerkleRoot, Receipt, ServiceContext, SignedTransaction, TransactionRequest,
+    Address, Bloom, Hash, MerkleRoot, RawTransaction, Receipt, ServiceContext, SignedTransaction,
+    TransactionRequest,
 };
 use crate::ProtocolResult;
 
@@ -20,6 +23,21 @@ pub struct ExecutorParams {
     pub height:       u64,
     pub timestamp:    u64,
     pub cycles_limit: u64,
+    pub proposer:     Address,
+}
+
+// Proofs of a key in the service state. `root_proof` proves the service root
+// under `state_root`, keyed by the service name, and `proof` proves the key
+// under the service root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateProof {
+    pub state_root:   MerkleRoot,
+    pub service_name: String,
+    pub service_root: MerkleRoot,
+    pub root_proof:   Vec<Bytes>,
+    pub key:          Bytes,
+    pub value:        Option<Bytes>,
+    pub proof:        Vec<Bytes>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -86,6 +104,30 @@ pub trait Executor {
         cycles_price: u64,
         request: &TransactionRequest,
     ) -> ProtocolResult<ServiceResponse<String>>;
+
+    // Execute a transaction as a write without committing, all state changes
+    // are left in the cache and dropped along with the executor.
+    fn simulate(
+        &mut self,
+        params: &ExecutorParams,
+        caller: &Address,
+        tx_hash: Hash,
+        raw: &RawTransaction,
+    ) -> ProtocolResult<Receipt>;
+
+    // The key is the encoded one, if `address` is given it is the key of an
+    // account value.
+    fn get_state_proof(
+        &self,
+        params: &ExecutorParams,
+        service_name: &str,
+        address: Option<&Address>,
+        key: Bytes,
+    ) -> ProtocolResult<StateProof>;
+
+    // Admission controls registered by services, then the ones set in the
+    // metadata of the chain, which transactions must pass before dispatch.
+    fn admission_controls(&self) -> ProtocolResult<Vec<Arc<dyn AdmissionControl>>>;
 }
 
 // `Dispatcher` provides ability to send a call message to other services
@@ -20,6 +20,14 @@ impl MixedTxHashes {
 pub trait MemPool: Send + Sync {
     async fn insert(&self, ctx: Context, tx: SignedTransaction) -> ProtocolResult<()>;
 
+    /// Insert transactions concurrently, returns the result of each one in
+    /// the same order.
+    async fn insert_batch(
+        &self,
+        ctx: Context,
+        txs: Vec<SignedTransaction>,
+    ) -> Vec<ProtocolResult<()>>;
+
     async fn package(
         &self,
         ctx: Context,
@@ -54,6 +62,19 @@ pub trait MemPool: Send + Sync {
     ) -> ProtocolResult<()>;
 
     fn set_args(&self, timeout_gap: u64, cycles_limit: u64, max_tx_size: u64);
+
+    /// The number of transactions waiting in pool.
+    fn tx_count(&self) -> usize;
+
+    /// Hashes of at most `limit` transactions waiting in pool, in no
+    /// particular order.
+    fn pending_tx_hashes(&self, limit: usize) -> Vec<Hash>;
+
+    fn get_pending_tx(&self, tx_hash: &Hash) -> Option<SignedTransaction>;
+
+    /// A transaction pulled for an ordering proposal, which is not in pool,
+    /// until the block is committed.
+    fn get_proposed_tx(&self, tx_hash: &Hash) -> Option<SignedTransaction>;
 }
 
 #[async_trait]
@@ -72,6 +93,8 @@ pub trait MemPoolAdapter: Send + Sync {
 
     async fn broadcast_tx(&self, ctx: Context, tx: SignedTransaction) -> ProtocolResult<()>;
 
+    /// Check the hash, chain id and signature of the transaction. The peer
+    /// which sent a transaction failing them is reported.
     async fn check_signature(&self, ctx: Context, tx: SignedTransaction) -> ProtocolResult<()>;
 
     async fn check_transaction(&self, ctx: Context, tx: SignedTransaction) -> ProtocolResult<()>;
@@ -80,5 +103,8 @@ pub trait MemPoolAdapter: Send + Sync {
 
     async fn get_latest_height(&self, ctx: Context) -> ProtocolResult<u64>;
 
+    /// Report the peer which sent us a malformed transaction.
+    fn report_invalid_tx(&self, ctx: Context, tx_hash: &Hash);
+
     fn set_args(&self, timeout_gap: u64, cycles_limit: u64, max_tx_size: u64);
 }
@@ -6,7 +6,7 @@ mod mempool;
 mod network;
 mod storage;
 
-pub use api::APIAdapter;
+pub use api::{APIAdapter, ChainInfo, TransactionStatus};
 pub use binding::{
     AdmissionControl, ChainQuerier, Service, ServiceMapping, ServiceSDK, ServiceState, StoreArray,
     StoreBool, StoreMap, StoreString, StoreUint64,
@@ -17,10 +17,10 @@ pub use consensus::{
 };
 pub use executor::{
     Dispatcher, Executor, ExecutorFactory, ExecutorParams, ExecutorResp, NoopDispatcher,
-    ServiceResponse,
+    ServiceResponse, StateProof,
 };
 pub use mempool::{MemPool, MemPoolAdapter, MixedTxHashes};
-pub use network::{Gossip, MessageCodec, MessageHandler, Priority, Rpc};
+pub use network::{Gossip, MessageCodec, MessageHandler, NetworkStatus, PeerTrust, Priority, Rpc};
 pub use storage::{Storage, StorageAdapter, StorageBatchModify, StorageCategory, StorageSchema};
 
 pub use creep::{Cloneable, Context};
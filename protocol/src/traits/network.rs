@@ -84,6 +84,16 @@ pub trait Rpc: Send + Sync {
         M: MessageCodec;
 }
 
+pub trait NetworkStatus: Send + Sync {
+    fn connected_peers(&self) -> usize;
+}
+
+pub trait PeerTrust: Send + Sync {
+    /// Report the remote peer of the context, which sent us invalid data.
+    /// The peer is disconnected after too many reports.
+    fn report_bad(&self, ctx: Context, reason: &str);
+}
+
 #[async_trait]
 pub trait MessageHandler: Sync + Send + 'static {
     type Message: MessageCodec;
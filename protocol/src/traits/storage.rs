@@ -4,7 +4,7 @@ use derive_more::Display;
 use crate::codec::ProtocolCodec;
 use crate::types::block::{Block, Proof};
 use crate::types::receipt::Receipt;
-use crate::types::{Hash, SignedTransaction};
+use crate::types::{Address, Hash, SignedTransaction};
 use crate::{Bytes, ProtocolResult};
 
 #[derive(Debug, Copy, Clone, Display)]
@@ -13,6 +13,7 @@ pub enum StorageCategory {
     Receipt,
     SignedTransaction,
     Wal,
+    AddressIndex,
 }
 
 pub trait StorageSchema {
@@ -34,8 +35,22 @@ pub trait Storage: Send + Sync {
 
     async fn get_transaction_by_hash(&self, tx_hash: Hash) -> ProtocolResult<SignedTransaction>;
 
+    /// `None` if the transaction is not committed.
+    async fn try_get_transaction_by_hash(
+        &self,
+        tx_hash: Hash,
+    ) -> ProtocolResult<Option<SignedTransaction>>;
+
     async fn get_transactions(&self, hashes: Vec<Hash>) -> ProtocolResult<Vec<SignedTransaction>>;
 
+    /// Get the transactions sent by the address, the latest one comes first.
+    async fn get_transactions_by_address(
+        &self,
+        address: Address,
+        offset: u64,
+        limit: u64,
+    ) -> ProtocolResult<Vec<SignedTransaction>>;
+
     async fn get_latest_block(&self) -> ProtocolResult<Block>;
 
     async fn get_block_by_height(&self, height: u64) -> ProtocolResult<Block>;
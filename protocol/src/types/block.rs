@@ -49,3 +49,20 @@ pub struct Pill {
     pub block:          Block,
     pub propose_hashes: Vec<Hash>,
 }
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleProofNode {
+    pub is_right: bool,
+    pub hash:     Hash,
+}
+
+// A merkle path from the leaf to the root, which is carried by the header of
+// the block at `height`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleProof {
+    pub height: u64,
+    pub root:   MerkleRoot,
+    pub leaf:   Hash,
+    pub index:  u64,
+    pub nodes:  Vec<MerkleProofNode>,
+}
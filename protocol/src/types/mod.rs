@@ -11,17 +11,20 @@ use derive_more::{Display, From};
 
 use crate::{ProtocolError, ProtocolErrorKind};
 
-pub use block::{Block, BlockHeader, Pill, Proof, Validator};
+pub use block::{Block, BlockHeader, MerkleProof, MerkleProofNode, Pill, Proof, Validator};
 pub use bytes::{Bytes, BytesMut};
 pub use ethbloom::{Bloom, BloomRef, Input as BloomInput};
 pub use genesis::{Genesis, ServiceParam};
 pub use primitive::{
-    Address, Balance, Hash, Hex, JsonString, MerkleRoot, Metadata, ValidatorExtend, GENESIS_HEIGHT,
-    METADATA_KEY,
+    Address, Admission, Balance, Hash, Hex, JsonString, MerkleRoot, Metadata, ValidatorExtend,
+    GENESIS_HEIGHT, METADATA_KEY,
 };
 pub use receipt::{Event, Receipt, ReceiptResponse};
 pub use service_context::{ServiceContext, ServiceContextError, ServiceContextParams};
-pub use transaction::{RawTransaction, SignedTransaction, TransactionRequest};
+pub use transaction::{
+    MultiSigPubkey, MultiSigSignature, RawTransaction, SignatureScheme, SignedTransaction,
+    TransactionRequest, TxWitness, MAX_MULTISIG_PUBKEYS,
+};
 
 #[derive(Debug, Display, From)]
 pub enum TypesError {
@@ -36,6 +39,12 @@ pub enum TypesError {
 
     #[display(fmt = "Hex should start with 0x")]
     HexPrefix,
+
+    #[display(fmt = "Invalid multisig: {}", reason)]
+    InvalidMultiSig { reason: &'static str },
+
+    #[display(fmt = "Unknown signature scheme {}", id)]
+    UnknownScheme { id: u8 },
 }
 
 impl Error for TypesError {}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fmt;
 
 use bytes::{Bytes, BytesMut};
@@ -7,7 +8,8 @@ use num_bigint::BigUint;
 use serde::de;
 use serde::{Deserialize, Serialize};
 
-use crate::types::TypesError;
+use crate::fixed_codec::FixedCodec;
+use crate::types::{MultiSigPubkey, SignatureScheme, TypesError};
 use crate::ProtocolResult;
 
 pub const METADATA_KEY: &str = "metadata";
@@ -250,6 +252,26 @@ impl Address {
         Self::from_hash(hash)
     }
 
+    /// Address of a public key of the scheme. Addresses of secp256k1 keys
+    /// are the same as `from_pubkey_bytes`, keys of other schemes are hashed
+    /// with the scheme id as prefix.
+    pub fn from_scheme_pubkey(scheme: SignatureScheme, bytes: Bytes) -> ProtocolResult<Self> {
+        if scheme == SignatureScheme::Secp256k1 {
+            return Self::from_pubkey_bytes(bytes);
+        }
+
+        let mut prefixed = BytesMut::with_capacity(1 + bytes.len());
+        prefixed.extend_from_slice(&[scheme.id()]);
+        prefixed.extend_from_slice(&bytes);
+        Self::from_pubkey_bytes(prefixed.freeze())
+    }
+
+    /// Address of a multisig account, which is the address of the multisig
+    /// public key in fixed codec.
+    pub fn from_multisig(scheme: SignatureScheme, pubkey: &MultiSigPubkey) -> ProtocolResult<Self> {
+        Self::from_scheme_pubkey(scheme, pubkey.encode_fixed()?)
+    }
+
     pub fn from_hash(hash: Hash) -> ProtocolResult<Self> {
         let mut hash_val = hash.as_bytes();
         hash_val.truncate(20);
@@ -303,6 +325,31 @@ pub struct Metadata {
     pub brake_ratio:     u64,
     pub tx_num_limit:    u64,
     pub max_tx_size:     u64,
+    // Fees of `cycles_used * cycles_price` are charged in this asset, it is
+    // free to send transactions if not set.
+    #[serde(default)]
+    pub fee_asset_id:    Option<Hash>,
+    // Fees go to the proposer of the block if not set.
+    #[serde(default)]
+    pub fee_treasury:    Option<Address>,
+    #[serde(default)]
+    pub admission:       Admission,
+}
+
+// Admission checks of transactions on chain, run by the mempool and the
+// executor of every node. Nothing is checked by default.
+#[derive(Deserialize, Default, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Admission {
+    // Only these senders may send transactions if set.
+    #[serde(default)]
+    pub allow_senders:    Option<Vec<Address>>,
+    #[serde(default)]
+    pub deny_senders:     Vec<Address>,
+    #[serde(default)]
+    pub min_cycles_price: u64,
+    // Allowed methods by service name, other services are not restricted.
+    #[serde(default)]
+    pub allowed_methods:  BTreeMap<String, Vec<String>>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
@@ -354,6 +401,7 @@ mod tests {
     use bytes::Bytes;
 
     use super::{Address, Hash};
+    use crate::types::{MultiSigPubkey, SignatureScheme};
 
     #[test]
     fn test_hash() {
@@ -374,6 +422,45 @@ mod tests {
         assert_eq!(addr.as_hex(), expect_addr);
     }
 
+    #[test]
+    fn test_from_multisig() {
+        let scheme = SignatureScheme::Secp256k1;
+        let pubkeys = (1..4u8)
+            .map(|i| Bytes::from(vec![2u8, i]))
+            .collect::<Vec<_>>();
+
+        let pubkey = MultiSigPubkey::new(2, pubkeys.clone()).unwrap();
+        let addr = Address::from_multisig(scheme, &pubkey).unwrap();
+        let other = MultiSigPubkey::new(3, pubkeys.clone()).unwrap();
+        assert_ne!(addr, Address::from_multisig(scheme, &other).unwrap());
+        assert_ne!(
+            addr,
+            Address::from_pubkey_bytes(pubkeys[0].clone()).unwrap()
+        );
+        assert_ne!(
+            addr,
+            Address::from_multisig(SignatureScheme::Ed25519, &pubkey).unwrap()
+        );
+
+        assert!(MultiSigPubkey::new(0, pubkeys.clone()).is_err());
+        assert!(MultiSigPubkey::new(4, pubkeys.clone()).is_err());
+        assert!(MultiSigPubkey::new(1, vec![pubkeys[0].clone(), pubkeys[0].clone()]).is_err());
+    }
+
+    #[test]
+    fn test_from_scheme_pubkey() {
+        let pubkey = Bytes::from(vec![3u8; 33]);
+
+        assert_eq!(
+            Address::from_scheme_pubkey(SignatureScheme::Secp256k1, pubkey.clone()).unwrap(),
+            Address::from_pubkey_bytes(pubkey.clone()).unwrap()
+        );
+        assert_ne!(
+            Address::from_scheme_pubkey(SignatureScheme::Ed25519, pubkey.clone()).unwrap(),
+            Address::from_pubkey_bytes(pubkey).unwrap()
+        );
+    }
+
     #[test]
     fn test_address() {
         let add_str = "CAB8EEA4799C21379C20EF5BAA2CC8AF1BEC475B";
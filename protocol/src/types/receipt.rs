@@ -13,6 +13,8 @@ pub struct Receipt {
     pub height:      u64,
     pub tx_hash:     Hash,
     pub cycles_used: u64,
+    // Fee paid by the caller, `cycles_used * cycles_price` if fees are enabled.
+    pub fee:         u64,
     pub events:      Vec<Event>,
     pub response:    ReceiptResponse,
 }
@@ -1,6 +1,9 @@
 use bytes::Bytes;
 
-use crate::types::primitive::{Hash, JsonString};
+use crate::fixed_codec::{FixedCodec, FixedCodecError};
+use crate::types::primitive::{Address, Hash, JsonString};
+use crate::types::TypesError;
+use crate::ProtocolResult;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct RawTransaction {
@@ -23,6 +26,166 @@ pub struct TransactionRequest {
 pub struct SignedTransaction {
     pub raw:       RawTransaction,
     pub tx_hash:   Hash,
+    pub scheme:    SignatureScheme,
     pub pubkey:    Bytes,
     pub signature: Bytes,
 }
+
+/// Signature scheme of the keys which signed a transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SignatureScheme {
+    Secp256k1,
+    Ed25519,
+}
+
+impl SignatureScheme {
+    pub fn id(self) -> u8 {
+        match self {
+            SignatureScheme::Secp256k1 => 0,
+            SignatureScheme::Ed25519 => 1,
+        }
+    }
+
+    pub fn from_id(id: u8) -> ProtocolResult<Self> {
+        match id {
+            0 => Ok(SignatureScheme::Secp256k1),
+            1 => Ok(SignatureScheme::Ed25519),
+            _ => Err(TypesError::UnknownScheme { id }.into()),
+        }
+    }
+}
+
+impl Default for SignatureScheme {
+    fn default() -> Self {
+        SignatureScheme::Secp256k1
+    }
+}
+
+/// Max number of public keys in a multisig account.
+pub const MAX_MULTISIG_PUBKEYS: usize = 16;
+
+const ED25519_PUBKEY_LEN: usize = 32;
+
+/// Public keys of an M-of-N multisig account, at least `threshold` of them
+/// must sign a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultiSigPubkey {
+    pub threshold: u8,
+    pub pubkeys:   Vec<Bytes>,
+}
+
+/// Signature signed by the key at `index` of `MultiSigPubkey::pubkeys`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultiSigSignature {
+    pub index:     u8,
+    pub signature: Bytes,
+}
+
+/// Who signed a transaction, and how.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TxWitness {
+    Single {
+        pubkey:    Bytes,
+        signature: Bytes,
+    },
+    MultiSig {
+        pubkey:     MultiSigPubkey,
+        signatures: Vec<MultiSigSignature>,
+    },
+}
+
+impl MultiSigPubkey {
+    pub fn new(threshold: u8, pubkeys: Vec<Bytes>) -> ProtocolResult<Self> {
+        let pubkey = MultiSigPubkey { threshold, pubkeys };
+        pubkey.check()?;
+        Ok(pubkey)
+    }
+
+    fn check(&self) -> ProtocolResult<()> {
+        let reason = if self.pubkeys.is_empty() || self.pubkeys.len() > MAX_MULTISIG_PUBKEYS {
+            "number of public keys out of range"
+        } else if self.threshold == 0 || self.threshold as usize > self.pubkeys.len() {
+            "threshold out of range"
+        } else if (1..self.pubkeys.len()).any(|i| self.pubkeys[..i].contains(&self.pubkeys[i])) {
+            "duplicate public key"
+        } else if self.pubkeys.iter().any(|pk| is_multisig_pubkey(pk)) {
+            "nested multisig public key"
+        } else {
+            return Ok(());
+        };
+
+        Err(TypesError::InvalidMultiSig { reason }.into())
+    }
+}
+
+impl SignedTransaction {
+    /// A transaction of a multisig account. The multisig public key and
+    /// signatures are carried in rlp in the `pubkey` and `signature` fields,
+    /// so the sender is still the address of `pubkey`. All keys are of the
+    /// same `scheme`.
+    pub fn new_multisig(
+        raw: RawTransaction,
+        tx_hash: Hash,
+        scheme: SignatureScheme,
+        pubkey: &MultiSigPubkey,
+        signatures: &[MultiSigSignature],
+    ) -> ProtocolResult<Self> {
+        pubkey.check()?;
+
+        Ok(SignedTransaction {
+            raw,
+            tx_hash,
+            scheme,
+            pubkey: pubkey.encode_fixed()?,
+            signature: Bytes::from(rlp::encode_list::<MultiSigSignature, _>(signatures)),
+        })
+    }
+
+    pub fn sender(&self) -> ProtocolResult<Address> {
+        Address::from_scheme_pubkey(self.scheme, self.pubkey.clone())
+    }
+
+    /// Decode the signer of the transaction. Signatures of a multisig
+    /// transaction must be of distinct keys, and no less than the threshold.
+    pub fn witness(&self) -> ProtocolResult<TxWitness> {
+        if !is_multisig_pubkey(&self.pubkey) {
+            return Ok(TxWitness::Single {
+                pubkey:    self.pubkey.clone(),
+                signature: self.signature.clone(),
+            });
+        }
+
+        let pubkey = MultiSigPubkey::decode_fixed(self.pubkey.clone())?;
+        pubkey.check()?;
+        let signatures: Vec<MultiSigSignature> = rlp::Rlp::new(&self.signature)
+            .as_list()
+            .map_err(FixedCodecError::from)?;
+
+        let mut signed = vec![false; pubkey.pubkeys.len()];
+        for sig in signatures.iter() {
+            match signed.get_mut(sig.index as usize) {
+                Some(signed) if !*signed => *signed = true,
+                _ => {
+                    return Err(TypesError::InvalidMultiSig {
+                        reason: "invalid signature index",
+                    }
+                    .into())
+                }
+            }
+        }
+        if signatures.len() < pubkey.threshold as usize {
+            return Err(TypesError::InvalidMultiSig {
+                reason: "not enough signatures",
+            }
+            .into());
+        }
+
+        Ok(TxWitness::MultiSig { pubkey, signatures })
+    }
+}
+
+// A multisig public key is a rlp list, which is longer than an ed25519 key.
+// Secp256k1 keys start with 0x02, 0x03 or 0x04, never a rlp list prefix.
+fn is_multisig_pubkey(bytes: &[u8]) -> bool {
+    bytes.len() > ED25519_PUBKEY_LEN && bytes[0] >= 0xc0
+}
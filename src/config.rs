@@ -4,20 +4,36 @@ use std::path::PathBuf;
 
 use serde_derive::Deserialize;
 
-use core_mempool::{DEFAULT_BROADCAST_TXS_INTERVAL, DEFAULT_BROADCAST_TXS_SIZE};
-use protocol::types::Hex;
+use core_api::config::ApiKey;
+use core_mempool::{
+    PackageOrder, DEFAULT_BROADCAST_TXS_INTERVAL, DEFAULT_BROADCAST_TXS_SIZE, DEFAULT_PRICE_BUMP,
+    DEFAULT_VERIFY_BATCH_SIZE, DEFAULT_VERIFY_QUEUE_SIZE,
+};
+use protocol::types::{Admission, Hex};
+
+const DEFAULT_PRUNE_INTERVAL: u64 = 100;
 
 #[derive(Debug, Deserialize)]
 pub struct ConfigGraphQL {
-    pub listening_address: SocketAddr,
-    pub graphql_uri:       String,
-    pub graphiql_uri:      String,
+    pub listening_address:    SocketAddr,
+    pub graphql_uri:          String,
+    pub graphiql_uri:         String,
+    #[serde(default)]
+    pub subscription_uri:     String,
+    pub jsonrpc_uri:          Option<String>,
     #[serde(default)]
-    pub workers:           usize,
+    pub workers:              usize,
     #[serde(default)]
-    pub maxconn:           usize,
+    pub maxconn:              usize,
     #[serde(default)]
-    pub max_payload_size:  usize,
+    pub max_payload_size:     usize,
+    pub api_keys:             Option<Vec<ApiKey>>,
+    pub enable_graphiql:      Option<bool>,
+    pub enable_unsafe_api:    Option<bool>,
+    pub rate_limit:           Option<u64>,
+    pub rate_limit_burst:     Option<u64>,
+    pub max_query_depth:      Option<usize>,
+    pub max_query_complexity: Option<usize>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -63,6 +79,18 @@ fn default_broadcast_txs_interval() -> u64 {
     DEFAULT_BROADCAST_TXS_INTERVAL
 }
 
+fn default_price_bump() -> u64 {
+    DEFAULT_PRICE_BUMP
+}
+
+fn default_verify_batch_size() -> usize {
+    DEFAULT_VERIFY_BATCH_SIZE
+}
+
+fn default_verify_queue_size() -> usize {
+    DEFAULT_VERIFY_QUEUE_SIZE
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ConfigMempool {
     pub pool_size: u64,
@@ -71,6 +99,33 @@ pub struct ConfigMempool {
     pub broadcast_txs_size:     usize,
     #[serde(default = "default_broadcast_txs_interval")]
     pub broadcast_txs_interval: u64,
+    // "fifo" or "fee_priority"
+    #[serde(default)]
+    pub package_order:          PackageOrder,
+    // Journal pending transactions to reload them after restart.
+    #[serde(default)]
+    pub enable_journal:         bool,
+    // Max pending transactions of a sender, 0 means unlimited.
+    #[serde(default)]
+    pub max_txs_per_sender:     usize,
+    // Max bytes of pending transactions of a sender, 0 means unlimited.
+    #[serde(default)]
+    pub max_bytes_per_sender:   usize,
+    // Percentage of cycles price a transaction pays more at least to replace
+    // the pending one of the same sender and nonce. Replacement doesn't
+    // cancel a transaction, the replaced one may still be committed from the
+    // pools of other nodes.
+    #[serde(default = "default_price_bump")]
+    pub price_bump:             u64,
+    // Threads verifying signatures, 0 means one per CPU.
+    #[serde(default)]
+    pub verify_workers:         usize,
+    #[serde(default = "default_verify_batch_size")]
+    pub verify_batch_size:      usize,
+    // Max transactions waiting for signature verification, further ones are
+    // rejected, and gossip of peers is dropped.
+    #[serde(default = "default_verify_queue_size")]
+    pub verify_queue_size:      usize,
 }
 
 #[derive(Debug, Deserialize)]
@@ -78,6 +133,36 @@ pub struct ConfigExecutor {
     pub light: bool,
 }
 
+fn default_prune_interval() -> u64 {
+    DEFAULT_PRUNE_INTERVAL
+}
+
+// History pruning of non-archive nodes, 0 means keeping all.
+#[derive(Debug, Deserialize)]
+pub struct ConfigPrune {
+    // Latest blocks kept with their transactions and receipts, must be more
+    // than the timeout gap of transactions.
+    #[serde(default)]
+    pub keep_blocks: u64,
+    // Latest states kept, older ones can't be queried. Not supported by a
+    // light executor.
+    #[serde(default)]
+    pub keep_states: u64,
+    // Blocks between two rounds of state pruning.
+    #[serde(default = "default_prune_interval")]
+    pub interval:    u64,
+}
+
+impl Default for ConfigPrune {
+    fn default() -> Self {
+        Self {
+            keep_blocks: 0,
+            keep_states: 0,
+            interval:    DEFAULT_PRUNE_INTERVAL,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ConfigRocksDB {
     pub max_open_files: i32,
@@ -126,12 +211,19 @@ pub struct Config {
     pub network:   ConfigNetwork,
     pub mempool:   ConfigMempool,
     pub executor:  ConfigExecutor,
+    // Admission checks of transactions accepted by the mempool of this node,
+    // along with the ones in the metadata of the chain, which every node
+    // checks. Transactions proposed by other nodes are executed without them.
+    #[serde(default)]
+    pub admission: Admission,
     #[serde(default)]
     pub consensus: ConfigConsensus,
     #[serde(default)]
     pub logger:    ConfigLogger,
     #[serde(default)]
     pub rocksdb:   ConfigRocksDB,
+    #[serde(default)]
+    pub prune:     ConfigPrune,
 }
 
 impl Config {
@@ -154,4 +246,10 @@ impl Config {
         path_state.push("txs_wal");
         path_state
     }
+
+    pub fn data_path_for_mempool_journal(&self) -> PathBuf {
+        let mut path_state = self.data_path.clone();
+        path_state.push("mempool");
+        path_state
+    }
 }
@@ -1,16 +1,20 @@
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::thread;
 
 use bytes::Bytes;
+use futures::channel::mpsc::Receiver;
+use futures::stream::StreamExt;
 use futures::{future, lock::Mutex};
 #[cfg(unix)]
 use tokio::signal::unix::{self as os_impl};
 
 use common_crypto::{
-    BlsCommonReference, BlsPrivateKey, BlsPublicKey, PublicKey, Secp256k1, Secp256k1PrivateKey,
-    ToPublicKey,
+    BlsCommonReference, BlsPrivateKey, BlsPublicKey, PublicKey, Secp256k1PrivateKey, ToPublicKey,
 };
+use common_pubsub::{ChainEvent, PubSub};
 use core_api::adapter::DefaultAPIAdapter;
 use core_api::config::GraphQLConfig;
 use core_consensus::fixed_types::{FixedBlock, FixedProof, FixedSignedTxs};
@@ -30,18 +34,21 @@ use core_consensus::{
 };
 use core_mempool::{
     DefaultMemPoolAdapter, HashMemPool, MsgPushTxs, NewTxsHandler, PullTxsHandler,
-    PullTxsSyncHandler, END_GOSSIP_NEW_TXS, RPC_PULL_TXS, RPC_PULL_TXS_SYNC, RPC_RESP_PULL_TXS,
-    RPC_RESP_PULL_TXS_SYNC,
+    PullTxsSyncHandler, SenderLimit, SigVerifier, TxJournal, END_GOSSIP_NEW_TXS, RPC_PULL_TXS,
+    RPC_PULL_TXS_SYNC, RPC_RESP_PULL_TXS, RPC_RESP_PULL_TXS_SYNC,
 };
 use core_network::{NetworkConfig, NetworkService};
 use core_storage::{adapter::rocks::RocksAdapter, ImplStorage};
+use framework::admission::admission_controls;
 use framework::binding::state::RocksTrieDB;
 use framework::executor::{ServiceExecutor, ServiceExecutorFactory};
 use protocol::traits::{APIAdapter, Context, MemPool, NodeInfo, ServiceMapping, Storage};
-use protocol::types::{Address, Block, BlockHeader, Genesis, Hash, Metadata, Proof, Validator};
+use protocol::types::{
+    Address, Block, BlockHeader, Genesis, Hash, MerkleRoot, Metadata, Proof, Validator,
+};
 use protocol::{fixed_codec::FixedCodec, ProtocolResult};
 
-use crate::config::Config;
+use crate::config::{Config, ConfigPrune};
 use crate::MainError;
 
 pub async fn create_genesis<Mapping: 'static + ServiceMapping>(
@@ -150,7 +157,9 @@ pub async fn start<Mapping: 'static + ServiceMapping>(
         path_block.clone(),
         config.rocksdb.max_open_files,
     )?);
-    let storage = Arc::new(ImplStorage::new(Arc::clone(&rocks_adapter)));
+    let storage = Arc::new(
+        ImplStorage::new(Arc::clone(&rocks_adapter)).with_keep_blocks(config.prune.keep_blocks),
+    );
 
     // Init network
     let network_config = NetworkConfig::new()
@@ -187,26 +196,50 @@ pub async fn start<Mapping: 'static + ServiceMapping>(
         .listen(config.network.listening_address)
         .await?;
 
+    // Init trie db
+    let path_state = config.data_path_for_state();
+    let mut trie_db = RocksTrieDB::new(
+        path_state,
+        config.executor.light,
+        config.rocksdb.max_open_files,
+    )?;
+    if config.prune.keep_states != 0 {
+        trie_db = trie_db.with_prune()?;
+    }
+    let trie_db = Arc::new(trie_db);
+
     // Init mempool
     let current_block = storage.get_latest_block().await?;
-    let mempool_adapter = DefaultMemPoolAdapter::<Secp256k1, _, _>::new(
+    let sig_verifier = SigVerifier::new(
+        config.mempool.verify_workers,
+        config.mempool.verify_batch_size,
+        config.mempool.verify_queue_size,
+    )?;
+    let mempool_adapter = DefaultMemPoolAdapter::<_, _, _, _, ServiceExecutorFactory>::new(
         network_service.handle(),
         Arc::clone(&storage),
+        Arc::clone(&trie_db),
+        Arc::clone(&service_mapping),
+        sig_verifier.clone(),
         config.mempool.broadcast_txs_size,
         config.mempool.broadcast_txs_interval,
-    );
-    let mempool = Arc::new(HashMemPool::new(
+    )
+    .with_admission_controls(admission_controls(&config.admission));
+    let mut mempool = HashMemPool::new(
         config.mempool.pool_size as usize,
+        config.mempool.package_order,
         mempool_adapter,
-    ));
-
-    // Init trie db
-    let path_state = config.data_path_for_state();
-    let trie_db = Arc::new(RocksTrieDB::new(
-        path_state,
-        config.executor.light,
-        config.rocksdb.max_open_files,
-    )?);
+    )
+    .with_sender_limit(SenderLimit {
+        max_txs:   config.mempool.max_txs_per_sender,
+        max_bytes: config.mempool.max_bytes_per_sender,
+    })
+    .with_price_bump(config.mempool.price_bump);
+    if config.mempool.enable_journal {
+        let journal = TxJournal::new(config.data_path_for_mempool_journal())?;
+        mempool = mempool.with_journal(journal);
+    }
+    let mempool = Arc::new(mempool);
 
     // self private key
     let hex_privkey = hex::decode(config.privkey.as_string_trim0x()).map_err(MainError::FromHex)?;
@@ -216,12 +249,13 @@ pub async fn start<Mapping: 'static + ServiceMapping>(
     let my_address = Address::from_pubkey_bytes(my_pubkey.to_bytes())?;
 
     // Get metadata
-    let api_adapter = DefaultAPIAdapter::<ServiceExecutorFactory, _, _, _, _>::new(
+    let mut api_adapter = DefaultAPIAdapter::<ServiceExecutorFactory, _, _, _, _>::new(
         Arc::clone(&mempool),
         Arc::clone(&storage),
         Arc::clone(&trie_db),
         Arc::clone(&service_mapping),
-    );
+    )
+    .with_keep_states(config.prune.keep_states);
 
     // Create full transactions wal
     let wal_path = config.data_path_for_txs_wal().to_str().unwrap().to_string();
@@ -230,7 +264,7 @@ pub async fn start<Mapping: 'static + ServiceMapping>(
     let exec_resp = api_adapter
         .query_service(
             Context::new(),
-            current_block.header.height,
+            None,
             u64::max_value(),
             1,
             my_address.clone(),
@@ -242,6 +276,7 @@ pub async fn start<Mapping: 'static + ServiceMapping>(
 
     let metadata: Metadata =
         serde_json::from_str(&exec_resp.succeed_data).expect("Decode metadata failed!");
+    check_prune_config(&config.prune, config.executor.light, metadata.timeout_gap)?;
 
     // set args in mempool
     mempool.set_args(
@@ -249,11 +284,12 @@ pub async fn start<Mapping: 'static + ServiceMapping>(
         metadata.cycles_limit,
         metadata.max_tx_size,
     );
+    mempool.reload_journal(Context::new()).await?;
 
     // register broadcast new transaction
     network_service.register_endpoint_handler(
         END_GOSSIP_NEW_TXS,
-        Box::new(NewTxsHandler::new(Arc::clone(&mempool))),
+        Box::new(NewTxsHandler::new(Arc::clone(&mempool)).with_sig_verifier(sig_verifier)),
     )?;
 
     // register pull txs from other node
@@ -346,6 +382,9 @@ pub async fn start<Mapping: 'static + ServiceMapping>(
 
     let crypto = Arc::new(OverlordCrypto::new(bls_priv_key, bls_pub_keys, common_ref));
 
+    // Committed blocks and receipts are published to graphql subscriptions
+    let event_bus = Arc::new(PubSub::default());
+
     let mut consensus_adapter =
         OverlordConsensusAdapter::<ServiceExecutorFactory, _, _, _, _, _, _>::new(
             Arc::new(network_service.handle()),
@@ -356,6 +395,7 @@ pub async fn start<Mapping: 'static + ServiceMapping>(
             Arc::clone(&service_mapping),
             status_agent.clone(),
             Arc::clone(&crypto),
+            Arc::clone(&event_bus),
         )?;
 
     let exec_demon = consensus_adapter.take_exec_demon();
@@ -382,6 +422,27 @@ pub async fn start<Mapping: 'static + ServiceMapping>(
         lock,
     ));
 
+    api_adapter.set_node_status(
+        my_address.clone(),
+        Arc::new(network_service.handle()),
+        Arc::clone(&synchronization),
+    );
+
+    // References of state nodes are counted on the first run with pruning,
+    // before any state is written.
+    if config.prune.keep_states != 0 {
+        let roots = kept_state_roots(
+            &*storage,
+            &status_agent,
+            current_height,
+            config.prune.keep_states,
+        )
+        .await?;
+        log::info!("[prune] init state pruning");
+        let deleted = trie_db.init_prune(&roots)?;
+        log::info!("[prune] delete {} state nodes on init", deleted);
+    }
+
     // Re-execute block from exec_height + 1 to current_height, so that init the
     // lost current status.
     log::info!("Re-execute from {} to {}", exec_height + 1, current_height);
@@ -485,11 +546,27 @@ pub async fn start<Mapping: 'static + ServiceMapping>(
     let (abortable_demon, abort_handle) = future::abortable(exec_demon.run());
     tokio::task::spawn_local(abortable_demon);
 
+    // Run state pruning
+    if config.prune.keep_states != 0 {
+        tokio::spawn(prune_states(
+            event_bus.subscribe(),
+            Arc::clone(&storage),
+            Arc::clone(&trie_db),
+            status_agent.clone(),
+            config.prune.keep_states,
+            config.prune.interval,
+        ));
+    }
+
     // Init graphql
     let mut graphql_config = GraphQLConfig::default();
     graphql_config.listening_address = config.graphql.listening_address;
     graphql_config.graphql_uri = config.graphql.graphql_uri.clone();
     graphql_config.graphiql_uri = config.graphql.graphiql_uri.clone();
+    if !config.graphql.subscription_uri.is_empty() {
+        graphql_config.subscription_uri = config.graphql.subscription_uri.clone();
+    }
+    graphql_config.jsonrpc_uri = config.graphql.jsonrpc_uri.clone();
     if config.graphql.workers != 0 {
         graphql_config.workers = config.graphql.workers;
     }
@@ -499,13 +576,34 @@ pub async fn start<Mapping: 'static + ServiceMapping>(
     if config.graphql.max_payload_size != 0 {
         graphql_config.max_payload_size = config.graphql.max_payload_size;
     }
+    if let Some(api_keys) = &config.graphql.api_keys {
+        graphql_config.api_keys = api_keys.clone();
+    }
+    if let Some(enable_graphiql) = config.graphql.enable_graphiql {
+        graphql_config.enable_graphiql = enable_graphiql;
+    }
+    if let Some(enable_unsafe_api) = config.graphql.enable_unsafe_api {
+        graphql_config.enable_unsafe_api = enable_unsafe_api;
+    }
+    if let Some(rate_limit) = config.graphql.rate_limit {
+        graphql_config.rate_limit = rate_limit;
+    }
+    if let Some(rate_limit_burst) = config.graphql.rate_limit_burst {
+        graphql_config.rate_limit_burst = rate_limit_burst;
+    }
+    if let Some(max_query_depth) = config.graphql.max_query_depth {
+        graphql_config.max_query_depth = max_query_depth;
+    }
+    if let Some(max_query_complexity) = config.graphql.max_query_complexity {
+        graphql_config.max_query_complexity = max_query_complexity;
+    }
 
     tokio::task::spawn_local(async move {
         let local = tokio::task::LocalSet::new();
         let actix_rt = actix_rt::System::run_in_tokio("muta-graphql", &local);
         tokio::task::spawn_local(actix_rt);
 
-        core_api::start_graphql(graphql_config, api_adapter).await;
+        core_api::start_graphql(graphql_config, api_adapter, event_bus).await;
     });
 
     #[cfg(windows)]
@@ -525,3 +623,114 @@ pub async fn start<Mapping: 'static + ServiceMapping>(
 
     Ok(())
 }
+
+// Blocks within the timeout gap are checked for duplicate transactions, so
+// pruning them would let transactions be replayed.
+fn check_prune_config(config: &ConfigPrune, light: bool, timeout_gap: u64) -> ProtocolResult<()> {
+    if config.keep_blocks != 0 && config.keep_blocks <= timeout_gap {
+        return Err(MainError::Other(format!(
+            "prune.keep_blocks {} must be more than the timeout gap {}",
+            config.keep_blocks, timeout_gap
+        ))
+        .into());
+    }
+
+    if config.keep_states != 0 {
+        if config.interval == 0 {
+            return Err(MainError::Other("prune.interval must not be 0".to_owned()).into());
+        }
+        // Nodes removed by the light executor are not counted off their
+        // children, which may then be deleted while still referenced.
+        if light {
+            return Err(MainError::Other(
+                "prune.keep_states must be 0 if executor.light is set".to_owned(),
+            )
+            .into());
+        }
+        // Roots of the kept states are read from blocks and receipts.
+        if config.keep_blocks != 0 && config.keep_states > config.keep_blocks {
+            return Err(MainError::Other(format!(
+                "prune.keep_states {} must not be more than prune.keep_blocks {}",
+                config.keep_states, config.keep_blocks
+            ))
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+// Drop the states older than the latest `keep_states` ones every `interval`
+// blocks, only the nodes no longer referenced are visited. Nodes are deleted
+// on a thread of its own, and a round is skipped if the last one is still
+// running.
+async fn prune_states<S: Storage>(
+    mut events: Receiver<ChainEvent>,
+    storage: Arc<S>,
+    trie_db: Arc<RocksTrieDB>,
+    status_agent: StatusAgent,
+    keep_states: u64,
+    interval: u64,
+) {
+    let running = Arc::new(AtomicBool::new(false));
+
+    while let Some(event) = events.next().await {
+        let height = match event {
+            ChainEvent::NewBlock(block) => block.header.height,
+            _ => continue,
+        };
+        if height % interval != 0 || running.load(Ordering::SeqCst) {
+            continue;
+        }
+
+        let roots = match kept_state_roots(&*storage, &status_agent, height, keep_states).await {
+            Ok(roots) => roots,
+            Err(e) => {
+                log::error!("[prune] get state roots at height {} error {:?}", height, e);
+                continue;
+            }
+        };
+
+        running.store(true, Ordering::SeqCst);
+        let running = Arc::clone(&running);
+        let trie_db = Arc::clone(&trie_db);
+        thread::spawn(move || {
+            match trie_db.prune(&roots) {
+                Ok(count) => {
+                    log::info!("[prune] delete {} state nodes at height {}", count, height)
+                }
+                Err(e) => log::error!("[prune] prune states error {:?}", e),
+            }
+            running.store(false, Ordering::SeqCst);
+        });
+    }
+}
+
+// The state after executing a block is found in its receipts or the header
+// of the block which executed it, see `DefaultAPIAdapter::get_state_root`.
+// States executed but not recorded by a header yet are kept as well, so that
+// empty blocks have their states once recorded. Blocks not executed yet have
+// no receipts, their states are not committed either.
+async fn kept_state_roots<S: Storage>(
+    storage: &S,
+    status_agent: &StatusAgent,
+    latest_height: u64,
+    keep_states: u64,
+) -> ProtocolResult<Vec<MerkleRoot>> {
+    let mut roots = status_agent.to_inner().list_state_root;
+    roots.push(status_agent.get_latest_state_root());
+
+    for height in latest_height.saturating_sub(keep_states) + 1..=latest_height {
+        let block = storage.get_block_by_height(height).await?;
+        roots.push(block.header.state_root.clone());
+
+        if let Some(tx_hash) = block.ordered_tx_hashes.first() {
+            if let Ok(receipt) = storage.get_receipt(tx_hash.clone()).await {
+                roots.push(receipt.state_root);
+            }
+        }
+    }
+    roots.dedup();
+
+    Ok(roots)
+}